@@ -0,0 +1,91 @@
+//! Compares the ring's enqueue and dequeue cost across topologies.
+//!
+//! Run with `cargo bench --bench ring_topology`. Prints nanoseconds per item
+//! for a thread enqueuing and dequeuing on its own (the cost of the claims
+//! themselves), and for one producer thread feeding one consumer thread.
+
+use std::hint::black_box;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use open_rust_timeseries_db::memory::low_latency_mpmc_ring::{LowLatencyMpmcRing, Topology};
+
+const CAPACITY: usize = 1024;
+const ROUND_TRIPS: u64 = 10_000_000;
+const BATCH: usize = 32;
+const HANDOFFS: u64 = 5_000_000;
+const TOPOLOGIES: [Topology; 4] = [Topology::Mpmc, Topology::Mpsc, Topology::Spmc, Topology::Spsc];
+
+fn ring(topology: Topology) -> LowLatencyMpmcRing<u64> {
+    // SAFETY: every benchmark uses at most one producer and one consumer thread
+    unsafe { LowLatencyMpmcRing::new(CAPACITY).with_topology(topology) }
+}
+
+fn per_item(elapsed: Duration, items: u64) -> f64 {
+    elapsed.as_nanos() as f64 / items as f64
+}
+
+// Enqueue then dequeue one item at a time on a single thread
+fn round_trip(topology: Topology) -> f64 {
+    let ring = ring(topology);
+    let start = Instant::now();
+    for i in 0..ROUND_TRIPS {
+        ring.try_enqueue(black_box(i)).unwrap();
+        black_box(ring.try_dequeue().unwrap());
+    }
+    per_item(start.elapsed(), ROUND_TRIPS)
+}
+
+// Enqueue then dequeue BATCH items at a time on a single thread
+fn batch_round_trip(topology: Topology) -> f64 {
+    let ring = ring(topology);
+    let mut out = Vec::with_capacity(BATCH);
+    let start = Instant::now();
+    for i in 0..ROUND_TRIPS / BATCH as u64 {
+        let first = i as usize * BATCH;
+        ring.try_enqueue_batch(&mut (first..first + BATCH).map(|item| item as u64));
+        ring.try_dequeue_batch(&mut out, BATCH);
+        black_box(&out);
+        out.clear();
+    }
+    per_item(start.elapsed(), ROUND_TRIPS)
+}
+
+// One producer thread hands items to one consumer thread
+fn handoff(topology: Topology) -> f64 {
+    let ring = ring(topology);
+    let start = Instant::now();
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            for i in 0..HANDOFFS {
+                while ring.try_enqueue(i).is_err() {
+                    thread::yield_now();
+                }
+            }
+        });
+        let mut received = 0;
+        while received < HANDOFFS {
+            match ring.try_dequeue().unwrap() {
+                Some(item) => {
+                    black_box(item);
+                    received += 1;
+                }
+                None => thread::yield_now(),
+            }
+        }
+    });
+    per_item(start.elapsed(), HANDOFFS)
+}
+
+fn main() {
+    println!("{:<8} {:>14} {:>14} {:>14}", "topology", "round trip", "batch of 32", "two threads");
+    for topology in TOPOLOGIES {
+        println!(
+            "{:<8} {:>11.1} ns {:>11.1} ns {:>11.1} ns",
+            format!("{:?}", topology),
+            round_trip(topology),
+            batch_round_trip(topology),
+            handoff(topology),
+        );
+    }
+}
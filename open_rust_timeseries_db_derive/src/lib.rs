@@ -0,0 +1,190 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr, Type};
+
+/// A struct field mapped onto a table column.
+struct ColumnField {
+    ident: syn::Ident,
+    ty: Type,
+    name: String,
+    timestamp: bool,
+    big_endian: bool,
+}
+
+/// Derive `TableRecord` for a struct with named fields.
+///
+/// Field attributes:
+/// - `#[table(rename = "name")]` stores the field under a different column name
+/// - `#[table(timestamp)]` declares a `u64` field as `timestamp_ns`
+/// - `#[table(big_endian)]` stores a numeric field big-endian
+///
+/// `Option<T>` fields are nullable, with `None` stored as a null.
+#[proc_macro_derive(TableRecord, attributes(table))]
+pub fn derive_table_record(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let named = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(named) => &named.named,
+            _ => return Err(syn::Error::new_spanned(&input.ident, "TableRecord requires named fields")),
+        },
+        _ => return Err(syn::Error::new_spanned(&input.ident, "TableRecord can only be derived for structs")),
+    };
+
+    let mut columns = Vec::with_capacity(named.len());
+    for field in named {
+        columns.push(parse_field(field)?);
+    }
+    if columns.is_empty() {
+        return Err(syn::Error::new_spanned(&input.ident, "TableRecord requires at least one field"));
+    }
+
+    // Tables store columns sorted by name, so encode in that order
+    columns.sort_by(|a, b| a.name.cmp(&b.name));
+    for pair in columns.windows(2) {
+        if pair[0].name == pair[1].name {
+            return Err(syn::Error::new_spanned(&pair[1].ident, "duplicate column name"));
+        }
+    }
+
+    let krate = quote!(::open_rust_timeseries_db);
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let column_count = columns.len();
+
+    let configs = columns.iter().map(|column| {
+        let name = &column.name;
+        let ty = &column.ty;
+        let data_type = if column.timestamp {
+            quote!(#krate::storage::schema::DataType::TimestampNs)
+        } else {
+            quote!(<#ty as #krate::storage::table_record::ColumnType>::DATA_TYPE)
+        };
+        let endianness = if column.big_endian {
+            quote!(#krate::storage::schema::Endianness::Big)
+        } else {
+            quote!(#krate::storage::schema::Endianness::Little)
+        };
+        quote! {
+            fields.insert(
+                #name,
+                #krate::storage::table::FieldConfig::new(#data_type, ring_capacity)
+                    .with_endianness(#endianness)
+                    .with_nullable(<#ty as #krate::storage::table_record::ColumnType>::NULLABLE),
+            );
+        }
+    });
+
+    let name_checks = columns.iter().enumerate().map(|(idx, column)| {
+        let name = &column.name;
+        quote! {
+            if columns.get(#idx).map(|column| &*column.name) != ::std::option::Option::Some(#name) {
+                return ::std::result::Result::Err(
+                    #krate::error::TableError::UnknownField(::std::string::String::from(#name)),
+                );
+            }
+        }
+    });
+
+    let timestamp_checks = columns.iter().filter(|column| column.timestamp).map(|column| {
+        let field = &column.ident;
+        quote!(__timestamp_is_u64(&self.#field);)
+    });
+
+    let encoded = columns.iter().enumerate().map(|(idx, column)| {
+        let field = &column.ident;
+        quote! {
+            let endianness = #krate::storage::table_record::FieldWriter::columns(writer)[#idx].config.endianness;
+            #krate::storage::table_record::ColumnType::encode_field(
+                &self.#field,
+                endianness,
+                |value| #krate::storage::table_record::FieldWriter::put(writer, value),
+            )?;
+        }
+    });
+
+    let decoded = columns.iter().enumerate().map(|(idx, column)| {
+        let field = &column.ident;
+        let name = &column.name;
+        quote! {
+            #field: {
+                let (column, value) = #krate::storage::table_record::FieldReader::field(reader, #idx, #name)?;
+                #krate::storage::table_record::ColumnType::decode_field(column, value)?
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics #krate::storage::table_record::TableRecord for #ident #ty_generics #where_clause {
+            fn table_config(ring_capacity: usize) -> #krate::storage::table::TableConfig {
+                let mut fields = ::std::collections::HashMap::with_capacity(#column_count);
+                #(#configs)*
+                #krate::storage::table::TableConfig::new(fields)
+            }
+
+            #[inline(always)]
+            fn encode<W: #krate::storage::table_record::FieldWriter>(
+                &self,
+                writer: &mut W,
+            ) -> ::std::result::Result<(), #krate::error::TableError> {
+                #[allow(dead_code)]
+                fn __timestamp_is_u64(_: &u64) {}
+                #(#timestamp_checks)*
+
+                let columns = #krate::storage::table_record::FieldWriter::columns(writer);
+                #(#name_checks)*
+                if let ::std::option::Option::Some(column) = columns.get(#column_count) {
+                    return ::std::result::Result::Err(
+                        #krate::error::TableError::MissingField(::std::string::String::from(&*column.name)),
+                    );
+                }
+                #(#encoded)*
+                ::std::result::Result::Ok(())
+            }
+
+            #[inline(always)]
+            fn decode<R: #krate::storage::table_record::FieldReader>(
+                reader: &R,
+            ) -> ::std::result::Result<Self, #krate::error::TableError> {
+                ::std::result::Result::Ok(Self {
+                    #(#decoded),*
+                })
+            }
+        }
+    })
+}
+
+fn parse_field(field: &syn::Field) -> syn::Result<ColumnField> {
+    let ident = field.ident.clone().expect("named field");
+    let mut column = ColumnField {
+        name: ident.to_string().trim_start_matches("r#").to_owned(),
+        ident,
+        ty: field.ty.clone(),
+        timestamp: false,
+        big_endian: false,
+    };
+
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("table")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("timestamp") {
+                column.timestamp = true;
+            } else if meta.path.is_ident("big_endian") {
+                column.big_endian = true;
+            } else if meta.path.is_ident("rename") {
+                let name: LitStr = meta.value()?.parse()?;
+                column.name = name.value();
+            } else {
+                return Err(meta.error("unsupported table attribute"));
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(column)
+}
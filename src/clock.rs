@@ -0,0 +1,51 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Source of wall-clock time for stamping rows.
+pub trait Clock: Send + Sync {
+    /// Nanoseconds since the Unix epoch.
+    fn now_ns(&self) -> u64;
+}
+
+/// The operating system's realtime clock.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[inline(always)]
+    fn now_ns(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+    }
+}
+
+/// A clock that only moves when told to, for tests and replays.
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    now_ns: AtomicU64,
+}
+
+impl ManualClock {
+    #[inline(always)]
+    pub fn new(now_ns: u64) -> Self {
+        Self { now_ns: AtomicU64::new(now_ns) }
+    }
+
+    #[inline(always)]
+    pub fn set(&self, now_ns: u64) {
+        self.now_ns.store(now_ns, Ordering::Release);
+    }
+
+    #[inline(always)]
+    pub fn advance(&self, delta_ns: u64) {
+        self.now_ns.fetch_add(delta_ns, Ordering::AcqRel);
+    }
+}
+
+impl Clock for ManualClock {
+    #[inline(always)]
+    fn now_ns(&self) -> u64 {
+        self.now_ns.load(Ordering::Acquire)
+    }
+}
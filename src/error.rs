@@ -0,0 +1,99 @@
+use std::fmt;
+use std::io;
+
+use crate::storage::schema::DataType;
+
+/// Why a ring or table operation did not complete.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TableError {
+    /// No free slot right now; back off and retry
+    Full,
+    /// The record names a field the table does not have
+    UnknownField(String),
+    /// The record leaves out a field the table requires
+    MissingField(String),
+    /// A field read as a value is null in this row
+    Null(String),
+    /// A value's length does not fit its field
+    SizeMismatch { field: String, expected: usize, actual: usize },
+    /// A value is not a valid encoding of its field's type, or was read back
+    /// as a type the field cannot hold
+    TypeMismatch { field: String, expected: DataType },
+    /// The ring or table has been shut down
+    Closed,
+    /// Only the first `written` records of a batch were stored
+    PartialWrite { written: usize },
+    /// A time-based operation on a table without a timestamp column
+    NoTimeColumn,
+    /// Every consumer group slot is already in use
+    TooManyGroups,
+    /// Reading or writing persisted state failed
+    Io(String),
+    /// Persisted state failed its checksum or does not match the schema
+    Corrupt(String),
+    /// Persisted state was written for a different schema
+    SchemaMismatch(String),
+    /// A schema or config cannot describe a table: no fields, a field name
+    /// that is empty or repeated, a time column that is not timestamp_ns, or
+    /// a width or default that does not fit its field
+    InvalidSchema(String),
+    /// A database already has a table of this name
+    TableExists(String),
+    /// A database has no table of this name
+    NoSuchTable(String),
+    /// A table or shared memory name is empty or cannot name a file
+    InvalidName(String),
+    /// Creating a table would reserve more than the database's memory budget
+    OutOfMemory { requested: usize, available: usize },
+    /// A broadcast reader fell a lap behind and `missed` items were
+    /// overwritten before it read them
+    Lagged { missed: usize },
+}
+
+impl TableError {
+    /// Whether the operation may succeed if retried later unchanged.
+    #[inline(always)]
+    pub fn is_backpressure(&self) -> bool {
+        matches!(self, TableError::Full | TableError::PartialWrite { .. })
+    }
+}
+
+impl fmt::Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableError::Full => write!(f, "buffer is full"),
+            TableError::UnknownField(field) => write!(f, "unknown field `{}`", field),
+            TableError::MissingField(field) => write!(f, "missing field `{}`", field),
+            TableError::Null(field) => write!(f, "field `{}` is null", field),
+            TableError::SizeMismatch { field, expected, actual } => {
+                write!(f, "field `{}` expects {} bytes, got {}", field, expected, actual)
+            }
+            TableError::TypeMismatch { field, expected } => {
+                write!(f, "field `{}` is not a valid {:?}", field, expected)
+            }
+            TableError::Closed => write!(f, "closed"),
+            TableError::PartialWrite { written } => write!(f, "only {} records written", written),
+            TableError::NoTimeColumn => write!(f, "table has no timestamp_ns column"),
+            TableError::TooManyGroups => write!(f, "too many consumer groups"),
+            TableError::Io(message) => write!(f, "i/o error: {}", message),
+            TableError::Corrupt(message) => write!(f, "corrupt data: {}", message),
+            TableError::SchemaMismatch(message) => write!(f, "schema mismatch: {}", message),
+            TableError::InvalidSchema(message) => write!(f, "invalid schema: {}", message),
+            TableError::TableExists(table) => write!(f, "table `{}` already exists", table),
+            TableError::NoSuchTable(table) => write!(f, "no table `{}`", table),
+            TableError::InvalidName(name) => write!(f, "invalid name `{}`", name),
+            TableError::OutOfMemory { requested, available } => {
+                write!(f, "table needs {} bytes, {} left in the memory budget", requested, available)
+            }
+            TableError::Lagged { missed } => write!(f, "reader lagged, {} items overwritten", missed),
+        }
+    }
+}
+
+impl std::error::Error for TableError {}
+
+impl From<io::Error> for TableError {
+    fn from(err: io::Error) -> Self {
+        TableError::Io(err.to_string())
+    }
+}
@@ -0,0 +1,239 @@
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::memory::counters::shard;
+
+const SUB_BUCKET_BITS: u32 = 5;
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;  // Per power of two, so values are kept to within 1/32
+const MAX_VALUE_BITS: u32 = 36;  // Values from 2^36 (about 69 s in ns) up share the top bucket
+const BUCKETS: usize = (MAX_VALUE_BITS - SUB_BUCKET_BITS + 1) as usize * SUB_BUCKETS;
+const SHARDS: usize = 8;
+
+// One thread's calls since it last took a sample, on its own cache line
+#[repr(align(64))]
+struct Ticks(AtomicU32);
+
+/// Picks one call in every `one_in` each thread makes to timing, counting
+/// calls separately from every other sampler. Threads beyond `SHARDS` share
+/// a count, and may then race on it and sample a little more or less often.
+pub(crate) struct Sampler {
+    one_in: u32,
+    ticks: Box<[Ticks]>,
+}
+
+impl Sampler {
+    pub(crate) fn new(one_in: u32) -> Self {
+        Self { one_in, ticks: (0..SHARDS).map(|_| Ticks(AtomicU32::new(0))).collect() }
+    }
+
+    /// Whether the calling thread should time this call.
+    #[inline(always)]
+    pub(crate) fn sample(&self) -> bool {
+        let ticks = &self.ticks[shard() % SHARDS].0;
+        let tick = ticks.load(Ordering::Relaxed) + 1;
+        let sampled = tick >= self.one_in;
+        ticks.store(if sampled { 0 } else { tick }, Ordering::Relaxed);
+        sampled
+    }
+}
+
+// Bucket holding `value`: exact below 2 * SUB_BUCKETS, then SUB_BUCKETS
+// equal buckets per power of two
+#[inline(always)]
+fn bucket(value: u64) -> usize {
+    let value = value.min((1 << MAX_VALUE_BITS) - 1);
+    if value < SUB_BUCKETS as u64 {
+        return value as usize;
+    }
+    let shift = 63 - value.leading_zeros() - SUB_BUCKET_BITS;
+    (shift as usize + 1) * SUB_BUCKETS + (value >> shift) as usize - SUB_BUCKETS
+}
+
+// Largest value that lands in bucket `index`
+#[inline(always)]
+fn highest_in(index: usize) -> u64 {
+    if index < SUB_BUCKETS {
+        return index as u64;
+    }
+    let shift = index / SUB_BUCKETS - 1;
+    let lowest = ((SUB_BUCKETS + index % SUB_BUCKETS) as u64) << shift;
+    lowest + (1 << shift) - 1
+}
+
+// One thread's share of the histogram
+#[repr(align(64))]
+struct Shard {
+    sum: AtomicU64,
+    min: AtomicU64,
+    max: AtomicU64,
+    counts: Box<[AtomicU64]>,
+}
+
+/// HDR-style histogram of latencies in nanoseconds, or any other `u64`
+/// values, recorded from many threads at once without locks.
+///
+/// Buckets are log-linear: every power of two is split into 32 equal
+/// buckets, so a percentile is within about 3% of the true value, in a
+/// fixed 8 KiB per shard whatever the range. Each thread records into its own
+/// shard with relaxed atomic adds; `snapshot` sums the shards.
+pub struct Histogram {
+    shards: Box<[Shard]>,
+}
+
+impl Histogram {
+    pub fn new() -> Self {
+        Self {
+            shards: (0..SHARDS).map(|_| Shard {
+                sum: AtomicU64::new(0),
+                min: AtomicU64::new(u64::MAX),
+                max: AtomicU64::new(0),
+                counts: (0..BUCKETS).map(|_| AtomicU64::new(0)).collect(),
+            }).collect(),
+        }
+    }
+
+    #[inline(always)]
+    pub fn record(&self, value: u64) {
+        let shard = &self.shards[shard() % SHARDS];
+        shard.counts[bucket(value)].fetch_add(1, Ordering::Relaxed);
+        shard.sum.fetch_add(value, Ordering::Relaxed);
+        if value < shard.min.load(Ordering::Relaxed) {
+            shard.min.fetch_min(value, Ordering::Relaxed);
+        }
+        if value > shard.max.load(Ordering::Relaxed) {
+            shard.max.fetch_max(value, Ordering::Relaxed);
+        }
+    }
+
+    /// Record `duration` in nanoseconds.
+    #[inline(always)]
+    pub fn record_duration(&self, duration: Duration) {
+        self.record(u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX));
+    }
+
+    /// Copy the recorded values out. Values recorded while the copy is taken
+    /// may be partly included, for example in the count but not the sum.
+    pub fn snapshot(&self) -> HistogramSnapshot {
+        let mut snapshot = HistogramSnapshot::default();
+        for shard in self.shards.iter() {
+            for (total, count) in snapshot.counts.iter_mut().zip(shard.counts.iter()) {
+                *total += count.load(Ordering::Relaxed);
+            }
+            snapshot.sum = snapshot.sum.wrapping_add(shard.sum.load(Ordering::Relaxed));
+            snapshot.min = snapshot.min.min(shard.min.load(Ordering::Relaxed));
+            snapshot.max = snapshot.max.max(shard.max.load(Ordering::Relaxed));
+        }
+        snapshot.count = snapshot.counts.iter().sum();
+        snapshot
+    }
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Recorded values of a `Histogram` at one point, for percentile queries.
+/// Snapshots of several histograms, say one per table or per process, merge
+/// into one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HistogramSnapshot {
+    counts: Box<[u64]>,
+    count: u64,
+    sum: u64,
+    min: u64,
+    max: u64,
+}
+
+impl Default for HistogramSnapshot {
+    fn default() -> Self {
+        Self {
+            counts: vec![0; BUCKETS].into_boxed_slice(),
+            count: 0,
+            sum: 0,
+            min: u64::MAX,
+            max: 0,
+        }
+    }
+}
+
+impl HistogramSnapshot {
+    /// Add `other`'s values to this snapshot.
+    pub fn merge(&mut self, other: &HistogramSnapshot) {
+        for (total, count) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *total += count;
+        }
+        self.count += other.count;
+        self.sum = self.sum.saturating_add(other.sum);
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    /// Value at or below which `percentile` percent of the recorded values
+    /// fall, as the top of the bucket holding it, capped at the largest value
+    /// recorded. 0 if nothing was recorded.
+    pub fn value_at_percentile(&self, percentile: f64) -> u64 {
+        if self.count == 0 {
+            return 0;
+        }
+        let rank = ((percentile.clamp(0.0, 100.0) / 100.0) * self.count as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (index, &count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                // Not `clamp`: a snapshot racing a record may see a count before its min
+                return highest_in(index).min(self.max).max(self.min);
+            }
+        }
+        self.max
+    }
+
+    #[inline(always)]
+    pub fn p50(&self) -> u64 {
+        self.value_at_percentile(50.0)
+    }
+
+    #[inline(always)]
+    pub fn p99(&self) -> u64 {
+        self.value_at_percentile(99.0)
+    }
+
+    #[inline(always)]
+    pub fn p999(&self) -> u64 {
+        self.value_at_percentile(99.9)
+    }
+
+    #[inline(always)]
+    pub fn p9999(&self) -> u64 {
+        self.value_at_percentile(99.99)
+    }
+
+    #[inline(always)]
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Smallest value recorded, exactly; 0 if nothing was recorded.
+    #[inline(always)]
+    pub fn min(&self) -> u64 {
+        if self.count == 0 { 0 } else { self.min }
+    }
+
+    /// Largest value recorded, exactly.
+    #[inline(always)]
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    /// Mean of the recorded values, exactly; 0 if nothing was recorded.
+    #[inline(always)]
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 { 0.0 } else { self.sum as f64 / self.count as f64 }
+    }
+}
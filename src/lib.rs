@@ -1,5 +1,11 @@
+// Lets `#[derive(TableRecord)]` expand to absolute paths inside this crate too
+extern crate self as open_rust_timeseries_db;
+
+pub mod clock;
+pub mod error;
+pub mod histogram;
 pub mod memory;
 pub mod storage;
 
 #[cfg(test)]
-mod tests; 
\ No newline at end of file
+mod tests;
\ No newline at end of file
@@ -0,0 +1,354 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::error::TableError;
+use crate::memory::low_latency_mpmc_ring::{Cursor, INACTIVE, MAX_CURSORS, OverflowPolicy, Slot};
+use crate::memory::wait_strategy::{SpinThenYield, WaitStrategy};
+
+const SPIN_LIMIT: u32 = 6;  // Spins before yielding while another producer finishes a lap
+
+/// Disruptor-style ring where every reader sees every item.
+///
+/// Producers claim sequence numbers and publish each item once, with the
+/// slot sequence protocol of `LowLatencyMpmcRing`: a slot's sequence reads
+/// `seq + 1` once the item for `seq` is published, and `seq` while a
+/// producer is writing it. Readers never take items out. Each one has a
+/// gating sequence, the next item it will read, and clones items as it
+/// passes over them, pinning the slot so a producer cannot replace the item
+/// mid-copy.
+///
+/// The overflow policy decides what happens when the slowest reader is a
+/// whole lap behind. `Reject` fails the publish with `Full` and `Block`
+/// waits for the reader, so both gate producers. `Overwrite` and
+/// `DropOldest` publish anyway; the lapped reader's next read fails with
+/// `Lagged`, reporting how many items it missed, and it resumes at the
+/// oldest item still held.
+#[repr(align(64))]
+pub struct BroadcastRing<T> {
+    buffer: Box<[Slot<T>]>,
+    capacity: usize,
+    mask: usize,
+    producer_index: AtomicUsize,  // Next sequence to claim
+    readers: Box<[Cursor]>,
+    policy: OverflowPolicy,
+    closed: AtomicBool,
+    wait: Arc<dyn WaitStrategy>,
+}
+
+// Thread safety implementations
+unsafe impl<T: Send> Send for BroadcastRing<T> {}
+unsafe impl<T: Send + Sync> Sync for BroadcastRing<T> {}
+
+impl<T> BroadcastRing<T> {
+    #[inline(always)]
+    pub fn new(capacity: usize) -> Self {
+        Self::with_policy(capacity, OverflowPolicy::Reject)
+    }
+
+    pub fn with_policy(capacity: usize, policy: OverflowPolicy) -> Self {
+        assert!(capacity.is_power_of_two(), "Capacity must be a power of 2");
+        // With one slot, "being written for seq + 1" and "published for seq" collide
+        assert!(capacity >= 2, "Broadcast rings need a capacity of at least 2");
+
+        Self {
+            buffer: (0..capacity).map(Slot::new).collect(),
+            capacity,
+            mask: capacity - 1,
+            producer_index: AtomicUsize::new(0),
+            readers: (0..MAX_CURSORS).map(|_| Cursor::inactive()).collect(),
+            policy,
+            closed: AtomicBool::new(false),
+            wait: Arc::new(SpinThenYield::default()),
+        }
+    }
+
+    /// How blocked producers and `BroadcastReader::read_timeout` wait.
+    /// Defaults to `SpinThenYield`.
+    #[inline(always)]
+    pub fn with_wait_strategy(mut self, wait: Arc<dyn WaitStrategy>) -> Self {
+        self.wait = wait;
+        self
+    }
+
+    /// Register a reader that sees every item published from now on.
+    /// Returns None when all `MAX_CURSORS` readers are in use.
+    pub fn subscribe(&self) -> Option<BroadcastReader<'_, T>> {
+        let start = self.producer_index.load(Ordering::SeqCst);
+        let id = self.readers.iter().position(|reader| {
+            reader.position.compare_exchange(INACTIVE, start, Ordering::SeqCst, Ordering::Relaxed).is_ok()
+        })?;
+        let reader = &self.readers[id];
+        reader.skipped.store(0, Ordering::Relaxed);
+        // Producers that claimed before seeing our gate may have lapped `start`;
+        // everything claimed from here on is gated by it
+        reader.position.store(self.producer_index.load(Ordering::SeqCst), Ordering::SeqCst);
+        Some(BroadcastReader { ring: self, id })
+    }
+
+    /// Publish `item` to every reader. Returns its sequence number, or fails
+    /// with `Full` if the slowest reader is a lap behind under `Reject` (or
+    /// stays behind past the timeout under `Block`), and with `Closed` once
+    /// the ring is closed.
+    #[inline(always)]
+    pub fn publish(&self, item: T) -> Result<usize, TableError> {
+        self.publish_with(move |_| item)
+    }
+
+    /// Like `publish`, building the item from its sequence number once a
+    /// slot is claimed.
+    pub fn publish_with(&self, make: impl FnOnce(usize) -> T) -> Result<usize, TableError> {
+        if self.is_closed() {
+            return Err(TableError::Closed);
+        }
+        match self.policy {
+            OverflowPolicy::Block { timeout } => {
+                self.publish_until(make, timeout.map(|timeout| Instant::now() + timeout))
+            }
+            _ => self.try_claim(make).map_err(|_| TableError::Full),
+        }
+    }
+
+    fn publish_until<F: FnOnce(usize) -> T>(&self, mut make: F, deadline: Option<Instant>) -> Result<usize, TableError> {
+        loop {
+            make = match self.try_claim(make) {
+                Ok(seq) => return Ok(seq),
+                Err(make) => make,
+            };
+            if self.is_closed() {
+                return Err(TableError::Closed);
+            }
+            if !self.wait.wait_until(&|| !self.is_gated() || self.is_closed(), deadline) {
+                return Err(TableError::Full);
+            }
+        }
+    }
+
+    // Claim the next sequence and write the item, unless a reader gates it
+    fn try_claim<F: FnOnce(usize) -> T>(&self, make: F) -> Result<usize, F> {
+        let mut spin_count = 0;
+        loop {
+            let seq = self.producer_index.load(Ordering::SeqCst);
+            let slot = unsafe { self.buffer.get_unchecked(seq & self.mask) };
+            let slot_seq = slot.sequence.load(Ordering::Acquire);
+            // Free once the item one lap back is published; the first lap starts free
+            let free = if seq >= self.capacity { seq - self.capacity + 1 } else { seq };
+
+            if slot_seq == free {
+                if self.gates() && seq.saturating_sub(self.gate()) >= self.capacity {
+                    return Err(make);
+                }
+                if self.producer_index.compare_exchange_weak(
+                    seq, seq.wrapping_add(1),
+                    Ordering::SeqCst, Ordering::Relaxed
+                ).is_ok() {
+                    self.write(slot, seq, make(seq));
+                    return Ok(seq);
+                }
+            } else if slot_seq < free {
+                // The producer one lap back has not published yet
+                spin_count += 1;
+                if spin_count > SPIN_LIMIT {
+                    std::thread::yield_now();
+                    spin_count = 0;
+                } else {
+                    std::hint::spin_loop();
+                }
+            }
+            // Otherwise another producer claimed `seq` first
+        }
+    }
+
+    #[inline(always)]
+    fn write(&self, slot: &Slot<T>, seq: usize, item: T) {
+        // Marks the item being replaced as gone, so lapped readers stop pinning it
+        slot.sequence.store(seq, Ordering::SeqCst);
+        slot.wait_for_readers();
+        unsafe {
+            let value = &mut *slot.value.get();
+            if seq >= self.capacity {
+                value.assume_init_drop();
+            }
+            value.write(item);
+        }
+        slot.sequence.store(seq.wrapping_add(1), Ordering::Release);
+        self.wait.notify();
+    }
+
+    // Whether a lapped reader holds producers back rather than losing items
+    #[inline(always)]
+    fn gates(&self) -> bool {
+        matches!(self.policy, OverflowPolicy::Reject | OverflowPolicy::Block { .. })
+    }
+
+    // Gating sequence of the slowest reader, or INACTIVE if there is none
+    #[inline(always)]
+    fn gate(&self) -> usize {
+        self.readers.iter()
+            .map(|reader| reader.position.load(Ordering::SeqCst))
+            .min()
+            .unwrap_or(INACTIVE)
+    }
+
+    #[inline(always)]
+    fn is_gated(&self) -> bool {
+        let seq = self.producer_index.load(Ordering::SeqCst);
+        self.gates() && seq.saturating_sub(self.gate()) >= self.capacity
+    }
+
+    /// Refuse further publishes. Readers can still read what was published,
+    /// and blocked producers and readers wake up.
+    #[inline(always)]
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        self.wait.notify();
+    }
+
+    #[inline(always)]
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    /// Sequence number the next published item will get.
+    #[inline(always)]
+    pub fn published(&self) -> usize {
+        self.producer_index.load(Ordering::Acquire)
+    }
+
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    #[inline(always)]
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+}
+
+impl<T> Drop for BroadcastRing<T> {
+    fn drop(&mut self) {
+        // Readers borrow the ring, so none is left; every published slot of the
+        // last lap still owns its item
+        let tail = *self.producer_index.get_mut();
+        for seq in tail.saturating_sub(self.capacity)..tail {
+            let slot = &mut self.buffer[seq & self.mask];
+            if *slot.sequence.get_mut() == seq.wrapping_add(1) {
+                unsafe {
+                    slot.value.get_mut().assume_init_drop();
+                }
+            }
+        }
+    }
+}
+
+/// One reader of a `BroadcastRing`, from `subscribe`. Reads every item in
+/// publish order; dropping it stops it gating producers.
+pub struct BroadcastReader<'a, T> {
+    ring: &'a BroadcastRing<T>,
+    id: usize,
+}
+
+impl<T> BroadcastReader<'_, T> {
+    /// Read the next item, cloning it out of the ring. Returns `Ok(None)`
+    /// when caught up, and fails with `Lagged` if producers overwrote items
+    /// before this reader got to them; reading again resumes at the oldest
+    /// item still held.
+    #[inline(always)]
+    pub fn try_read(&mut self) -> Result<Option<T>, TableError>
+    where
+        T: Clone,
+    {
+        self.read_with(|_, item| item.clone())
+    }
+
+    /// Like `try_read`, copying the item out with `f(seq, &item)`.
+    pub fn read_with<R>(&mut self, f: impl FnOnce(usize, &T) -> R) -> Result<Option<R>, TableError> {
+        let ring = self.ring;
+        let cursor = &ring.readers[self.id];
+        let position = cursor.position.load(Ordering::Relaxed);
+        let slot = unsafe { ring.buffer.get_unchecked(position & ring.mask) };
+
+        // Pin before checking, pairing with the producer's mark-then-wait
+        slot.readers.fetch_add(1, Ordering::SeqCst);
+        let slot_seq = slot.sequence.load(Ordering::SeqCst);
+        let item = (slot_seq == position.wrapping_add(1))
+            .then(|| f(position, unsafe { (*slot.value.get()).assume_init_ref() }));
+        slot.readers.fetch_sub(1, Ordering::Release);
+
+        if let Some(item) = item {
+            cursor.position.store(position.wrapping_add(1), Ordering::SeqCst);
+            if ring.gates() {
+                ring.wait.notify();
+            }
+            return Ok(Some(item));
+        }
+        if slot_seq <= position.wrapping_add(1) {
+            // Not published yet
+            return Ok(None);
+        }
+
+        // A producer has claimed the slot for a later lap
+        let resume = ring.producer_index.load(Ordering::SeqCst) - ring.capacity;
+        let missed = resume - position;
+        cursor.position.store(resume, Ordering::SeqCst);
+        cursor.skipped.fetch_add(missed, Ordering::Relaxed);
+        Err(TableError::Lagged { missed })
+    }
+
+    /// Read the next item, waiting up to `timeout` with the ring's wait
+    /// strategy. Returns `Ok(None)` if none arrives in time, and fails with
+    /// `Closed` once the ring is closed and this reader has caught up.
+    pub fn read_timeout(&mut self, timeout: Duration) -> Result<Option<T>, TableError>
+    where
+        T: Clone,
+    {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(item) = self.try_read()? {
+                return Ok(Some(item));
+            }
+            if self.ring.is_closed() {
+                // Anything published before the close is visible by now
+                return self.try_read()?.map(Some).ok_or(TableError::Closed);
+            }
+            if !self.ring.wait.wait_until(&|| self.has_next() || self.ring.is_closed(), Some(deadline)) {
+                return Ok(None);
+            }
+        }
+    }
+
+    // The slot at our position no longer holds an older item
+    #[inline(always)]
+    fn has_next(&self) -> bool {
+        let position = self.position();
+        let slot = unsafe { self.ring.buffer.get_unchecked(position & self.ring.mask) };
+        slot.sequence.load(Ordering::Acquire) >= position.wrapping_add(1)
+    }
+
+    /// Sequence number of the next item this reader will read.
+    #[inline(always)]
+    pub fn position(&self) -> usize {
+        self.ring.readers[self.id].position.load(Ordering::Relaxed)
+    }
+
+    /// Items published but not yet read. More than the capacity means the
+    /// next read reports `Lagged`.
+    #[inline(always)]
+    pub fn lag(&self) -> usize {
+        self.ring.published().saturating_sub(self.position())
+    }
+
+    /// Items this reader never saw because producers overwrote them first.
+    #[inline(always)]
+    pub fn missed(&self) -> usize {
+        self.ring.readers[self.id].skipped.load(Ordering::Relaxed)
+    }
+}
+
+impl<T> Drop for BroadcastReader<'_, T> {
+    fn drop(&mut self) {
+        self.ring.readers[self.id].position.store(INACTIVE, Ordering::SeqCst);
+        self.ring.wait.notify();
+    }
+}
@@ -0,0 +1,71 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+const CACHE_LINE_SIZE: usize = 64;
+const SHARDS: usize = 16;  // Threads beyond this share shards
+
+static NEXT_SHARD: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    // Shard this thread adds to, handed out round-robin on first use
+    static SHARD: usize = NEXT_SHARD.fetch_add(1, Ordering::Relaxed) % SHARDS;
+}
+
+/// Shard the calling thread records into, handed out round-robin.
+#[inline(always)]
+pub(crate) fn shard() -> usize {
+    SHARD.with(|&shard| shard)
+}
+
+// One thread's share of every counter, on its own cache line
+#[repr(align(64))]
+struct Shard<const N: usize> {
+    counts: [AtomicU64; N],
+}
+
+/// `N` event counters split into per-thread shards, so threads counting at
+/// once do not contend on one cache line. Adding is a relaxed atomic add on
+/// the calling thread's shard; reading sums every shard, so a read taken
+/// while threads are counting may miss their latest adds.
+pub(crate) struct ShardedCounters<const N: usize> {
+    shards: Box<[Shard<N>]>,
+}
+
+impl<const N: usize> ShardedCounters<N> {
+    pub(crate) fn new() -> Self {
+        assert!(N * 8 <= CACHE_LINE_SIZE, "Counters must fit one cache line per shard");
+        Self {
+            shards: (0..SHARDS).map(|_| Shard { counts: std::array::from_fn(|_| AtomicU64::new(0)) }).collect(),
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn add(&self, counter: usize, n: u64) {
+        self.shards[shard()].counts[counter].fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub(crate) fn sum(&self, counter: usize) -> u64 {
+        self.shards.iter().map(|shard| shard.counts[counter].load(Ordering::Relaxed)).sum()
+    }
+}
+
+/// Counters of one `LowLatencyMpmcRing`, from `LowLatencyMpmcRing::stats`.
+/// Each one is read separately while the ring keeps running, so they need
+/// not add up exactly.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RingStats {
+    /// Items enqueued, including any later evicted
+    pub enqueued: u64,
+    /// Items taken out by consumers, or released once every cursor read them
+    pub dequeued: u64,
+    /// Items evicted by the DropOldest and Overwrite policies
+    pub evicted: u64,
+    /// Enqueue attempts that found the ring full, including retries while
+    /// blocked under the Block policy
+    pub rejected_full: u64,
+    /// Index claims lost to another producer or consumer and retried
+    pub cas_retries: u64,
+    /// Times a thread yielded its time slice while another finished a claim
+    pub yields: u64,
+    /// Most items the ring has held at once
+    pub high_water_mark: usize,
+}
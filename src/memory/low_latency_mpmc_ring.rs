@@ -1,23 +1,162 @@
 use std::cell::UnsafeCell;
-use std::sync::atomic::{AtomicUsize, Ordering, fence};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering, fence};
 use std::mem::MaybeUninit;
+use std::ops::Range;
+use std::sync::Arc;
+#[cfg(feature = "async")]
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+use crate::error::TableError;
+use crate::memory::counters::{RingStats, ShardedCounters};
+use crate::memory::wait_strategy::{SpinThenYield, WaitStrategy};
+#[cfg(feature = "async")]
+use crate::memory::wakers::WakerList;
 
 // Constants for performance tuning
 const CACHE_LINE_SIZE: usize = 64;
-const SPIN_LIMIT: u32 = 6;  // Optimal spin count before yielding
+const SPIN_LIMIT: u32 = 6;  // Spins before yielding while another thread finishes a claim
+/// Independent read cursors a ring can track at once
+pub const MAX_CURSORS: usize = 16;
+pub(crate) const INACTIVE: usize = usize::MAX;
+
+// Slow-path events counted in `LowLatencyMpmcRing::counters`
+const REJECTED_FULL: usize = 0;
+const CAS_RETRIES: usize = 1;
+const YIELDS: usize = 2;
+
+// How a single producer stores its claim. With the async feature it is
+// SeqCst like every other claim, so wakers need no fence to see it.
+#[cfg(feature = "async")]
+const PRODUCER_STORE: Ordering = Ordering::SeqCst;
+#[cfg(not(feature = "async"))]
+const PRODUCER_STORE: Ordering = Ordering::Release;
 
 // Cache-line aligned slot for better performance
 #[repr(align(64))]
-struct Slot<T> {
-    sequence: AtomicUsize,
-    value: UnsafeCell<MaybeUninit<T>>,  // Use MaybeUninit for better performance
-    _padding: [u8; CACHE_LINE_SIZE - 16],  // Prevent false sharing
+pub(crate) struct Slot<T> {
+    pub(crate) sequence: AtomicUsize,
+    pub(crate) readers: AtomicUsize,  // Scanners currently borrowing the value
+    pub(crate) value: UnsafeCell<MaybeUninit<T>>,  // Use MaybeUninit for better performance
+    _padding: [u8; CACHE_LINE_SIZE - 24],  // Prevent false sharing
 }
 
 // Thread safety implementations
 unsafe impl<T: Send> Send for Slot<T> {}
 unsafe impl<T: Send> Sync for Slot<T> {}
 
+impl<T> Slot<T> {
+    #[inline(always)]
+    pub(crate) fn new(sequence: usize) -> Self {
+        Self {
+            sequence: AtomicUsize::new(sequence),
+            readers: AtomicUsize::new(0),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+            _padding: [0; CACHE_LINE_SIZE - 24],
+        }
+    }
+
+    /// Wait until no reader has the slot pinned. Whoever calls this must
+    /// already have claimed the slot, so no new pin can succeed.
+    #[inline(always)]
+    pub(crate) fn wait_for_readers(&self) {
+        let mut spin_count = 0;
+        while self.readers.load(Ordering::SeqCst) != 0 {
+            spin_count += 1;
+            if spin_count > SPIN_LIMIT {
+                std::thread::yield_now();
+                spin_count = 0;
+            } else {
+                std::hint::spin_loop();
+            }
+        }
+    }
+}
+
+// Read position of one consumer group or reader, on its own cache line
+#[repr(align(64))]
+pub(crate) struct Cursor {
+    pub(crate) position: AtomicUsize,  // Next sequence to read, or INACTIVE
+    pub(crate) skipped: AtomicUsize,  // Items released before this cursor reached them
+    _padding: [u8; CACHE_LINE_SIZE - 16],
+}
+
+impl Cursor {
+    #[inline(always)]
+    pub(crate) fn inactive() -> Self {
+        Self {
+            position: AtomicUsize::new(INACTIVE),
+            skipped: AtomicUsize::new(0),
+            _padding: [0; CACHE_LINE_SIZE - 16],
+        }
+    }
+}
+
+/// What `enqueue` does when the ring is full.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Fail with `Full` and leave the ring untouched
+    #[default]
+    Reject,
+    /// Dequeue and discard the oldest item, then retry
+    DropOldest,
+    /// Wait for a consumer to free a slot, up to `timeout` if given
+    Block { timeout: Option<Duration> },
+    /// Never wait: take over the oldest unconsumed slot in place and write
+    /// the new item into it. Lossy for readers that fall a lap behind.
+    Overwrite,
+}
+
+/// How many threads may enqueue, and how many may dequeue, at the same time.
+///
+/// A single producer or consumer advances its index with a plain store
+/// instead of a CAS. A single producer also keeps a copy of the consumer
+/// index and only reads the shared one when the ring looks full; a single
+/// consumer does the same with the producer index for batch dequeues. The
+/// ring cannot check the promise cheaply, so
+/// `LowLatencyMpmcRing::with_topology` is unsafe.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Topology {
+    /// Any number of producers and consumers
+    #[default]
+    Mpmc,
+    /// Any number of producers, one consumer
+    Mpsc,
+    /// One producer, any number of consumers
+    Spmc,
+    /// One producer, one consumer
+    Spsc,
+}
+
+impl Topology {
+    #[inline(always)]
+    pub fn single_producer(self) -> bool {
+        matches!(self, Topology::Spmc | Topology::Spsc)
+    }
+
+    #[inline(always)]
+    pub fn single_consumer(self) -> bool {
+        matches!(self, Topology::Mpsc | Topology::Spsc)
+    }
+}
+
+// The other side's index as a single producer or consumer last read it
+#[repr(align(64))]
+struct IndexCache {
+    index: AtomicUsize,
+    _padding: [u8; CACHE_LINE_SIZE - 8],
+}
+
+impl IndexCache {
+    #[inline(always)]
+    fn new(index: usize) -> Self {
+        Self {
+            index: AtomicUsize::new(index),
+            _padding: [0; CACHE_LINE_SIZE - 8],
+        }
+    }
+}
+
 // Cache-line aligned ring buffer
 #[repr(align(64))]
 pub struct LowLatencyMpmcRing<T> {
@@ -26,7 +165,20 @@ pub struct LowLatencyMpmcRing<T> {
     mask: usize,
     producer_index: AtomicUsize,
     consumer_index: AtomicUsize,
-    _padding: [u8; CACHE_LINE_SIZE - 40],  // Prevent false sharing
+    policy: OverflowPolicy,
+    evicted: AtomicUsize,
+    closed: AtomicBool,
+    cursors: Box<[Cursor]>,
+    wait: Arc<dyn WaitStrategy>,
+    topology: Topology,
+    cached_head: IndexCache,  // Consumer index, as the single producer last saw it
+    cached_tail: IndexCache,  // Producer index, as the single consumer last saw it
+    first_seq: usize,  // Sequence the indices started from
+    high_water_mark: AtomicUsize,
+    counters: ShardedCounters<3>,
+    #[cfg(feature = "async")]
+    wakers: WakerList,
+    _padding: [u8; CACHE_LINE_SIZE - 56],  // Prevent false sharing
 }
 
 // Thread safety implementations
@@ -36,16 +188,17 @@ unsafe impl<T: Send> Sync for LowLatencyMpmcRing<T> {}
 impl<T> LowLatencyMpmcRing<T> {
     #[inline(always)]
     pub fn new(capacity: usize) -> Self {
+        Self::with_policy(capacity, OverflowPolicy::Reject)
+    }
+
+    #[inline(always)]
+    pub fn with_policy(capacity: usize, policy: OverflowPolicy) -> Self {
         assert!(capacity.is_power_of_two(), "Capacity must be a power of 2");
         
         // Pre-allocate all slots
         let mut vec = Vec::with_capacity(capacity);
         for i in 0..capacity {
-            vec.push(Slot {
-                sequence: AtomicUsize::new(i),
-                value: UnsafeCell::new(MaybeUninit::uninit()),
-                _padding: [0; CACHE_LINE_SIZE - 16],
-            });
+            vec.push(Slot::new(i));
         }
 
         Self {
@@ -54,50 +207,487 @@ impl<T> LowLatencyMpmcRing<T> {
             mask: capacity - 1,
             producer_index: AtomicUsize::new(0),
             consumer_index: AtomicUsize::new(0),
-            _padding: [0; CACHE_LINE_SIZE - 40],
+            policy,
+            evicted: AtomicUsize::new(0),
+            closed: AtomicBool::new(false),
+            cursors: (0..MAX_CURSORS).map(|_| Cursor::inactive()).collect(),
+            wait: Arc::new(SpinThenYield::default()),
+            topology: Topology::Mpmc,
+            cached_head: IndexCache::new(0),
+            cached_tail: IndexCache::new(0),
+            first_seq: 0,
+            high_water_mark: AtomicUsize::new(0),
+            counters: ShardedCounters::new(),
+            #[cfg(feature = "async")]
+            wakers: WakerList::default(),
+            _padding: [0; CACHE_LINE_SIZE - 56],
         }
     }
 
+    /// How threads wait for room under the Block policy and in the timed
+    /// enqueue and dequeue calls. Defaults to `SpinThenYield`.
+    #[inline(always)]
+    pub fn with_wait_strategy(mut self, wait: Arc<dyn WaitStrategy>) -> Self {
+        self.wait = wait;
+        self
+    }
+
+    #[inline(always)]
+    pub fn wait_strategy(&self) -> &Arc<dyn WaitStrategy> {
+        &self.wait
+    }
+
+    /// Drop the CAS on the producer index, the consumer index or both, as
+    /// `topology` allows. A single-consumer ring cannot evict, so its policy
+    /// must be Reject or Block, and it has no cursors: `add_cursor` returns
+    /// None.
+    ///
+    /// # Safety
+    /// With a single producer, no two threads may enqueue at the same time;
+    /// with a single consumer, no two threads may dequeue at the same time.
+    pub unsafe fn with_topology(mut self, topology: Topology) -> Self {
+        assert!(
+            !topology.single_consumer()
+                || matches!(self.policy, OverflowPolicy::Reject | OverflowPolicy::Block { .. }),
+            "A single-consumer ring needs the Reject or Block policy"
+        );
+        self.topology = topology;
+        self
+    }
+
     #[inline(always)]
-    pub fn try_enqueue(&self, item: T) -> bool {
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    /// Renumber an empty ring so the next item enqueued gets sequence `seq`.
+    /// Used when restoring persisted state.
+    pub fn start_at(&mut self, seq: usize) {
+        assert!(self.is_empty(), "Only an empty ring can be renumbered");
+        for offset in 0..self.capacity {
+            let slot_seq = seq.wrapping_add(offset);
+            *self.buffer[slot_seq & self.mask].sequence.get_mut() = slot_seq;
+        }
+        *self.producer_index.get_mut() = seq;
+        *self.consumer_index.get_mut() = seq;
+        *self.cached_head.index.get_mut() = seq;
+        *self.cached_tail.index.get_mut() = seq;
+        self.first_seq = seq;
+    }
+
+    /// Refuse every later enqueue with `Closed`, and wake producers blocked
+    /// on a full ring with the same error. Consumers keep reading until the
+    /// ring is empty. An enqueue already past its check when the ring closes
+    /// may still land, so drain after closing rather than before.
+    #[inline(always)]
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        self.notify();
+    }
+
+    /// Accept enqueues again after `close`, for an owner that closed the
+    /// ring only to find it could not hand the items on after all.
+    #[inline(always)]
+    pub fn reopen(&self) {
+        self.closed.store(false, Ordering::SeqCst);
+    }
+
+    #[inline(always)]
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    /// Dequeue items until the ring is empty. Other consumers may take items
+    /// concurrently, and items enqueued while draining may be included.
+    #[inline(always)]
+    pub fn drain(&self) -> Drain<'_, T> {
+        Drain { ring: self }
+    }
+
+    /// Enqueue according to the ring's overflow policy. Returns how many
+    /// items were evicted to make room.
+    #[inline(always)]
+    pub fn enqueue(&self, item: T) -> Result<usize, TableError> {
+        self.enqueue_with(move |_| item)
+    }
+
+    /// Like `enqueue`, but the item is built by `make` once its sequence
+    /// number is claimed and before it is published, so `make` may also fill
+    /// storage kept outside the ring for slot `seq & (capacity - 1)`. Not
+    /// called if the ring refuses the item.
+    #[inline(always)]
+    pub fn enqueue_with(&self, make: impl FnOnce(usize) -> T) -> Result<usize, TableError> {
+        if self.is_closed() {
+            return Err(TableError::Closed);
+        }
+        match self.policy {
+            OverflowPolicy::Reject => self.try_enqueue_with(make).map(|_| 0),
+            OverflowPolicy::DropOldest => Ok(self.enqueue_drop_oldest(make)),
+            OverflowPolicy::Block { timeout } => self.enqueue_blocking(make, timeout).map(|_| 0),
+            OverflowPolicy::Overwrite => Ok(self.enqueue_overwrite(make)),
+        }
+    }
+
+    #[inline(always)]
+    pub fn try_enqueue(&self, item: T) -> Result<(), TableError> {
+        self.try_enqueue_with(move |_| item)
+    }
+
+    #[inline(always)]
+    pub fn try_enqueue_with(&self, make: impl FnOnce(usize) -> T) -> Result<(), TableError> {
+        if self.is_closed() {
+            return Err(TableError::Closed);
+        }
+        self.try_push(make).map_err(|_| TableError::Full)
+    }
+
+    fn enqueue_drop_oldest<F: FnOnce(usize) -> T>(&self, mut make: F) -> usize {
+        let mut evicted = 0;
+        loop {
+            match self.try_push(make) {
+                Ok(()) => break,
+                Err(rejected) => {
+                    make = rejected;
+                    // A consumer may win the race for the head; either way a slot frees up
+                    if let Ok(Some(oldest)) = self.try_dequeue() {
+                        drop(oldest);
+                        evicted += 1;
+                    }
+                }
+            }
+        }
+        if evicted > 0 {
+            self.evicted.fetch_add(evicted, Ordering::Relaxed);
+        }
+        evicted
+    }
+
+    fn enqueue_blocking<F: FnOnce(usize) -> T>(&self, make: F, timeout: Option<Duration>) -> Result<(), TableError> {
+        self.enqueue_until(make, timeout.map(|timeout| Instant::now() + timeout))
+    }
+
+    /// Enqueue, waiting with the ring's wait strategy for a slot to free up
+    /// if it is full, whatever the overflow policy. Fails with `Full` if none
+    /// does within `timeout`, or `Closed` if the ring is closed meanwhile.
+    #[inline(always)]
+    pub fn enqueue_timeout(&self, item: T, timeout: Duration) -> Result<(), TableError> {
+        if self.is_closed() {
+            return Err(TableError::Closed);
+        }
+        self.enqueue_until(move |_| item, Some(Instant::now() + timeout))
+    }
+
+    fn enqueue_until<F: FnOnce(usize) -> T>(&self, mut make: F, deadline: Option<Instant>) -> Result<(), TableError> {
+        loop {
+            match self.try_push(make) {
+                Ok(()) => return Ok(()),
+                Err(rejected) => make = rejected,
+            }
+            if self.is_closed() {
+                return Err(TableError::Closed);
+            }
+            if !self.wait.wait_until(&|| self.next_slot_free() || self.is_closed(), deadline) {
+                return Err(TableError::Full);
+            }
+        }
+    }
+
+    /// Wait with the ring's wait strategy until the next enqueue would find
+    /// a free slot. Fails with `Full` if none does by `deadline` (`None`
+    /// waits indefinitely), or `Closed` once the ring is closed. Another
+    /// producer may still take the slot first, so enqueues can fail `Full`.
+    pub fn wait_enqueue_ready(&self, deadline: Option<Instant>) -> Result<(), TableError> {
+        let ready = self.wait.wait_until(&|| self.next_slot_free() || self.is_closed(), deadline);
+        if self.is_closed() {
+            return Err(TableError::Closed);
+        }
+        if !ready {
+            return Err(TableError::Full);
+        }
+        Ok(())
+    }
+
+    fn enqueue_overwrite(&self, make: impl FnOnce(usize) -> T) -> usize {
         let mut spin_count = 0;
         loop {
             let seq = self.producer_index.load(Ordering::Acquire);
             let idx = seq & self.mask;
 
-            // Fast path: check sequence without acquiring the slot
             let slot = unsafe { self.buffer.get_unchecked(idx) };
             let slot_seq = slot.sequence.load(Ordering::Acquire);
 
             if slot_seq == seq {
-                if self.producer_index.compare_exchange_weak(
-                    seq, seq.wrapping_add(1),
-                    Ordering::AcqRel, Ordering::Relaxed
+                if self.claim_produce(seq, 1) {
+                    unsafe {
+                        (*slot.value.get()).write(make(seq));
+                    }
+                    fence(Ordering::Release);
+                    slot.sequence.store(seq.wrapping_add(1), Ordering::Release);
+                    self.note_occupancy(seq.wrapping_add(1));
+                    self.notify();
+                    return 0;
+                }
+            } else if slot_seq == seq.wrapping_sub(self.capacity).wrapping_add(1) {
+                // Full: the slot still holds the item published one lap ago.
+                // Claiming it away from consumers makes us its only owner.
+                let head = seq.wrapping_sub(self.capacity);
+                if self.consumer_index.compare_exchange_weak(
+                    head, head.wrapping_add(1),
+                    Ordering::SeqCst, Ordering::Relaxed
                 ).is_ok() {
+                    slot.wait_for_readers();
+                    // No other producer can claim `seq` until the slot sequence
+                    // reaches it, so the producer index is ours to advance
+                    self.producer_index.store(seq.wrapping_add(1), PRODUCER_STORE);
+                    let oldest = unsafe {
+                        let value = &mut *slot.value.get();
+                        let oldest = value.assume_init_read();
+                        value.write(make(seq));
+                        oldest
+                    };
+                    fence(Ordering::Release);
+                    slot.sequence.store(seq.wrapping_add(1), Ordering::Release);
+                    self.high_water_mark.fetch_max(self.capacity, Ordering::Relaxed);
+                    self.notify();
+                    drop(oldest);
+                    self.evicted.fetch_add(1, Ordering::Relaxed);
+                    return 1;
+                }
+                self.counters.add(CAS_RETRIES, 1);
+            } else {
+                // A consumer is still releasing the slot
+                self.backoff(&mut spin_count);
+            }
+        }
+    }
+
+    /// Enqueue as many items as fit, claiming their sequence numbers with a
+    /// single CAS, then publish each slot. Returns how many were enqueued;
+    /// the rest stay in `items`. Panics if `items` yields fewer than its `len`.
+    #[inline(always)]
+    pub fn try_enqueue_batch<I: ExactSizeIterator<Item = T>>(&self, items: &mut I) -> usize {
+        self.try_enqueue_batch_with(items, &mut |_, item| item)
+    }
+
+    /// Like `try_enqueue_batch`, turning each claimed item into the stored
+    /// one with `place(seq, item)` before its slot is published. Enqueues
+    /// nothing once the ring is closed.
+    pub fn try_enqueue_batch_with<I: ExactSizeIterator>(
+        &self,
+        items: &mut I,
+        place: &mut impl FnMut(usize, I::Item) -> T,
+    ) -> usize {
+        let mut spin_count = 0;
+        loop {
+            let wanted = items.len().min(self.capacity);
+            if wanted == 0 || self.is_closed() {
+                return 0;
+            }
+
+            let seq = self.producer_index.load(Ordering::Acquire);
+            if self.known_full(seq) {
+                self.counters.add(REJECTED_FULL, 1);
+                return 0;
+            }
+            // Only claim slots that are free right now, so no claim ever waits on a consumer
+            let mut count = 0;
+            while count < wanted {
+                let slot_seq = seq.wrapping_add(count);
+                let slot = unsafe { self.buffer.get_unchecked(slot_seq & self.mask) };
+                if slot.sequence.load(Ordering::Acquire) != slot_seq {
+                    break;
+                }
+                count += 1;
+            }
+
+            if count == 0 {
+                let slot = unsafe { self.buffer.get_unchecked(seq & self.mask) };
+                if slot.sequence.load(Ordering::Acquire) < seq {
+                    self.counters.add(REJECTED_FULL, 1);
+                    return 0;
+                }
+                // Another producer claimed `seq` first
+                self.backoff(&mut spin_count);
+                continue;
+            }
+
+            if self.claim_produce(seq, count) {
+                for offset in 0..count {
+                    let item = items.next().expect("ExactSizeIterator yielded fewer items than its len");
+                    let slot_seq = seq.wrapping_add(offset);
+                    let slot = unsafe { self.buffer.get_unchecked(slot_seq & self.mask) };
+                    unsafe {
+                        (*slot.value.get()).write(place(slot_seq, item));
+                    }
+                    fence(Ordering::Release);
+                    slot.sequence.store(slot_seq.wrapping_add(1), Ordering::Release);
+                }
+                self.note_occupancy(seq.wrapping_add(count));
+                self.notify();
+                return count;
+            }
+        }
+    }
+
+    /// Enqueue every item according to the ring's overflow policy. Returns
+    /// how many items were evicted to make room. If the ring refuses part of
+    /// the batch, fails with `PartialWrite`, or `Full` if nothing was stored;
+    /// the refused items stay in `items`. Fails with `Closed` once the ring is
+    /// closed, possibly after storing part of the batch.
+    #[inline(always)]
+    pub fn enqueue_batch<I: ExactSizeIterator<Item = T>>(&self, items: &mut I) -> Result<usize, TableError> {
+        self.enqueue_batch_with(items, |_, item| item)
+    }
+
+    /// Like `enqueue_batch`, placing each item with `place(seq, item)` as in
+    /// `try_enqueue_batch_with`.
+    pub fn enqueue_batch_with<I: ExactSizeIterator>(
+        &self,
+        items: &mut I,
+        mut place: impl FnMut(usize, I::Item) -> T,
+    ) -> Result<usize, TableError> {
+        let total = items.len();
+        let refused = |remaining: usize| match total - remaining {
+            0 => TableError::Full,
+            written => TableError::PartialWrite { written },
+        };
+        if self.is_closed() {
+            return Err(TableError::Closed);
+        }
+
+        match self.policy {
+            OverflowPolicy::Reject => {
+                while items.len() > 0 {
+                    if self.try_enqueue_batch_with(items, &mut place) == 0 {
+                        if self.is_closed() {
+                            return Err(TableError::Closed);
+                        }
+                        return Err(refused(items.len()));
+                    }
+                }
+                Ok(0)
+            }
+            OverflowPolicy::DropOldest => {
+                let mut evicted = 0;
+                while items.len() > 0 {
+                    if self.try_enqueue_batch_with(items, &mut place) == 0 {
+                        if self.is_closed() {
+                            return Err(TableError::Closed);
+                        }
+                        if let Ok(Some(oldest)) = self.try_dequeue() {
+                            drop(oldest);
+                            evicted += 1;
+                        }
+                    }
+                }
+                if evicted > 0 {
+                    self.evicted.fetch_add(evicted, Ordering::Relaxed);
+                }
+                Ok(evicted)
+            }
+            OverflowPolicy::Block { timeout } => {
+                let deadline = timeout.map(|timeout| Instant::now() + timeout);
+                while items.len() > 0 {
+                    if self.try_enqueue_batch_with(items, &mut place) > 0 {
+                        continue;
+                    }
+                    if self.is_closed() {
+                        return Err(TableError::Closed);
+                    }
+                    if !self.wait.wait_until(&|| self.next_slot_free() || self.is_closed(), deadline) {
+                        return Err(refused(items.len()));
+                    }
+                }
+                Ok(0)
+            }
+            OverflowPolicy::Overwrite => Ok(items.map(|item| self.enqueue_overwrite(|seq| place(seq, item))).sum()),
+        }
+    }
+
+    /// Single attempt under the Reject policy, handing `make` back if full.
+    #[inline(always)]
+    fn try_push<F: FnOnce(usize) -> T>(&self, make: F) -> Result<(), F> {
+        let mut spin_count = 0;
+        loop {
+            let seq = self.producer_index.load(Ordering::Acquire);
+            if self.known_full(seq) {
+                self.counters.add(REJECTED_FULL, 1);
+                return Err(make);
+            }
+            let idx = seq & self.mask;
+
+            // Fast path: check sequence without acquiring the slot
+            let slot = unsafe { self.buffer.get_unchecked(idx) };
+            let slot_seq = slot.sequence.load(Ordering::Acquire);
+
+            if slot_seq == seq {
+                if self.claim_produce(seq, 1) {
                     // Write the value
                     unsafe {
-                        (*slot.value.get()).write(item);
+                        (*slot.value.get()).write(make(seq));
                     }
                     fence(Ordering::Release);
                     slot.sequence.store(seq.wrapping_add(1), Ordering::Release);
-                    return true;
+                    self.note_occupancy(seq.wrapping_add(1));
+                    self.notify();
+                    return Ok(());
                 }
             } else if slot_seq < seq {
-                return false;
+                self.counters.add(REJECTED_FULL, 1);
+                return Err(make);
             } else {
-                spin_count += 1;
-                if spin_count > SPIN_LIMIT {
-                    std::thread::yield_now();
-                    spin_count = 0;
-                } else {
-                    std::hint::spin_loop();
-                }
+                self.backoff(&mut spin_count);
             }
         }
     }
 
     #[inline(always)]
-    pub fn try_dequeue(&self) -> Option<T> {
+    pub fn try_dequeue(&self) -> Result<Option<T>, TableError> {
+        self.try_dequeue_with(|_, item| item)
+    }
+
+    /// Dequeue the oldest item and hand it to `f` with its sequence number
+    /// before the slot is released, so `f` may still read storage kept
+    /// outside the ring for that slot.
+    #[inline(always)]
+    pub fn try_dequeue_with<R>(&self, f: impl FnOnce(usize, T) -> R) -> Result<Option<R>, TableError> {
+        Ok(self.try_pop(f).ok())
+    }
+
+    /// Dequeue, waiting with the ring's wait strategy for an item to arrive.
+    /// Returns `Ok(None)` if none does within `timeout`, and fails with
+    /// `Closed` once the ring is closed and empty.
+    #[inline(always)]
+    pub fn dequeue_timeout(&self, timeout: Duration) -> Result<Option<T>, TableError> {
+        self.dequeue_timeout_with(Some(timeout), |_, item| item)
+    }
+
+    /// Like `dequeue_timeout` with `try_dequeue_with`'s `f`; a `None`
+    /// timeout waits until an item arrives or the ring is closed.
+    pub fn dequeue_timeout_with<R>(
+        &self,
+        timeout: Option<Duration>,
+        mut f: impl FnOnce(usize, T) -> R,
+    ) -> Result<Option<R>, TableError> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            match self.try_pop(f) {
+                Ok(result) => return Ok(Some(result)),
+                Err(unused) => f = unused,
+            }
+            if self.is_closed() {
+                // Anything enqueued before the close is still delivered
+                return self.try_pop(f).map(Some).map_err(|_| TableError::Closed);
+            }
+            if !self.wait.wait_until(&|| self.next_item_published() || self.is_closed(), deadline) {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Single dequeue attempt, handing `f` back if the ring is empty.
+    #[inline(always)]
+    fn try_pop<R, F: FnOnce(usize, T) -> R>(&self, f: F) -> Result<R, F> {
         let mut spin_count = 0;
         loop {
             let seq = self.consumer_index.load(Ordering::Acquire);
@@ -108,32 +698,477 @@ impl<T> LowLatencyMpmcRing<T> {
             let slot_seq = slot.sequence.load(Ordering::Acquire);
 
             if slot_seq == seq.wrapping_add(1) {
-                if self.consumer_index.compare_exchange_weak(
-                    seq, seq.wrapping_add(1),
-                    Ordering::AcqRel, Ordering::Relaxed
-                ).is_ok() {
+                if self.claim_consume(seq, 1) {
+                    slot.wait_for_readers();
                     // Take ownership of the value
                     let val = unsafe {
                         (*slot.value.get()).assume_init_read()
                     };
+                    let result = f(seq, val);
                     fence(Ordering::Release);
                     slot.sequence.store(seq.wrapping_add(self.capacity), Ordering::Release);
-                    return Some(val);
+                    self.notify();
+                    return Ok(result);
                 }
             } else if slot_seq < seq.wrapping_add(1) {
+                return Err(f);
+            } else {
+                self.backoff(&mut spin_count);
+            }
+        }
+    }
+
+    /// Dequeue up to `max` items into `out`, claiming them with a single CAS.
+    /// Returns how many were dequeued.
+    #[inline(always)]
+    pub fn try_dequeue_batch(&self, out: &mut Vec<T>, max: usize) -> usize {
+        out.reserve(max.min(self.capacity));
+        self.dequeue_batch_with(max, |_, item| out.push(item))
+    }
+
+    /// Claim up to `max` published items with a single CAS and hand each to
+    /// `f` with its sequence number, oldest first, before its slot is released.
+    pub fn dequeue_batch_with(&self, max: usize, mut f: impl FnMut(usize, T)) -> usize {
+        let wanted = max.min(self.capacity);
+        let mut spin_count = 0;
+        loop {
+            let seq = self.consumer_index.load(Ordering::Acquire);
+            if wanted == 0 || self.known_empty(seq) {
+                return 0;
+            }
+            let mut count = 0;
+            while count < wanted {
+                let slot_seq = seq.wrapping_add(count);
+                let slot = unsafe { self.buffer.get_unchecked(slot_seq & self.mask) };
+                if slot.sequence.load(Ordering::Acquire) != slot_seq.wrapping_add(1) {
+                    break;
+                }
+                count += 1;
+            }
+
+            if count == 0 {
+                let slot = unsafe { self.buffer.get_unchecked(seq & self.mask) };
+                if wanted == 0 || slot.sequence.load(Ordering::Acquire) < seq.wrapping_add(1) {
+                    return 0;
+                }
+                // Another consumer claimed `seq` first
+                self.backoff(&mut spin_count);
+                continue;
+            }
+
+            if self.claim_consume(seq, count) {
+                for offset in 0..count {
+                    let slot_seq = seq.wrapping_add(offset);
+                    let slot = unsafe { self.buffer.get_unchecked(slot_seq & self.mask) };
+                    slot.wait_for_readers();
+                    let item = unsafe {
+                        (*slot.value.get()).assume_init_read()
+                    };
+                    f(slot_seq, item);
+                    fence(Ordering::Release);
+                    slot.sequence.store(slot_seq.wrapping_add(self.capacity), Ordering::Release);
+                }
+                self.notify();
+                return count;
+            }
+        }
+    }
+
+    /// Start an independent cursor at the oldest retained item. Items stay in
+    /// the ring until every cursor has read them, so under Reject and Block
+    /// producers are gated by the slowest cursor. Returns None when all
+    /// `MAX_CURSORS` are in use, and always on a single-consumer ring.
+    pub fn add_cursor(&self) -> Option<usize> {
+        // Cursors release items, which makes them consumers too
+        if self.topology.single_consumer() {
+            return None;
+        }
+        let head = self.consumer_index.load(Ordering::SeqCst);
+        let id = self.cursors.iter().position(|cursor| {
+            cursor.position.compare_exchange(INACTIVE, head, Ordering::SeqCst, Ordering::Relaxed).is_ok()
+        })?;
+        let cursor = &self.cursors[id];
+        cursor.skipped.store(0, Ordering::Relaxed);
+        // Items released while we registered were never ours to read
+        let mut position = head;
+        loop {
+            let head = self.consumer_index.load(Ordering::SeqCst);
+            if position >= head {
+                break;
+            }
+            match cursor.position.compare_exchange(position, head, Ordering::SeqCst, Ordering::Relaxed) {
+                Ok(_) => break,
+                Err(current) => position = current,
+            }
+        }
+        Some(id)
+    }
+
+    /// Stop tracking a cursor. Call `release_consumed` afterwards so the items
+    /// it was holding back can be freed.
+    #[inline(always)]
+    pub fn remove_cursor(&self, id: usize) {
+        self.cursors[id].position.store(INACTIVE, Ordering::SeqCst);
+    }
+
+    /// Read the next item for cursor `id`, cloning it out of the ring. Callers
+    /// sharing a cursor each get distinct items. Returns None when the cursor
+    /// has caught up with the producers.
+    #[inline(always)]
+    pub fn try_read_cursor(&self, id: usize) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.read_cursor_with(id, |_, item| item.clone())
+    }
+
+    /// Like `try_read_cursor`, copying the item out with `f(seq, &item)`.
+    /// `f` may run for items the caller then loses to another reader.
+    pub fn read_cursor_with<R>(&self, id: usize, mut f: impl FnMut(usize, &T) -> R) -> Option<R> {
+        let cursor = &self.cursors[id];
+        loop {
+            let position = cursor.position.load(Ordering::Acquire);
+            // Copy first: once the cursor moves on, the slot may be released
+            if let Some(item) = self.peek_with(position, |item| f(position, item)) {
+                if cursor.position.compare_exchange_weak(
+                    position, position.wrapping_add(1),
+                    Ordering::SeqCst, Ordering::Relaxed
+                ).is_ok() {
+                    return Some(item);
+                }
+                continue;
+            }
+
+            let head = self.consumer_index.load(Ordering::SeqCst);
+            if position >= head {
                 return None;
+            }
+            // Evicted or dequeued before this cursor got to it
+            if cursor.position.compare_exchange(
+                position, head,
+                Ordering::SeqCst, Ordering::Relaxed
+            ).is_ok() {
+                cursor.skipped.fetch_add(head - position, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Free the items every cursor has read. Returns how many were freed.
+    pub fn release_consumed(&self) -> usize {
+        let limit = self.cursors.iter()
+            .map(|cursor| cursor.position.load(Ordering::SeqCst))
+            .min()
+            .unwrap_or(INACTIVE);
+        if limit == INACTIVE {
+            return 0;
+        }
+
+        let mut released = 0;
+        loop {
+            let seq = self.consumer_index.load(Ordering::Acquire);
+            if seq >= limit {
+                break;
+            }
+            let slot = unsafe { self.buffer.get_unchecked(seq & self.mask) };
+            if slot.sequence.load(Ordering::Acquire) != seq.wrapping_add(1) {
+                // Already being taken by a consumer or an overwriting producer
+                std::hint::spin_loop();
+                continue;
+            }
+            if self.consumer_index.compare_exchange_weak(
+                seq, seq.wrapping_add(1),
+                Ordering::SeqCst, Ordering::Relaxed
+            ).is_ok() {
+                slot.wait_for_readers();
+                unsafe {
+                    (*slot.value.get()).assume_init_drop();
+                }
+                fence(Ordering::Release);
+                slot.sequence.store(seq.wrapping_add(self.capacity), Ordering::Release);
+                released += 1;
             } else {
-                spin_count += 1;
-                if spin_count > SPIN_LIMIT {
-                    std::thread::yield_now();
-                    spin_count = 0;
-                } else {
-                    std::hint::spin_loop();
+                self.counters.add(CAS_RETRIES, 1);
+            }
+        }
+        if released > 0 {
+            self.notify();
+        }
+        released
+    }
+
+    /// Items published but not yet read by cursor `id`.
+    #[inline(always)]
+    pub fn cursor_lag(&self, id: usize) -> usize {
+        let position = self.cursors[id].position.load(Ordering::Acquire);
+        let tail = self.producer_index.load(Ordering::Acquire);
+        tail.saturating_sub(position.max(self.consumer_index.load(Ordering::Acquire)))
+    }
+
+    /// Items cursor `id` never saw because they were evicted or dequeued first.
+    #[inline(always)]
+    pub fn cursor_skipped(&self, id: usize) -> usize {
+        self.cursors[id].skipped.load(Ordering::Relaxed)
+    }
+
+    /// Borrow the item at sequence `seq` without dequeuing it. Returns None if
+    /// that item has already been consumed, evicted or not yet published.
+    ///
+    /// The scanner pins the slot, then re-checks that no consumer has claimed
+    /// it; a consumer that claims a pinned slot waits for the pin to be
+    /// released before moving the value out, so `f` never sees a freed item.
+    #[inline(always)]
+    pub fn peek_with<R>(&self, seq: usize, f: impl FnOnce(&T) -> R) -> Option<R> {
+        let slot = unsafe { self.buffer.get_unchecked(seq & self.mask) };
+        if slot.sequence.load(Ordering::Acquire) != seq.wrapping_add(1) {
+            return None;
+        }
+
+        slot.readers.fetch_add(1, Ordering::SeqCst);
+        let claimed = self.consumer_index.load(Ordering::SeqCst) > seq;
+        let result = if !claimed && slot.sequence.load(Ordering::Acquire) == seq.wrapping_add(1) {
+            Some(f(unsafe { (*slot.value.get()).assume_init_ref() }))
+        } else {
+            None
+        };
+        slot.readers.fetch_sub(1, Ordering::Release);
+        result
+    }
+
+    /// Sequence numbers of the items currently held, oldest first. Only a
+    /// snapshot: consumers and producers may move either end concurrently.
+    #[inline(always)]
+    pub fn retained(&self) -> Range<usize> {
+        let head = self.consumer_index.load(Ordering::Acquire);
+        let tail = self.producer_index.load(Ordering::Acquire);
+        head..tail.max(head)
+    }
+
+    /// Copy the retained items without dequeuing them. Returns the sequence
+    /// of the first copied item; the copies are contiguous from there, so
+    /// they match a state the ring was really in. If consumers overtake the
+    /// copy it restarts from the new head, so the work is bounded by capacity.
+    /// `f` receives each item's sequence number along with the item.
+    pub fn copy_retained<R>(&self, mut f: impl FnMut(usize, &T) -> R) -> (usize, Vec<R>) {
+        let Range { mut start, end } = self.retained();
+        let mut items = Vec::with_capacity(end - start);
+        let mut spin_count = 0;
+        while start + items.len() < end {
+            let seq = start + items.len();
+            if let Some(item) = self.peek_with(seq, |item| f(seq, item)) {
+                items.push(item);
+                continue;
+            }
+
+            let head = self.consumer_index.load(Ordering::SeqCst);
+            if head > seq {
+                // Everything before the head is gone too
+                items.clear();
+                start = head.min(end);
+            } else {
+                // Claimed by a producer that has not published yet
+                self.backoff(&mut spin_count);
+            }
+        }
+        (start, items)
+    }
+
+    /// Next sequence cursor `id` will read.
+    #[inline(always)]
+    pub fn cursor_position(&self, id: usize) -> usize {
+        self.cursors[id].position.load(Ordering::Acquire)
+    }
+
+    /// Start a cursor at `position`, clamped to the retained items. Used when
+    /// restoring persisted state.
+    pub fn add_cursor_at(&mut self, position: usize, skipped: usize) -> Option<usize> {
+        let retained = self.retained();
+        let id = self.cursors.iter_mut().position(|cursor| *cursor.position.get_mut() == INACTIVE)?;
+        let cursor = &mut self.cursors[id];
+        *cursor.position.get_mut() = position.clamp(retained.start, retained.end);
+        *cursor.skipped.get_mut() = skipped;
+        Some(id)
+    }
+
+    /// Wake `waker` on the next publish, release or close. Pollers register
+    /// and then check the ring again. A claim made before the registration
+    /// may be published without a wake, so a poller that still finds nothing
+    /// asks `claimed_past` whether one is in flight.
+    #[cfg(feature = "async")]
+    #[inline(always)]
+    pub fn register_waker(&self, waker: &Waker) {
+        self.wakers.register(waker);
+    }
+
+    /// Whether producers have claimed sequence `seq`, published or not.
+    #[cfg(feature = "async")]
+    #[inline(always)]
+    pub fn claimed_past(&self, seq: usize) -> bool {
+        (self.producer_index.load(Ordering::SeqCst).wrapping_sub(seq) as isize) > 0
+    }
+
+    /// Dequeue, or register `cx`'s waker to be woken once an item may be
+    /// available. Ready with `Closed` once the ring is closed and empty.
+    #[cfg(feature = "async")]
+    pub fn poll_dequeue_with<R>(
+        &self,
+        cx: &mut Context<'_>,
+        f: impl FnOnce(usize, T) -> R,
+    ) -> Poll<Result<R, TableError>> {
+        let f = match self.try_pop(f) {
+            Ok(result) => return Poll::Ready(Ok(result)),
+            Err(f) => f,
+        };
+        let closed = self.is_closed();
+        self.register_waker(cx.waker());
+        match self.try_pop(f) {
+            Ok(result) => Poll::Ready(Ok(result)),
+            Err(_) if closed => Poll::Ready(Err(TableError::Closed)),
+            Err(_) => {
+                // Being published, or closed, without a wake for us
+                if self.claimed_past(self.consumer_index.load(Ordering::SeqCst)) || self.is_closed() {
+                    cx.waker().wake_by_ref();
                 }
+                Poll::Pending
+            }
+        }
+    }
+
+    /// Ready once the next enqueue would find a free slot, or with `Closed`
+    /// once the ring is closed; otherwise registers `cx`'s waker. Another
+    /// producer may still take the slot first, so enqueues can fail `Full`.
+    #[cfg(feature = "async")]
+    pub fn poll_enqueue_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), TableError>> {
+        for registered in [false, true] {
+            if self.is_closed() {
+                return Poll::Ready(Err(TableError::Closed));
             }
+            if self.next_slot_free() {
+                return Poll::Ready(Ok(()));
+            }
+            if !registered {
+                self.register_waker(cx.waker());
+            }
+        }
+        // A consumer has claimed the slot but may release it without a wake
+        let tail = self.producer_index.load(Ordering::SeqCst);
+        if tail.wrapping_sub(self.consumer_index.load(Ordering::SeqCst)) < self.capacity {
+            cx.waker().wake_by_ref();
+        }
+        Poll::Pending
+    }
+
+    // Move the producer index from `seq` past `count` claimed slots: a plain
+    // store when nobody else can move it, a CAS that may lose otherwise.
+    // SeqCst, like the consumer's, pairs with the checks pollers make after
+    // registering a waker.
+    #[inline(always)]
+    fn claim_produce(&self, seq: usize, count: usize) -> bool {
+        if self.topology.single_producer() {
+            self.producer_index.store(seq.wrapping_add(count), PRODUCER_STORE);
+            return true;
+        }
+        let claimed = self.producer_index.compare_exchange_weak(
+            seq, seq.wrapping_add(count),
+            Ordering::SeqCst, Ordering::Relaxed
+        ).is_ok();
+        if !claimed {
+            self.counters.add(CAS_RETRIES, 1);
+        }
+        claimed
+    }
+
+    // Same for the consumer index; SeqCst pairs with `peek_with`'s pin
+    #[inline(always)]
+    fn claim_consume(&self, seq: usize, count: usize) -> bool {
+        if self.topology.single_consumer() {
+            self.consumer_index.store(seq.wrapping_add(count), Ordering::SeqCst);
+            return true;
+        }
+        let claimed = self.consumer_index.compare_exchange_weak(
+            seq, seq.wrapping_add(count),
+            Ordering::SeqCst, Ordering::Relaxed
+        ).is_ok();
+        if !claimed {
+            self.counters.add(CAS_RETRIES, 1);
+        }
+        claimed
+    }
+
+    // Whether a single producer at `seq` is a lap ahead of the consumers. The
+    // cached head only ever lags, so the shared one is read only to confirm.
+    #[inline(always)]
+    fn known_full(&self, seq: usize) -> bool {
+        if !self.topology.single_producer()
+            || seq.wrapping_sub(self.cached_head.index.load(Ordering::Relaxed)) < self.capacity
+        {
+            return false;
+        }
+        let head = self.consumer_index.load(Ordering::Acquire);
+        self.cached_head.index.store(head, Ordering::Relaxed);
+        seq.wrapping_sub(head) >= self.capacity
+    }
+
+    // Whether a single consumer at `seq` has caught up with every claim.
+    // Only batches use it: a lone dequeue would reload the tail every time
+    // the ring runs dry, which costs more than the slot check it saves.
+    #[inline(always)]
+    fn known_empty(&self, seq: usize) -> bool {
+        if !self.topology.single_consumer() || seq != self.cached_tail.index.load(Ordering::Relaxed) {
+            return false;
+        }
+        let tail = self.producer_index.load(Ordering::Acquire);
+        self.cached_tail.index.store(tail, Ordering::Relaxed);
+        tail == seq
+    }
+
+    // Spin a few times, then yield, while another thread finishes a claim
+    #[inline(always)]
+    fn backoff(&self, spin_count: &mut u32) {
+        *spin_count += 1;
+        if *spin_count > SPIN_LIMIT {
+            self.counters.add(YIELDS, 1);
+            std::thread::yield_now();
+            *spin_count = 0;
+        } else {
+            std::hint::spin_loop();
         }
     }
 
+    // Raise the high-water mark to the items held once the claims before
+    // `tail` are published. Reads the consumer index but writes nothing
+    // unless the mark moves.
+    #[inline(always)]
+    fn note_occupancy(&self, tail: usize) {
+        let held = tail.wrapping_sub(self.consumer_index.load(Ordering::Relaxed)).min(self.capacity);
+        if held > self.high_water_mark.load(Ordering::Relaxed) {
+            self.high_water_mark.fetch_max(held, Ordering::Relaxed);
+        }
+    }
+
+    // Wake threads waiting in the wait strategy, and tasks registered for a wake-up
+    #[inline(always)]
+    fn notify(&self) {
+        self.wait.notify();
+        #[cfg(feature = "async")]
+        self.wakers.wake_all();
+    }
+
+    // The slot the next enqueue claims has been released by its last reader
+    #[inline(always)]
+    fn next_slot_free(&self) -> bool {
+        let seq = self.producer_index.load(Ordering::Acquire);
+        let slot = unsafe { self.buffer.get_unchecked(seq & self.mask) };
+        slot.sequence.load(Ordering::Acquire) >= seq
+    }
+
+    // The slot the next dequeue claims has been published
+    #[inline(always)]
+    fn next_item_published(&self) -> bool {
+        let seq = self.consumer_index.load(Ordering::Acquire);
+        let slot = unsafe { self.buffer.get_unchecked(seq & self.mask) };
+        slot.sequence.load(Ordering::Acquire) > seq
+    }
+
     #[inline(always)]
     pub fn is_empty(&self) -> bool {
         self.producer_index.load(Ordering::Relaxed) == self.consumer_index.load(Ordering::Relaxed)
@@ -149,4 +1184,65 @@ impl<T> LowLatencyMpmcRing<T> {
     pub fn capacity(&self) -> usize {
         self.capacity
     }
-} 
\ No newline at end of file
+
+    #[inline(always)]
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    /// Total items evicted by the DropOldest and Overwrite policies.
+    #[inline(always)]
+    pub fn evicted(&self) -> usize {
+        self.evicted.load(Ordering::Relaxed)
+    }
+
+    /// Read the ring's counters. Enqueues and dequeues are counted by the
+    /// indices themselves, so only full rings, lost claims and yields pay
+    /// for counting, plus one relaxed load per enqueue for the high-water mark.
+    pub fn stats(&self) -> RingStats {
+        // Evictions move the consumer index before they are counted
+        let evicted = self.evicted() as u64;
+        let dequeued = self.consumer_index.load(Ordering::Acquire).wrapping_sub(self.first_seq) as u64;
+        RingStats {
+            enqueued: self.producer_index.load(Ordering::Acquire).wrapping_sub(self.first_seq) as u64,
+            dequeued: dequeued.saturating_sub(evicted),
+            evicted,
+            rejected_full: self.counters.sum(REJECTED_FULL),
+            cas_retries: self.counters.sum(CAS_RETRIES),
+            yields: self.counters.sum(YIELDS),
+            high_water_mark: self.high_water_mark.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl<T> Drop for LowLatencyMpmcRing<T> {
+    fn drop(&mut self) {
+        // No other thread can touch the ring, so every published slot between
+        // the indices still owns its item
+        let tail = *self.producer_index.get_mut();
+        let mut seq = *self.consumer_index.get_mut();
+        while seq != tail {
+            let slot = &mut self.buffer[seq & self.mask];
+            if *slot.sequence.get_mut() == seq.wrapping_add(1) {
+                unsafe {
+                    slot.value.get_mut().assume_init_drop();
+                }
+            }
+            seq = seq.wrapping_add(1);
+        }
+    }
+}
+
+/// Iterator returned by `LowLatencyMpmcRing::drain`.
+pub struct Drain<'a, T> {
+    ring: &'a LowLatencyMpmcRing<T>,
+}
+
+impl<T> Iterator for Drain<'_, T> {
+    type Item = T;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<T> {
+        self.ring.try_dequeue().ok().flatten()
+    }
+}
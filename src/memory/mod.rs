@@ -1 +1,8 @@
-pub mod low_latency_mpmc_ring; 
\ No newline at end of file
+pub mod broadcast_ring;
+pub mod counters;
+pub mod low_latency_mpmc_ring; 
+pub(crate) mod shared_memory;
+pub mod slot_ring;
+pub mod wait_strategy;
+#[cfg(feature = "async")]
+pub(crate) mod wakers;
\ No newline at end of file
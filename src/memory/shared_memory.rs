@@ -0,0 +1,78 @@
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+
+use memmap2::MmapMut;
+
+use crate::error::TableError;
+
+// Memory-backed on Linux, so mapped pages never reach a disk
+const SHM_DIR: &str = "/dev/shm";
+
+/// A named region of memory that processes on the same host can all map.
+///
+/// Backed by a file in `/dev/shm`, or in the temp dir where there is none.
+/// The creator removes the name when dropped; processes that already
+/// attached keep their mapping until they drop it too.
+pub(crate) struct SharedMemory {
+    _map: MmapMut,
+    ptr: *mut u8,
+    len: usize,
+    path: PathBuf,
+    owner: bool,
+}
+
+impl SharedMemory {
+    /// Create region `name` of `len` zeroed bytes. Fails with `Io` if a
+    /// region of that name already exists, and with `InvalidName` unless
+    /// `name` is a plain file name.
+    pub(crate) fn create(name: &str, len: usize) -> Result<Self, TableError> {
+        let path = shm_path(name)?;
+        let file = OpenOptions::new().read(true).write(true).create_new(true).open(&path)?;
+        let map = file.set_len(len as u64)
+            .and_then(|()| unsafe { MmapMut::map_mut(&file) })
+            .inspect_err(|_| { let _ = fs::remove_file(&path); })?;
+        Ok(Self::new(map, path, true))
+    }
+
+    /// Map the existing region `name`.
+    pub(crate) fn open(name: &str) -> Result<Self, TableError> {
+        let path = shm_path(name)?;
+        let file = OpenOptions::new().read(true).write(true).open(&path)?;
+        let map = unsafe { MmapMut::map_mut(&file) }?;
+        Ok(Self::new(map, path, false))
+    }
+
+    #[inline(always)]
+    fn new(mut map: MmapMut, path: PathBuf, owner: bool) -> Self {
+        Self { ptr: map.as_mut_ptr(), len: map.len(), _map: map, path, owner }
+    }
+
+    /// Start of the mapping, which is page aligned. Every process sees other
+    /// processes' writes through it, so access goes through atomics or a
+    /// protocol built on them.
+    #[inline(always)]
+    pub(crate) fn ptr(&self) -> *mut u8 {
+        self.ptr
+    }
+
+    #[inline(always)]
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl Drop for SharedMemory {
+    fn drop(&mut self) {
+        if self.owner {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn shm_path(name: &str) -> Result<PathBuf, TableError> {
+    if name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+        return Err(TableError::InvalidName(name.to_owned()));
+    }
+    let dir = Path::new(SHM_DIR);
+    Ok(if dir.is_dir() { dir.to_path_buf() } else { std::env::temp_dir() }.join(name))
+}
@@ -0,0 +1,408 @@
+use std::cell::UnsafeCell;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+use crate::error::TableError;
+use crate::memory::shared_memory::SharedMemory;
+
+// Constants for performance tuning
+const CACHE_LINE_SIZE: usize = 64;
+const SPIN_LIMIT: u32 = 6;  // Optimal spin count before yielding
+
+// Slot sequence on its own cache line, same protocol as LowLatencyMpmcRing:
+// `seq` when free for the producer of `seq`, `seq + 1` once published
+#[repr(align(64))]
+struct Sequence {
+    value: AtomicUsize,
+    _padding: [u8; CACHE_LINE_SIZE - 8],
+}
+
+#[repr(align(64))]
+#[derive(Clone, Copy)]
+struct CacheLine {
+    _bytes: [u8; CACHE_LINE_SIZE],  // Only ever accessed through raw pointers
+}
+
+/// Zeroed, cache-line aligned bytes that slot guards write to through a
+/// shared reference. The sequence protocol decides who may touch which range.
+pub(crate) struct AlignedBytes {
+    lines: Box<[UnsafeCell<CacheLine>]>,
+    len: usize,
+}
+
+// Callers synchronize access through the sequence protocol
+unsafe impl Send for AlignedBytes {}
+unsafe impl Sync for AlignedBytes {}
+
+impl AlignedBytes {
+    pub(crate) fn zeroed(len: usize) -> Self {
+        let lines = (0..len.div_ceil(CACHE_LINE_SIZE))
+            .map(|_| UnsafeCell::new(CacheLine { _bytes: [0; CACHE_LINE_SIZE] }))
+            .collect();
+        Self { lines, len }
+    }
+
+    /// Pointer to byte `offset`. Callers must own `offset..offset + n` under
+    /// the sequence protocol before reading or writing through it.
+    #[inline(always)]
+    pub(crate) fn ptr(&self, offset: usize) -> *mut u8 {
+        debug_assert!(offset <= self.len);
+        unsafe { UnsafeCell::raw_get(self.lines.as_ptr()).cast::<u8>().add(offset) }
+    }
+}
+
+// Ring memory layout, in cache lines: header, producer index, consumer
+// index, one sequence per slot; then `capacity * slot_size` bytes of slots
+const PRODUCER_OFFSET: usize = CACHE_LINE_SIZE;
+const CONSUMER_OFFSET: usize = 2 * CACHE_LINE_SIZE;
+const SEQUENCES_OFFSET: usize = 3 * CACHE_LINE_SIZE;
+
+const MAGIC: u64 = u64::from_le_bytes(*b"ORTSRING");
+const VERSION: u32 = 1;
+
+// First cache line of the ring's memory. Lets another process check what it
+// is attaching to before touching anything else.
+#[repr(C, align(64))]
+struct Header {
+    magic: AtomicU64,  // Stored last by the creator, once the rest is set up
+    version: u32,
+    word_size: u32,  // Width of the indices and sequences
+    capacity: u64,
+    slot_size: u64,
+    layout: u64,  // Caller-defined tag for what the slot bytes mean
+    closed: AtomicBool,
+}
+
+// Where the ring's memory lives; the ring caches its start as `base`
+enum RingMemory {
+    Heap(AlignedBytes),
+    Shared(SharedMemory),
+}
+
+impl RingMemory {
+    #[inline(always)]
+    fn ptr(&self) -> *mut u8 {
+        match self {
+            RingMemory::Heap(bytes) => bytes.ptr(0),
+            RingMemory::Shared(shared) => shared.ptr(),
+        }
+    }
+}
+
+/// MPMC ring of fixed-size byte slots that producers fill in place.
+///
+/// Where `LowLatencyMpmcRing<T>` moves an owned value in and out of each
+/// slot, this ring pre-allocates `capacity * slot_size` contiguous bytes.
+/// `claim` lends a producer one slot's bytes and publishes them when the
+/// guard drops; `read` lends a consumer the bytes and frees the slot when
+/// that guard drops. No allocation or copy happens on either side.
+///
+/// The indices, sequences and slots all live in one block of memory, which
+/// `create_shared` places in a named shared mapping so that producers and
+/// consumers in other processes can `attach_shared` to the same ring.
+///
+/// `SharedTable` stores its rows here. `Table` does not: its rows stay in a
+/// `LowLatencyMpmcRing`, with fixed-width values kept inline beside it, so
+/// `Table::write_record` and `read_one_record` still box each value.
+/// `Table::write_values` and `Table::write` encode fixed-width values
+/// straight into the claimed slot instead, and `Table::read` decodes them
+/// where they lie.
+pub struct SlotRing {
+    base: *mut u8,
+    data_offset: usize,
+    slot_size: usize,
+    capacity: usize,
+    mask: usize,
+    _memory: RingMemory,
+}
+
+// Thread safety implementations
+unsafe impl Send for SlotRing {}
+unsafe impl Sync for SlotRing {}
+
+impl SlotRing {
+    #[inline(always)]
+    pub fn new(capacity: usize, slot_size: usize) -> Self {
+        let memory = RingMemory::Heap(AlignedBytes::zeroed(Self::memory_len(capacity, slot_size)));
+        unsafe { Self::init(memory, capacity, slot_size, 0) }
+    }
+
+    /// Create a ring in the shared memory region `name`, for other processes
+    /// to `attach_shared`. `layout` is stored in the header and must be
+    /// matched by every process that attaches, e.g. a hash of the schema the
+    /// slot bytes are encoded with. The name is removed when this ring is
+    /// dropped. Fails with `Io` if the region cannot be created, including
+    /// when the name is taken.
+    pub fn create_shared(name: &str, capacity: usize, slot_size: usize, layout: u64) -> Result<Self, TableError> {
+        assert!(capacity.is_power_of_two(), "Capacity must be a power of 2");
+        let memory = SharedMemory::create(name, Self::memory_len(capacity, slot_size))?;
+        Ok(unsafe { Self::init(RingMemory::Shared(memory), capacity, slot_size, layout) })
+    }
+
+    /// Attach to a ring another process made with `create_shared`, as a
+    /// producer, a consumer or both. Fails with `Corrupt` if the region does
+    /// not hold a ring of this version and `SchemaMismatch` if `layout`
+    /// differs from the creator's.
+    pub fn attach_shared(name: &str, layout: u64) -> Result<Self, TableError> {
+        let memory = SharedMemory::open(name)?;
+        let corrupt = |reason: &str| TableError::Corrupt(format!("shared ring `{}`: {}", name, reason));
+        if memory.len() < SEQUENCES_OFFSET {
+            return Err(corrupt("too short for a header"));
+        }
+
+        let header = unsafe { &*memory.ptr().cast::<Header>() };
+        if header.magic.load(Ordering::Acquire) != MAGIC {
+            return Err(corrupt("not an initialized slot ring"));
+        }
+        if header.version != VERSION || header.word_size as usize != size_of::<usize>() {
+            return Err(corrupt("unsupported version"));
+        }
+        let (capacity, slot_size) = (header.capacity as usize, header.slot_size as usize);
+        if !capacity.is_power_of_two() || memory.len() < Self::memory_len(capacity, slot_size) {
+            return Err(corrupt("header does not match the region size"));
+        }
+        if header.layout != layout {
+            return Err(TableError::SchemaMismatch(format!(
+                "shared ring `{}` has layout {:#x}, expected {:#x}", name, header.layout, layout
+            )));
+        }
+
+        let memory = RingMemory::Shared(memory);
+        Ok(Self {
+            base: memory.ptr(),
+            data_offset: Self::data_offset(capacity),
+            slot_size,
+            capacity,
+            mask: capacity - 1,
+            _memory: memory,
+        })
+    }
+
+    // Lay out a fresh ring in `memory`, which must be zeroed and at least
+    // `memory_len` bytes
+    unsafe fn init(memory: RingMemory, capacity: usize, slot_size: usize, layout: u64) -> Self {
+        assert!(capacity.is_power_of_two(), "Capacity must be a power of 2");
+
+        let base = memory.ptr();
+        let ring = Self {
+            base,
+            data_offset: Self::data_offset(capacity),
+            slot_size,
+            capacity,
+            mask: capacity - 1,
+            _memory: memory,
+        };
+        for i in 0..capacity {
+            ring.sequence(i).store(i, Ordering::Relaxed);
+        }
+        let header = unsafe { &mut *base.cast::<Header>() };
+        header.version = VERSION;
+        header.word_size = size_of::<usize>() as u32;
+        header.capacity = capacity as u64;
+        header.slot_size = slot_size as u64;
+        header.layout = layout;
+        header.magic.store(MAGIC, Ordering::Release);
+        ring
+    }
+
+    #[inline(always)]
+    fn data_offset(capacity: usize) -> usize {
+        SEQUENCES_OFFSET + capacity * size_of::<Sequence>()
+    }
+
+    #[inline(always)]
+    fn memory_len(capacity: usize, slot_size: usize) -> usize {
+        Self::data_offset(capacity) + capacity * slot_size
+    }
+
+    /// Claim the next slot for writing. The bytes still hold whatever was
+    /// last written there; the slot is published when the guard drops.
+    /// Fails with `Full` if the slot is still unread, and with `Closed` once
+    /// the ring is closed.
+    #[inline(always)]
+    pub fn claim(&self) -> Result<SlotGuard<'_>, TableError> {
+        if self.is_closed() {
+            return Err(TableError::Closed);
+        }
+        let producer_index = self.index(PRODUCER_OFFSET);
+        let mut spin_count = 0;
+        loop {
+            let seq = producer_index.load(Ordering::Acquire);
+            let slot_seq = self.sequence(seq).load(Ordering::Acquire);
+
+            if slot_seq == seq {
+                if producer_index.compare_exchange_weak(
+                    seq, seq.wrapping_add(1),
+                    Ordering::AcqRel, Ordering::Relaxed
+                ).is_ok() {
+                    return Ok(SlotGuard { ring: self, seq });
+                }
+            } else if slot_seq < seq {
+                return Err(TableError::Full);
+            } else {
+                spin_count += 1;
+                if spin_count > SPIN_LIMIT {
+                    std::thread::yield_now();
+                    spin_count = 0;
+                } else {
+                    std::hint::spin_loop();
+                }
+            }
+        }
+    }
+
+    /// Borrow the oldest published slot. Returns `Ok(None)` if the ring is
+    /// empty; the slot is handed back to producers when the guard drops.
+    #[inline(always)]
+    pub fn read(&self) -> Result<Option<ReadGuard<'_>>, TableError> {
+        let consumer_index = self.index(CONSUMER_OFFSET);
+        let mut spin_count = 0;
+        loop {
+            let seq = consumer_index.load(Ordering::Acquire);
+            let slot_seq = self.sequence(seq).load(Ordering::Acquire);
+
+            if slot_seq == seq.wrapping_add(1) {
+                if consumer_index.compare_exchange_weak(
+                    seq, seq.wrapping_add(1),
+                    Ordering::AcqRel, Ordering::Relaxed
+                ).is_ok() {
+                    return Ok(Some(ReadGuard { ring: self, seq }));
+                }
+            } else if slot_seq < seq.wrapping_add(1) {
+                return Ok(None);
+            } else {
+                spin_count += 1;
+                if spin_count > SPIN_LIMIT {
+                    std::thread::yield_now();
+                    spin_count = 0;
+                } else {
+                    std::hint::spin_loop();
+                }
+            }
+        }
+    }
+
+    /// Refuse further claims, in this and every attached process. Slots
+    /// already published can still be read.
+    #[inline(always)]
+    pub fn close(&self) {
+        self.header().closed.store(true, Ordering::Release);
+    }
+
+    #[inline(always)]
+    pub fn is_closed(&self) -> bool {
+        self.header().closed.load(Ordering::Acquire)
+    }
+
+    #[inline(always)]
+    fn header(&self) -> &Header {
+        unsafe { &*self.base.cast::<Header>() }
+    }
+
+    #[inline(always)]
+    fn index(&self, offset: usize) -> &AtomicUsize {
+        unsafe { &(*self.base.add(offset).cast::<Sequence>()).value }
+    }
+
+    #[inline(always)]
+    fn sequence(&self, seq: usize) -> &AtomicUsize {
+        let offset = SEQUENCES_OFFSET + (seq & self.mask) * size_of::<Sequence>();
+        unsafe { &(*self.base.add(offset).cast::<Sequence>()).value }
+    }
+
+    #[inline(always)]
+    fn slot_ptr(&self, seq: usize) -> *mut u8 {
+        unsafe { self.base.add(self.data_offset + (seq & self.mask) * self.slot_size) }
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.index(PRODUCER_OFFSET).load(Ordering::Relaxed) == self.index(CONSUMER_OFFSET).load(Ordering::Relaxed)
+    }
+
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    #[inline(always)]
+    pub fn slot_size(&self) -> usize {
+        self.slot_size
+    }
+
+    /// The tag given to `create_shared`; 0 for heap rings.
+    #[inline(always)]
+    pub fn layout(&self) -> u64 {
+        self.header().layout
+    }
+}
+
+/// A claimed slot. Writes go straight into the ring's storage; dropping the
+/// guard publishes the slot to consumers.
+pub struct SlotGuard<'a> {
+    ring: &'a SlotRing,
+    seq: usize,
+}
+
+impl SlotGuard<'_> {
+    /// Sequence number the slot was claimed at.
+    #[inline(always)]
+    pub fn seq(&self) -> usize {
+        self.seq
+    }
+}
+
+impl Deref for SlotGuard<'_> {
+    type Target = [u8];
+
+    #[inline(always)]
+    fn deref(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ring.slot_ptr(self.seq), self.ring.slot_size) }
+    }
+}
+
+impl DerefMut for SlotGuard<'_> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut [u8] {
+        // The claim made this guard the slot's only owner until it publishes
+        unsafe { std::slice::from_raw_parts_mut(self.ring.slot_ptr(self.seq), self.ring.slot_size) }
+    }
+}
+
+impl Drop for SlotGuard<'_> {
+    #[inline(always)]
+    fn drop(&mut self) {
+        self.ring.sequence(self.seq).store(self.seq.wrapping_add(1), Ordering::Release);
+    }
+}
+
+/// A slot lent to a consumer. Dropping the guard frees the slot for the
+/// producer one lap ahead.
+pub struct ReadGuard<'a> {
+    ring: &'a SlotRing,
+    seq: usize,
+}
+
+impl ReadGuard<'_> {
+    #[inline(always)]
+    pub fn seq(&self) -> usize {
+        self.seq
+    }
+}
+
+impl Deref for ReadGuard<'_> {
+    type Target = [u8];
+
+    #[inline(always)]
+    fn deref(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ring.slot_ptr(self.seq), self.ring.slot_size) }
+    }
+}
+
+impl Drop for ReadGuard<'_> {
+    #[inline(always)]
+    fn drop(&mut self) {
+        let next_lap = self.seq.wrapping_add(self.ring.capacity);
+        self.ring.sequence(self.seq).store(next_lap, Ordering::Release);
+    }
+}
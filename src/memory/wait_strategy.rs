@@ -0,0 +1,189 @@
+use std::sync::{Condvar, Mutex, PoisonError};
+use std::sync::atomic::{AtomicUsize, Ordering, fence};
+use std::time::{Duration, Instant};
+
+/// How a thread waits for a ring to change state: for an item to arrive, or
+/// for a slot to free up. Trades wake-up latency against CPU use.
+pub trait WaitStrategy: Send + Sync {
+    /// Wait until `ready` returns true or `deadline` passes. Returns whether
+    /// `ready` held. `ready` may be called many times and must be cheap.
+    fn wait_until(&self, ready: &dyn Fn() -> bool, deadline: Option<Instant>) -> bool;
+
+    /// Wake threads waiting in `wait_until`. Rings call this after every
+    /// publish, release and close; strategies that never sleep ignore it.
+    #[inline(always)]
+    fn notify(&self) {}
+}
+
+#[inline(always)]
+fn expired(deadline: Option<Instant>) -> bool {
+    deadline.is_some_and(|deadline| Instant::now() >= deadline)
+}
+
+/// Spin on the CPU without ever yielding. Lowest latency; burns a core per
+/// waiting thread.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BusySpin;
+
+impl WaitStrategy for BusySpin {
+    fn wait_until(&self, ready: &dyn Fn() -> bool, deadline: Option<Instant>) -> bool {
+        let mut spin_count: u32 = 0;
+        loop {
+            if ready() {
+                return true;
+            }
+            spin_count = spin_count.wrapping_add(1);
+            // Reading the clock costs more than a spin, so only do it now and then
+            if spin_count.is_multiple_of(64) && expired(deadline) {
+                return false;
+            }
+            std::hint::spin_loop();
+        }
+    }
+}
+
+/// Spin a few times, then yield the thread to the scheduler between checks.
+#[derive(Clone, Copy, Debug)]
+pub struct SpinThenYield {
+    pub spin_limit: u32,
+}
+
+impl Default for SpinThenYield {
+    #[inline(always)]
+    fn default() -> Self {
+        Self { spin_limit: 6 }
+    }
+}
+
+impl WaitStrategy for SpinThenYield {
+    fn wait_until(&self, ready: &dyn Fn() -> bool, deadline: Option<Instant>) -> bool {
+        let mut spin_count = 0;
+        loop {
+            if ready() {
+                return true;
+            }
+            spin_count += 1;
+            if spin_count > self.spin_limit {
+                if expired(deadline) {
+                    return false;
+                }
+                std::thread::yield_now();
+                spin_count = 0;
+            } else {
+                std::hint::spin_loop();
+            }
+        }
+    }
+}
+
+/// Spin, then yield, then park for exponentially growing intervals up to
+/// `max_park`. Near-idle CPU when nothing happens, at the cost of up to
+/// `max_park` extra latency on wake-up.
+#[derive(Clone, Copy, Debug)]
+pub struct BackoffPark {
+    pub spin_limit: u32,
+    pub yield_limit: u32,
+    pub max_park: Duration,
+}
+
+impl Default for BackoffPark {
+    #[inline(always)]
+    fn default() -> Self {
+        Self {
+            spin_limit: 6,
+            yield_limit: 16,
+            max_park: Duration::from_millis(1),
+        }
+    }
+}
+
+impl WaitStrategy for BackoffPark {
+    fn wait_until(&self, ready: &dyn Fn() -> bool, deadline: Option<Instant>) -> bool {
+        let mut attempt = 0;
+        let mut park = Duration::from_micros(1);
+        loop {
+            if ready() {
+                return true;
+            }
+            attempt += 1;
+            if attempt <= self.spin_limit {
+                std::hint::spin_loop();
+                continue;
+            }
+            if expired(deadline) {
+                return false;
+            }
+            if attempt <= self.spin_limit + self.yield_limit {
+                std::thread::yield_now();
+                continue;
+            }
+            let park_for = match deadline {
+                Some(deadline) => park.min(deadline.saturating_duration_since(Instant::now())),
+                None => park,
+            };
+            std::thread::park_timeout(park_for);
+            park = (park * 2).min(self.max_park);
+        }
+    }
+}
+
+/// Sleep on a condition variable until notified. Waiting costs no CPU;
+/// every publish and release pays one atomic load, plus a lock and wake-up
+/// while a thread is actually waiting.
+#[derive(Debug, Default)]
+pub struct Blocking {
+    lock: Mutex<()>,
+    condvar: Condvar,
+    waiters: AtomicUsize,
+}
+
+impl Blocking {
+    #[inline(always)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl WaitStrategy for Blocking {
+    fn wait_until(&self, ready: &dyn Fn() -> bool, deadline: Option<Instant>) -> bool {
+        if ready() {
+            return true;
+        }
+        let mut guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        // Registered before the re-check, so a notifier that changes the state
+        // after it either sees us waiting or its change is seen by the re-check
+        self.waiters.fetch_add(1, Ordering::SeqCst);
+        fence(Ordering::SeqCst);
+        let result = loop {
+            if ready() {
+                break true;
+            }
+            match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        break false;
+                    }
+                    guard = self.condvar.wait_timeout(guard, deadline - now)
+                        .unwrap_or_else(PoisonError::into_inner).0;
+                }
+                None => {
+                    guard = self.condvar.wait(guard).unwrap_or_else(PoisonError::into_inner);
+                }
+            }
+        };
+        self.waiters.fetch_sub(1, Ordering::SeqCst);
+        result
+    }
+
+    #[inline(always)]
+    fn notify(&self) {
+        // Pairs with the waiter's fence: the state change is visible before we look
+        fence(Ordering::SeqCst);
+        if self.waiters.load(Ordering::SeqCst) > 0 {
+            // Taking the lock orders us after a waiter's re-check
+            drop(self.lock.lock().unwrap_or_else(PoisonError::into_inner));
+            self.condvar.notify_all();
+        }
+    }
+}
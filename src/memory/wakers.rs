@@ -0,0 +1,43 @@
+use std::sync::{Mutex, PoisonError};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::Waker;
+
+/// Tasks waiting for a ring to change state. Each registration is woken
+/// once, by the next `wake_all`; a task that is still waiting afterwards
+/// registers again.
+#[derive(Default)]
+pub(crate) struct WakerList {
+    registered: AtomicUsize,
+    wakers: Mutex<Vec<Waker>>,
+}
+
+impl WakerList {
+    /// Register `waker`. Callers re-check the ring afterwards with SeqCst
+    /// loads of the indices: a claim made before the registration is visible
+    /// to that check, and one made after it wakes the task.
+    pub(crate) fn register(&self, waker: &Waker) {
+        let mut wakers = self.wakers.lock().unwrap_or_else(PoisonError::into_inner);
+        if !wakers.iter().any(|registered| registered.will_wake(waker)) {
+            wakers.push(waker.clone());
+        }
+        self.registered.store(wakers.len(), Ordering::SeqCst);
+    }
+
+    /// Wake every registered task. Costs one atomic load when none is waiting:
+    /// the ring's claims are SeqCst, so no fence is needed to order this load
+    /// after the change being announced.
+    #[inline(always)]
+    pub(crate) fn wake_all(&self) {
+        if self.registered.load(Ordering::SeqCst) == 0 {
+            return;
+        }
+        let wakers = {
+            let mut wakers = self.wakers.lock().unwrap_or_else(PoisonError::into_inner);
+            self.registered.store(0, Ordering::SeqCst);
+            std::mem::take(&mut *wakers)
+        };
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::error::TableError;
+use crate::memory::low_latency_mpmc_ring::OverflowPolicy;
+use crate::storage::schema::Schema;
+use crate::storage::snapshot::{self, Reader, Writer};
+use crate::storage::table::{FieldConfig, TableConfig};
+use crate::storage::wal::{SyncPolicy, WalConfig};
+
+const MAGIC: &[u8; 8] = b"ORTSCATL";
+const VERSION: u32 = 2;
+
+/// Write a database's catalog: each table's name and the parts of its config
+/// that describe it rather than the process running it.
+///
+/// Layout, little-endian, framed by `snapshot::write_file`: version, then per
+/// table its name, overflow policy, time and ingest time columns (empty for
+/// none), latency sampling rate (0 for none), strict mode, WAL settings, and
+/// columns (name, type, endianness, size, ring capacity, nullable, default).
+/// The clock, wait strategy and topology belong to the process and are not
+/// saved. Version 1 catalogs have no strict mode, nullable flags or defaults.
+pub(crate) fn write<'a>(
+    path: &Path,
+    tables: impl ExactSizeIterator<Item = (&'a str, &'a TableConfig)>,
+) -> Result<(), TableError> {
+    snapshot::write_file(path, MAGIC, |out| {
+        out.put_u32(VERSION)?;
+        out.put_u32(tables.len() as u32)?;
+        for (name, config) in tables {
+            out.put_str(name)?;
+            put_policy(out, config.overflow_policy)?;
+            out.put_str(config.time_column.as_deref().unwrap_or_default())?;
+            out.put_str(config.ingest_time_column.as_deref().unwrap_or_default())?;
+            out.put_u32(config.latency_sampling.unwrap_or(0))?;
+            out.put(&[config.strict as u8])?;
+            put_wal(out, config.wal.as_ref())?;
+
+            let columns = config.schema.columns();
+            out.put_u32(columns.len() as u32)?;
+            for column in columns {
+                out.put_str(&column.name)?;
+                out.put_data_type(column.config.data_type)?;
+                out.put(&[column.config.endianness as u8])?;
+                out.put_u64(column.config.field_size_bytes as u64)?;
+                out.put_u64(column.config.ring_capacity as u64)?;
+                out.put(&[column.config.nullable as u8])?;
+                match &column.config.default {
+                    Some(default) => {
+                        out.put(&[1])?;
+                        out.put_bytes(default)?;
+                    }
+                    None => out.put(&[0])?,
+                }
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Read a catalog written by `write`, of this version or the one before.
+/// Configs of tables with a WAL carry its settings with an empty directory,
+/// for the database to fill in.
+pub(crate) fn read(path: &Path) -> Result<Vec<(String, TableConfig)>, TableError> {
+    let body = snapshot::read_file(path, MAGIC)?;
+    let corrupt = |reason: &str| TableError::Corrupt(format!("{}: {}", path.display(), reason));
+    let mut input = Reader { bytes: &body };
+
+    let parse = |input: &mut Reader| -> Option<Vec<(String, Option<TableConfig>)>> {
+        let version = input.u32()?;
+        if version != 1 && version != VERSION {
+            return None;
+        }
+        let table_count = input.u32()? as usize;
+        let mut tables = Vec::with_capacity(table_count.min(1024));
+        for _ in 0..table_count {
+            let name = input.string()?;
+            let overflow_policy = read_policy(input)?;
+            let time_column = input.string()?;
+            let ingest_time_column = input.string()?;
+            let latency_sampling = input.u32()?;
+            let strict = version > 1 && input.flag()?;
+            let wal = read_wal(input)?;
+
+            let column_count = input.u32()? as usize;
+            let mut schema = Schema::builder();
+            for _ in 0..column_count {
+                let name = input.string()?;
+                let data_type = input.data_type()?;
+                let mut config = FieldConfig::new(data_type, 0).with_endianness(input.endianness()?);
+                config.field_size_bytes = input.u64()? as usize;
+                config.ring_capacity = input.u64()? as usize;
+                if version > 1 {
+                    config.nullable = input.flag()?;
+                    if input.flag()? {
+                        config.default = Some(Box::from(input.bytes()?));
+                    }
+                }
+                schema = schema.field(name, config);
+            }
+
+            // A schema that no longer builds is reported as corrupt below
+            let config = schema.build().ok().map(|schema| {
+                let mut config = TableConfig::from_schema(schema).with_overflow_policy(overflow_policy);
+                config.time_column = (!time_column.is_empty()).then(|| time_column.into());
+                config.ingest_time_column = (!ingest_time_column.is_empty()).then(|| ingest_time_column.into());
+                config.latency_sampling = (latency_sampling > 0).then_some(latency_sampling);
+                config.strict = strict;
+                config.wal = wal;
+                config
+            });
+            tables.push((name, config));
+        }
+        input.bytes.is_empty().then_some(tables)
+    };
+
+    parse(&mut input)
+        .ok_or_else(|| corrupt("malformed or unsupported version"))?
+        .into_iter()
+        .map(|(name, config)| match config {
+            Some(config) => Ok((name, config)),
+            None => Err(corrupt(&format!("invalid schema for table `{}`", name))),
+        })
+        .collect()
+}
+
+fn put_policy(out: &mut Writer, policy: OverflowPolicy) -> Result<(), TableError> {
+    match policy {
+        OverflowPolicy::Reject => out.put(&[0]),
+        OverflowPolicy::DropOldest => out.put(&[1]),
+        OverflowPolicy::Block { timeout } => {
+            out.put(&[2])?;
+            put_duration(out, timeout)
+        }
+        OverflowPolicy::Overwrite => out.put(&[3]),
+    }
+}
+
+fn read_policy(input: &mut Reader) -> Option<OverflowPolicy> {
+    Some(match input.take(1)?[0] {
+        0 => OverflowPolicy::Reject,
+        1 => OverflowPolicy::DropOldest,
+        2 => OverflowPolicy::Block { timeout: read_duration(input)? },
+        3 => OverflowPolicy::Overwrite,
+        _ => return None,
+    })
+}
+
+fn put_wal(out: &mut Writer, wal: Option<&WalConfig>) -> Result<(), TableError> {
+    let Some(wal) = wal else {
+        return out.put(&[0]);
+    };
+    out.put(&[1])?;
+    out.put_u64(wal.segment_bytes)?;
+    match wal.sync {
+        SyncPolicy::EveryWrite => out.put(&[0]),
+        SyncPolicy::GroupCommit(interval) => {
+            out.put(&[1])?;
+            put_duration(out, Some(interval))
+        }
+        SyncPolicy::Never => out.put(&[2]),
+    }
+}
+
+fn read_wal(input: &mut Reader) -> Option<Option<WalConfig>> {
+    if input.take(1)?[0] == 0 {
+        return Some(None);
+    }
+    let segment_bytes = input.u64()?;
+    let sync = match input.take(1)?[0] {
+        0 => SyncPolicy::EveryWrite,
+        1 => SyncPolicy::GroupCommit(read_duration(input)??),
+        2 => SyncPolicy::Never,
+        _ => return None,
+    };
+    Some(Some(WalConfig::new(PathBuf::new()).with_segment_bytes(segment_bytes).with_sync(sync)))
+}
+
+// u64::MAX nanoseconds stands for no duration
+fn put_duration(out: &mut Writer, duration: Option<Duration>) -> Result<(), TableError> {
+    let nanos = duration.map_or(u64::MAX, |duration| duration.as_nanos().min(u64::MAX as u128 - 1) as u64);
+    out.put_u64(nanos)
+}
+
+fn read_duration(input: &mut Reader) -> Option<Option<Duration>> {
+    let nanos = input.u64()?;
+    Some((nanos != u64::MAX).then(|| Duration::from_nanos(nanos)))
+}
@@ -0,0 +1,227 @@
+use std::sync::Arc;
+
+use crate::memory::slot_ring::AlignedBytes;
+use crate::storage::layout::{self, is_valid};
+use crate::storage::schema::Column;
+use crate::storage::table::Row;
+
+/// Fixed-width values of a table, stored inline by ring slot.
+///
+/// Every fixed-width field gets one contiguous, cache-aligned byte column of
+/// `capacity * field_size_bytes`; the value for sequence `seq` sits at
+/// `(seq & mask) * field_size_bytes`. Variable-length fields stay boxed in
+/// the row held by the ring, which then carries only those fields, in
+/// column order.
+///
+/// The ring's sequence protocol guards the bytes: a slot's values may only be
+/// written by the producer that claimed it, before publishing, and read by
+/// whoever holds it (a consumer before releasing it, or a pinned scanner).
+///
+/// Byte columns are shared with the columns of later schema versions that
+/// keep their field, so rows written under either version find their values.
+///
+/// Tables with nullable fields keep every row's validity bitmap inline too,
+/// with every bit set for rows without nulls; a null leaves its slot's bytes
+/// as they were.
+pub(crate) struct InlineColumns {
+    columns: Box<[Option<Arc<InlineColumn>>]>,  // Indexed like the table's columns
+    validity: Option<InlineColumn>,
+    all_valid: Box<[u8]>,
+    boxed: usize,  // Variable-length fields per row
+    mask: usize,
+}
+
+struct InlineColumn {
+    bytes: AlignedBytes,
+    width: usize,
+}
+
+impl InlineColumns {
+    pub(crate) fn new(columns: &[Column], capacity: usize) -> Self {
+        Self::build(columns, capacity, |_| None)
+    }
+
+    /// Columns for a new version of `previous`'s fields: `kept[idx]` is where
+    /// column `idx` was in `previous`, whose bytes it then shares, or None
+    /// for a field just added.
+    pub(crate) fn evolve(previous: &Self, columns: &[Column], kept: &[Option<usize>]) -> Self {
+        Self::build(columns, previous.mask + 1, |idx| kept[idx].and_then(|old| previous.columns[old].clone()))
+    }
+
+    fn build(columns: &[Column], capacity: usize, shared: impl Fn(usize) -> Option<Arc<InlineColumn>>) -> Self {
+        let validity = columns.iter().any(|column| column.config.nullable).then(|| InlineColumn {
+            bytes: AlignedBytes::zeroed(capacity * layout::validity_len(columns.len())),
+            width: layout::validity_len(columns.len()),
+        });
+        let all_valid = layout::all_valid(columns.len());
+        let columns: Box<[Option<Arc<InlineColumn>>]> = columns.iter().enumerate()
+            .map(|(idx, column)| {
+                column.config.data_type.fixed_width()?;
+                Some(shared(idx).unwrap_or_else(|| Arc::new(InlineColumn {
+                    bytes: AlignedBytes::zeroed(capacity * column.config.field_size_bytes),
+                    width: column.config.field_size_bytes,
+                })))
+            })
+            .collect();
+        Self {
+            boxed: columns.iter().filter(|column| column.is_none()).count(),
+            columns,
+            validity,
+            all_valid,
+            mask: capacity - 1,
+        }
+    }
+
+    /// Copy the fixed-width values of a validated `row` into slot `seq` and
+    /// return the variable-length rest for the ring.
+    ///
+    /// # Safety
+    /// The caller must have claimed `seq` and not yet published it.
+    #[inline(always)]
+    pub(crate) unsafe fn put(&self, seq: usize, row: Row) -> Row {
+        let mut values = row.into_vec();
+        if let Some(validity) = &self.validity {
+            let bits = (values.len() > self.columns.len()).then(|| values.pop()).flatten();
+            unsafe { self.write(validity, seq, bits.as_deref().unwrap_or(&self.all_valid)) }
+        }
+        let mut boxed = Vec::with_capacity(self.boxed);
+        for (column, value) in self.columns.iter().zip(values) {
+            match column {
+                // Only nulls are empty
+                Some(_) if value.is_empty() => {}
+                Some(column) => unsafe { self.write(column, seq, &value) },
+                None => boxed.push(value),
+            }
+        }
+        boxed.into_boxed_slice()
+    }
+
+    /// Store column `idx`'s value for slot `seq` inline. Returns false,
+    /// storing nothing, if the column is variable-length and so boxed.
+    ///
+    /// # Safety
+    /// As for `put`, after `put_valid`.
+    #[inline(always)]
+    pub(crate) unsafe fn put_value(&self, seq: usize, idx: usize, value: &[u8]) -> bool {
+        match &self.columns[idx] {
+            Some(column) => {
+                unsafe { self.write(column, seq, value) };
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Mark every column of slot `seq` as holding a value.
+    ///
+    /// # Safety
+    /// As for `put`.
+    #[inline(always)]
+    pub(crate) unsafe fn put_valid(&self, seq: usize) {
+        if let Some(validity) = &self.validity {
+            unsafe { self.write(validity, seq, &self.all_valid) }
+        }
+    }
+
+    /// Mark column `idx` of slot `seq` as null. Returns false if the column
+    /// is variable-length, whose empty value then goes in the boxed rest.
+    ///
+    /// # Safety
+    /// As for `put`, after `put_valid`; column `idx` must be nullable.
+    #[inline(always)]
+    pub(crate) unsafe fn put_null(&self, seq: usize, idx: usize) -> bool {
+        let validity = self.validity.as_ref().expect("a table with nullable fields");
+        let offset = (seq & self.mask) * validity.width + idx / 8;
+        unsafe { *validity.bytes.ptr(offset) &= !(1 << (idx % 8)) };
+        self.columns[idx].is_some()
+    }
+
+    /// Rebuild the full row for slot `seq`, consuming its boxed values.
+    ///
+    /// # Safety
+    /// The caller must hold `seq`: claimed from consumers and not yet released.
+    #[inline(always)]
+    pub(crate) unsafe fn take(&self, seq: usize, boxed: Row) -> Row {
+        let validity = unsafe { self.nulls(seq) };
+        let mut boxed = boxed.into_vec().into_iter();
+        let mut row: Vec<Box<[u8]>> = self.columns.iter().enumerate()
+            .map(|(idx, column)| match column {
+                Some(_) if validity.is_some_and(|bits| !is_valid(bits, idx)) => Box::default(),
+                Some(column) => Box::from(unsafe { self.slot(column, seq) }),
+                None => boxed.next().unwrap_or_default(),
+            })
+            .collect();
+        row.extend(validity.map(Box::from));
+        row.into_boxed_slice()
+    }
+
+    /// Copy out the full row for slot `seq`.
+    ///
+    /// # Safety
+    /// The caller must hold `seq` as for `take`, or have it pinned by
+    /// `LowLatencyMpmcRing::peek_with`.
+    #[inline(always)]
+    pub(crate) unsafe fn copy(&self, seq: usize, boxed: &Row) -> Row {
+        let validity = unsafe { self.nulls(seq) };
+        let mut boxed = boxed.iter();
+        let mut row: Vec<Box<[u8]>> = self.columns.iter().enumerate()
+            .map(|(idx, column)| match column {
+                Some(_) if validity.is_some_and(|bits| !is_valid(bits, idx)) => Box::default(),
+                Some(column) => Box::from(unsafe { self.slot(column, seq) }),
+                None => boxed.next().cloned().unwrap_or_default(),
+            })
+            .collect();
+        row.extend(validity.map(Box::from));
+        row.into_boxed_slice()
+    }
+
+    /// The validity bitmap of slot `seq`, if the row there has nulls.
+    ///
+    /// # Safety
+    /// As for `copy`, or as for `put` once the row is stored.
+    #[inline(always)]
+    pub(crate) unsafe fn nulls(&self, seq: usize) -> Option<&[u8]> {
+        let bits = unsafe { self.slot(self.validity.as_ref()?, seq) };
+        (*bits != *self.all_valid).then_some(bits)
+    }
+
+    /// Borrow one field of slot `seq` without copying the row.
+    ///
+    /// # Safety
+    /// As for `copy`.
+    #[inline(always)]
+    pub(crate) unsafe fn value<'a>(&'a self, seq: usize, boxed: &'a Row, idx: usize) -> &'a [u8] {
+        match &self.columns[idx] {
+            Some(column) => unsafe { self.slot(column, seq) },
+            None => {
+                let boxed_idx = self.columns[..idx].iter().filter(|column| column.is_none()).count();
+                &boxed[boxed_idx]
+            }
+        }
+    }
+
+    /// Borrow one field of slot `seq` as `value` does, or None if it is null.
+    ///
+    /// # Safety
+    /// As for `copy`.
+    #[inline(always)]
+    pub(crate) unsafe fn get<'a>(&'a self, seq: usize, boxed: &'a Row, idx: usize) -> Option<&'a [u8]> {
+        match unsafe { self.nulls(seq) } {
+            Some(bits) if !is_valid(bits, idx) => None,
+            _ => Some(unsafe { self.value(seq, boxed, idx) }),
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn slot<'a>(&self, column: &'a InlineColumn, seq: usize) -> &'a [u8] {
+        let offset = (seq & self.mask) * column.width;
+        unsafe { std::slice::from_raw_parts(column.bytes.ptr(offset), column.width) }
+    }
+
+    #[inline(always)]
+    unsafe fn write(&self, column: &InlineColumn, seq: usize, value: &[u8]) {
+        assert_eq!(value.len(), column.width, "inline value of the wrong width");
+        let offset = (seq & self.mask) * column.width;
+        unsafe { std::ptr::copy_nonoverlapping(value.as_ptr(), column.bytes.ptr(offset), column.width) }
+    }
+}
@@ -0,0 +1,441 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock, RwLockWriteGuard};
+
+use crate::clock::{Clock, SystemClock};
+use crate::error::TableError;
+use crate::storage::catalog;
+use crate::storage::layout;
+use crate::storage::record::Record;
+use crate::storage::snapshot::ColumnImage;
+use crate::storage::table::{FieldConfig, Row, Table, TableConfig};
+
+const CATALOG_FILE: &str = "catalog";
+const WAL_DIR: &str = "wal";  // Holds one directory per table with a WAL
+
+/// What a database's tables share.
+#[derive(Clone)]
+pub struct DatabaseConfig {
+    /// Where the catalog and the WAL of every table that has one are kept;
+    /// required by `Database::open`
+    pub dir: Option<PathBuf>,
+    /// Stamps the time columns of every table, replacing each table's own
+    pub clock: Arc<dyn Clock>,
+    /// Most bytes the tables may reserve together, as counted by
+    /// `TableConfig::reserved_bytes`
+    pub memory_budget: Option<usize>,
+}
+
+impl DatabaseConfig {
+    #[inline(always)]
+    pub fn new() -> Self {
+        Self {
+            dir: None,
+            clock: Arc::new(SystemClock),
+            memory_budget: None,
+        }
+    }
+
+    #[inline(always)]
+    pub fn with_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dir = Some(dir.into());
+        self
+    }
+
+    #[inline(always)]
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    #[inline(always)]
+    pub fn with_memory_budget(mut self, bytes: usize) -> Self {
+        self.memory_budget = Some(bytes);
+        self
+    }
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// A table, the config it was built from, and its share of the budget
+struct Entry {
+    table: Arc<Table>,
+    config: TableConfig,
+    reserved: usize,
+}
+
+impl Entry {
+    // The config with the fields the table has now
+    fn current_config(&self) -> TableConfig {
+        let mut config = self.config.clone();
+        config.schema = self.table.schema().clone();
+        config.time_column = self.table.time_column().map(Arc::from);
+        config
+    }
+}
+
+#[derive(Default)]
+struct Catalog {
+    tables: BTreeMap<Arc<str>, Entry>,
+    reserved: usize,
+}
+
+/// Named tables sharing a clock, a memory budget and a directory, so the
+/// services of one process can share an embedded instance and find tables
+/// by name.
+///
+/// Lookups take a read lock and hand out `Arc<Table>`s; reads and writes on
+/// a table never touch the catalog. Creating, altering and dropping tables,
+/// and adding and dropping their fields, are serialized, and with a
+/// directory each one rewrites the catalog file before it returns, so
+/// `Database::open` brings the same tables back. Fields changed on a table
+/// directly are only recorded by the next of these.
+pub struct Database {
+    config: DatabaseConfig,
+    catalog: RwLock<Catalog>,
+}
+
+impl Database {
+    /// Create an in-memory database. Databases with a directory are created
+    /// with `open`.
+    pub fn new(config: DatabaseConfig) -> Self {
+        assert!(config.dir.is_none(), "databases with a directory must be created with Database::open");
+        Self { config, catalog: RwLock::default() }
+    }
+
+    /// Open the database kept in `config.dir`, creating the directory if it
+    /// does not exist, and recreate the tables its catalog lists. Tables with
+    /// a WAL get back the rows that had not been read, as with `Table::open`.
+    /// Fails with `InvalidSchema` if `config` has no directory, or a table in
+    /// the catalog cannot be built from its config.
+    pub fn open(config: DatabaseConfig) -> Result<Self, TableError> {
+        let dir = config.dir.clone()
+            .ok_or_else(|| TableError::InvalidSchema("Database::open needs a directory".to_owned()))?;
+        fs::create_dir_all(&dir)?;
+        let database = Self { config, catalog: RwLock::default() };
+
+        let path = dir.join(CATALOG_FILE);
+        if path.exists() {
+            let mut catalog = database.lock_catalog();
+            for (name, config) in catalog::read(&path)? {
+                let name: Arc<str> = name.into();
+                let entry = database.build(&name, config, catalog.reserved, false)?;
+                catalog.reserved += entry.reserved;
+                catalog.tables.insert(name, entry);
+            }
+        }
+        Ok(database)
+    }
+
+    /// Create a table called `name`. Its time columns are stamped from the
+    /// database's clock, and a WAL, if `config` asks for one, goes in the
+    /// database's directory whatever directory `config` gives it. Fails with
+    /// `TableExists`, `InvalidName`, `OutOfMemory` if the table would not
+    /// fit the memory budget, or `InvalidSchema` if `config` cannot build a
+    /// table, as for `Table::open`, or asks for a WAL in a database without
+    /// a directory.
+    pub fn create_table(&self, name: impl Into<Arc<str>>, config: TableConfig) -> Result<Arc<Table>, TableError> {
+        let name = checked_name(name.into())?;
+        let mut catalog = self.lock_catalog();
+        if catalog.tables.contains_key(&name) {
+            return Err(TableError::TableExists(name.to_string()));
+        }
+
+        let entry = self.build(&name, config, catalog.reserved, true)?;
+        let table = Arc::clone(&entry.table);
+        catalog.reserved += entry.reserved;
+        catalog.tables.insert(Arc::clone(&name), entry);
+        if let Err(err) = self.persist(&catalog) {
+            let entry = catalog.tables.remove(&name).expect("the table just added");
+            catalog.reserved -= entry.reserved;
+            return Err(err);
+        }
+        Ok(table)
+    }
+
+    /// Look a table up by name.
+    pub fn table(&self, name: &str) -> Result<Arc<Table>, TableError> {
+        let catalog = self.catalog.read().unwrap_or_else(PoisonError::into_inner);
+        catalog.tables.get(name)
+            .map(|entry| Arc::clone(&entry.table))
+            .ok_or_else(|| TableError::NoSuchTable(name.to_owned()))
+    }
+
+    /// Names of every table, in order.
+    pub fn table_names(&self) -> Vec<Arc<str>> {
+        let catalog = self.catalog.read().unwrap_or_else(PoisonError::into_inner);
+        catalog.tables.keys().cloned().collect()
+    }
+
+    /// Replace table `name` with one built from its config as changed by
+    /// `alter`, for example with a larger capacity or another overflow
+    /// policy. The fields, and whether the table has a WAL, must stay the
+    /// same, or this fails with `SchemaMismatch`; fields change with
+    /// `add_field` and `drop_field`.
+    ///
+    /// Once the new table is built, the old one is shut down, so writers
+    /// still holding it get `Closed`, and its unread rows move to the new
+    /// one, replayed from the WAL if the table has one; if they no longer
+    /// fit, the newest are kept. Consumer groups start over, and readers
+    /// should look the new table up. If the new table cannot be built, the
+    /// old one stays in place, as it does if the catalog cannot be written;
+    /// a table with a WAL stops accepting writes while its log is replayed,
+    /// so writers may see `Closed` meanwhile.
+    pub fn alter_table(
+        &self,
+        name: &str,
+        alter: impl FnOnce(TableConfig) -> TableConfig,
+    ) -> Result<Arc<Table>, TableError> {
+        let mut catalog = self.lock_catalog();
+        let (name, old) = catalog.tables.get_key_value(name)
+            .ok_or_else(|| TableError::NoSuchTable(name.to_owned()))?;
+        let name = Arc::clone(name);
+        let current = old.current_config();
+        let config = alter(current.clone());
+
+        let columns = |config: &TableConfig| -> Vec<ColumnImage> {
+            config.schema.columns().iter().map(ColumnImage::of).collect()
+        };
+        if columns(&config) != columns(&current) || config.wal.is_some() != current.wal.is_some() {
+            return Err(TableError::SchemaMismatch(format!(
+                "altering `{}` cannot change its fields or whether it has a WAL",
+                name
+            )));
+        }
+        let reserved = catalog.reserved - old.reserved;
+        self.check_budget(config.reserved_bytes(), reserved)?;
+
+        let old = Arc::clone(&old.table);
+        let was_closed = old.is_closed();
+        let logged = config.wal.is_some();
+        let entry = if logged {
+            // The new table replays the old one's log, so the old one stops
+            // writing it and lets go of it first, and takes it back on failure
+            let built = old.shutdown()
+                .and_then(|()| old.close_wal())
+                .and_then(|()| self.build(&name, config, reserved, false));
+            match built {
+                Ok(entry) => entry,
+                Err(err) => {
+                    old.reopen(!was_closed)?;
+                    return Err(err);
+                }
+            }
+        } else {
+            self.build(&name, config, reserved, false)?
+        };
+
+        // Recorded before the old table gives up its rows, so it can be put
+        // back if the catalog cannot be written
+        let table = Arc::clone(&entry.table);
+        let was_reserved = catalog.reserved;
+        catalog.reserved = reserved + entry.reserved;
+        let old_entry = catalog.tables.insert(Arc::clone(&name), entry).expect("the table being altered");
+        if let Err(err) = self.persist(&catalog) {
+            catalog.tables.insert(name, old_entry);
+            catalog.reserved = was_reserved;
+            if logged {
+                table.shutdown()?;
+                table.close_wal()?;
+                old.reopen(!was_closed)?;
+            }
+            return Err(err);
+        }
+
+        if !logged {
+            old.shutdown()?;
+            let rows: Vec<Row> = old.drain().map(Record::into_row).collect();
+            let newest = rows.len().saturating_sub(table.capacity());
+            let version = table.version().index;
+            for row in rows.into_iter().skip(newest) {
+                table.store_row(version, &mut Some(row), false)?;
+            }
+        }
+        Ok(table)
+    }
+
+    /// Add a field to table `name` as `Table::add_field` does, and record it
+    /// in the catalog. Its inline values, and the new version's validity
+    /// bitmaps if any field is nullable, count against the memory budget, so
+    /// this may fail with `OutOfMemory`. If the catalog cannot be written,
+    /// the field is dropped again, and stays counted as `drop_field` says.
+    pub fn add_field(
+        &self,
+        name: &str,
+        field_name: impl Into<Arc<str>>,
+        config: FieldConfig,
+        default: Option<Box<[u8]>>,
+    ) -> Result<(), TableError> {
+        let mut catalog = self.lock_catalog();
+        let reserved = catalog.reserved;
+        let entry = catalog.tables.get_mut(name)
+            .ok_or_else(|| TableError::NoSuchTable(name.to_owned()))?;
+        let columns = entry.table.columns();
+        let nullable = config.nullable || columns.iter().any(|column| column.config.nullable);
+        let inline = match config.data_type.fixed_width() {
+            Some(_) => config.field_size_bytes,
+            None => 0,
+        };
+        let bytes = entry.table.capacity() * (inline + validity_len(columns.len() + 1, nullable));
+        self.check_budget(bytes, reserved)?;
+
+        let field_name = field_name.into();
+        entry.table.add_field(Arc::clone(&field_name), config, default)?;
+        let current = entry.current_config();
+        let before = std::mem::replace(&mut entry.config, current);
+        entry.reserved += bytes;
+        catalog.reserved += bytes;
+        if let Err(err) = self.persist(&catalog) {
+            let entry = catalog.tables.get_mut(name).expect("the table just changed");
+            entry.config = before;
+            entry.table.drop_field(&field_name)?;
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    /// Drop a field from table `name` as `Table::drop_field` does, and record
+    /// it in the catalog. The field's inline values stay counted against the
+    /// memory budget until the table is altered or dropped, and the new
+    /// version's validity bitmaps count too if a field left is nullable.
+    ///
+    /// If the catalog cannot be written, this fails but the field stays
+    /// dropped, since adding it back would not bring back its values; the
+    /// next change that writes the catalog records it.
+    pub fn drop_field(&self, name: &str, field_name: &str) -> Result<(), TableError> {
+        let mut catalog = self.lock_catalog();
+        let reserved = catalog.reserved;
+        let entry = catalog.tables.get_mut(name)
+            .ok_or_else(|| TableError::NoSuchTable(name.to_owned()))?;
+        let columns = entry.table.columns();
+        let nullable = columns.iter().any(|column| column.config.nullable && &*column.name != field_name);
+        let bytes = entry.table.capacity() * validity_len(columns.len().saturating_sub(1), nullable);
+        self.check_budget(bytes, reserved)?;
+
+        entry.table.drop_field(field_name)?;
+        entry.config = entry.current_config();
+        entry.reserved += bytes;
+        catalog.reserved += bytes;
+        self.persist(&catalog)
+    }
+
+    /// Remove table `name` from the database, deleting its WAL, and return it
+    /// shut down, so the rows it still holds can be drained. Every handle to
+    /// it shares that shutdown: writes through any of them fail with
+    /// `Closed`, and its log is closed before it is deleted.
+    pub fn drop_table(&self, name: &str) -> Result<Arc<Table>, TableError> {
+        let mut catalog = self.lock_catalog();
+        let (name, entry) = catalog.tables.remove_entry(name)
+            .ok_or_else(|| TableError::NoSuchTable(name.to_owned()))?;
+        if let Err(err) = self.persist(&catalog) {
+            catalog.tables.insert(name, entry);
+            return Err(err);
+        }
+        catalog.reserved -= entry.reserved;
+
+        entry.table.shutdown()?;
+        entry.table.close_wal()?;
+        if let Some(wal) = &entry.config.wal {
+            remove_dir(&wal.dir)?;
+        }
+        Ok(entry.table)
+    }
+
+    #[inline(always)]
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.config.clock
+    }
+
+    #[inline(always)]
+    pub fn dir(&self) -> Option<&Path> {
+        self.config.dir.as_deref()
+    }
+
+    #[inline(always)]
+    pub fn memory_budget(&self) -> Option<usize> {
+        self.config.memory_budget
+    }
+
+    /// Bytes the tables reserve together, as counted by the memory budget.
+    pub fn reserved_bytes(&self) -> usize {
+        self.catalog.read().unwrap_or_else(PoisonError::into_inner).reserved
+    }
+
+    // Build a table with the database's clock and WAL directory, within the
+    // budget left after `reserved`. A `fresh` table starts with an empty WAL.
+    fn build(&self, name: &Arc<str>, mut config: TableConfig, reserved: usize, fresh: bool) -> Result<Entry, TableError> {
+        let bytes = config.reserved_bytes();
+        self.check_budget(bytes, reserved)?;
+        config.clock = Arc::clone(&self.config.clock);
+
+        if let Some(wal) = config.wal.as_mut() {
+            let dir = self.config.dir.as_ref().ok_or_else(|| TableError::InvalidSchema(format!(
+                "`{}` has a WAL, which needs a database with a directory",
+                name
+            )))?;
+            wal.dir = dir.join(WAL_DIR).join(&**name);
+            if fresh {
+                // Left behind if the database stopped while dropping a table of this name
+                remove_dir(&wal.dir)?;
+            }
+        }
+        let table = Table::open(Arc::clone(name), config.clone())?;
+        Ok(Entry { table: Arc::new(table), config, reserved: bytes })
+    }
+
+    fn check_budget(&self, bytes: usize, reserved: usize) -> Result<(), TableError> {
+        match self.config.memory_budget {
+            Some(budget) if bytes > budget.saturating_sub(reserved) => Err(TableError::OutOfMemory {
+                requested: bytes,
+                available: budget.saturating_sub(reserved),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    // Rewrite the catalog file, if the database has a directory
+    fn persist(&self, catalog: &Catalog) -> Result<(), TableError> {
+        let Some(dir) = self.config.dir.as_ref() else {
+            return Ok(());
+        };
+        let tables = catalog.tables.iter().map(|(name, entry)| (&**name, &entry.config));
+        catalog::write(&dir.join(CATALOG_FILE), tables)
+    }
+
+    #[inline(always)]
+    fn lock_catalog(&self) -> RwLockWriteGuard<'_, Catalog> {
+        self.catalog.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+// Bytes per row of the validity bitmaps for `columns` fields, if any is nullable
+fn validity_len(columns: usize, nullable: bool) -> usize {
+    match nullable {
+        true => layout::validity_len(columns),
+        false => 0,
+    }
+}
+
+// Table names double as directory names
+fn checked_name(name: Arc<str>) -> Result<Arc<str>, TableError> {
+    let invalid = name.is_empty()
+        || matches!(&*name, "." | "..")
+        || name.contains(['/', '\\', '\0']);
+    if invalid {
+        return Err(TableError::InvalidName(name.to_string()));
+    }
+    Ok(name)
+}
+
+fn remove_dir(dir: &Path) -> Result<(), TableError> {
+    match fs::remove_dir_all(dir) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,440 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use crate::clock::Clock;
+use crate::error::TableError;
+use crate::storage::schema::{Column, DataType, Endianness, FieldId, FieldValue, Schema};
+use crate::storage::table::{Row, TableConfig};
+use crate::storage::table_record::{FieldWriter, TableRecord};
+
+/// How a table's records map to rows: the column order, which columns the
+/// table stamps itself, what fills in fields a record leaves out, and the
+/// clock it stamps from.
+///
+/// A row holds one value per column. A row with nulls carries one more
+/// value after them, its validity bitmap: bit `idx % 8` of byte `idx / 8` is
+/// set when column `idx` has a value. A null's own value is empty. Rows
+/// without nulls leave the bitmap out, so tables without nullable fields
+/// never pay for it.
+pub(crate) struct RowLayout {
+    pub(crate) columns: Arc<[Column]>,
+    pub(crate) version: u32,  // Of the schema the columns come from
+    pub(crate) time_column: Option<usize>,
+    pub(crate) ingest_time_column: Option<usize>,
+    pub(crate) clock: Arc<dyn Clock>,
+    pub(crate) strict: bool,
+    pub(crate) nullable: bool,  // Whether any column is
+}
+
+impl RowLayout {
+    /// Layout of `config`'s fields. Fails with `InvalidSchema` if a time
+    /// column is not a timestamp_ns field or both are the same one, or a
+    /// field's width or default does not fit its type.
+    pub(crate) fn try_new(config: &TableConfig) -> Result<Self, TableError> {
+        Self::try_of(
+            &config.schema,
+            config.time_column.as_deref(),
+            config.ingest_time_column.as_deref(),
+            Arc::clone(&config.clock),
+            config.strict,
+        )
+    }
+
+    /// Layout of `schema`, with the time columns and strictness as in
+    /// `TableConfig`. Fails as `try_new`.
+    pub(crate) fn try_of(
+        schema: &Schema,
+        time_column: Option<&str>,
+        ingest_time_column: Option<&str>,
+        clock: Arc<dyn Clock>,
+        strict: bool,
+    ) -> Result<Self, TableError> {
+        let columns = schema.shared_columns();
+
+        let timestamp_column = |field_name: &str| {
+            columns.iter()
+                .position(|column| &*column.name == field_name)
+                .filter(|&idx| columns[idx].config.data_type == DataType::TimestampNs)
+                .ok_or_else(|| TableError::InvalidSchema(format!(
+                    "time column `{}` must be a timestamp_ns field", field_name
+                )))
+        };
+        let time_column = match time_column {
+            Some(field_name) => Some(timestamp_column(field_name)?),
+            None => columns.iter().position(|column| column.config.data_type == DataType::TimestampNs),
+        };
+        let ingest_time_column = ingest_time_column.map(timestamp_column).transpose()?;
+        for column in columns.iter() {
+            if let Some(width) = column.config.data_type.fixed_width() {
+                if column.config.field_size_bytes != width {
+                    return Err(TableError::InvalidSchema(format!(
+                        "field `{}` is {} bytes wide but declares field_size_bytes {}",
+                        column.name, width, column.config.field_size_bytes
+                    )));
+                }
+            }
+            if let Some(default) = &column.config.default {
+                column.config.validate(&column.name, default)
+                    .map_err(|err| TableError::InvalidSchema(format!("invalid default: {}", err)))?;
+            }
+        }
+        if ingest_time_column.is_some() && ingest_time_column == time_column {
+            return Err(TableError::InvalidSchema("ingest time column must differ from the time column".to_owned()));
+        }
+
+        Ok(Self {
+            nullable: columns.iter().any(|column| column.config.nullable),
+            columns,
+            version: schema.version(),
+            time_column,
+            ingest_time_column,
+            clock,
+            strict,
+        })
+    }
+
+    /// Lay a record out as a row in column order. Time columns left out are
+    /// empty until `prepare_row` stamps them; other fields left out get
+    /// their default or a null, unless the layout is strict.
+    pub(crate) fn record_row<K>(&self, mut record: HashMap<K, Box<[u8]>>) -> Result<Row, TableError>
+    where
+        K: Borrow<str> + Eq + Hash,
+    {
+        let mut row = Vec::with_capacity(self.columns.len());
+        let mut validity = None;
+        for (idx, column) in self.columns.iter().enumerate() {
+            match record.remove(&*column.name) {
+                Some(data) => row.push(data),
+                None => row.push(self.missing(idx, &mut validity)?),
+            }
+        }
+        if let Some(field_name) = record.keys().next() {
+            return Err(TableError::UnknownField(field_name.borrow().to_owned()));
+        }
+        row.extend(validity);
+        Ok(row.into_boxed_slice())
+    }
+
+    /// Lay values addressed by `FieldId` out as a row, with the same rules as
+    /// `record_row`. A field given twice keeps its last value.
+    pub(crate) fn field_row(&self, values: impl IntoIterator<Item = (FieldId, Box<[u8]>)>) -> Result<Row, TableError> {
+        let mut row: Vec<Option<Box<[u8]>>> = vec![None; self.columns.len()];
+        for (id, data) in values {
+            self.check_id(id)?;
+            row[id.index] = Some(data);
+        }
+        let mut validity = None;
+        let mut row = row.into_iter().enumerate()
+            .map(|(idx, value)| match value {
+                Some(data) => Ok(data),
+                None => self.missing(idx, &mut validity),
+            })
+            // Same size as the boxes, so this reuses the allocation
+            .collect::<Result<Vec<_>, _>>()?;
+        row.extend(validity);
+        Ok(row.into_boxed_slice())
+    }
+
+    /// Check borrowed values addressed by `FieldId` as `field_row` checks
+    /// the values it lays out, without laying out a row.
+    pub(crate) fn check_values(&self, values: &[(FieldId, &[u8])]) -> Result<(), TableError> {
+        for &(id, _) in values {
+            self.check_id(id)?;
+        }
+        for (idx, column) in self.columns.iter().enumerate() {
+            match given(values, idx) {
+                // Checked as `prepare_row` checks, after `stamp` replaces what it stamps
+                Some(data) if self.stamps(idx, data) => {}
+                Some(data) => column.config.validate(&column.name, data)?,
+                None => {
+                    self.missing_value(idx)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Hand each column's value for values checked with `check_values` to
+    /// `store(idx, value)`, in column order, with the time columns stamped
+    /// and None for a null.
+    #[inline(always)]
+    pub(crate) fn store_values(&self, values: &[(FieldId, &[u8])], mut store: impl FnMut(usize, Option<&[u8]>)) {
+        let mut now_ns = None;
+        for idx in 0..self.columns.len() {
+            let value = match given(values, idx) {
+                Some(data) => Some(data),
+                None => self.missing_value(idx).expect("values checked against this layout"),
+            };
+            match value {
+                Some(data) if self.stamps(idx, data) => {
+                    let now_ns = *now_ns.get_or_insert_with(|| self.clock.now_ns());
+                    store(idx, Some(&timestamp_bytes(now_ns, self.columns[idx].config.endianness)));
+                }
+                value => store(idx, value),
+            }
+        }
+    }
+
+    // Fails unless `id` names a column of this version of the fields
+    #[inline(always)]
+    fn check_id(&self, id: FieldId) -> Result<(), TableError> {
+        if id.version != self.version {
+            return Err(TableError::SchemaMismatch(format!(
+                "field #{} was resolved against schema version {}, the table is at version {}",
+                id.index, id.version, self.version
+            )));
+        }
+        if id.index >= self.columns.len() {
+            return Err(TableError::UnknownField(format!("#{}", id.index)));
+        }
+        Ok(())
+    }
+
+    // The value of column `idx` for a record that leaves it out, clearing
+    // its bit in `validity`, created on the first null, if it is null
+    #[inline(always)]
+    fn missing(&self, idx: usize, validity: &mut Option<Box<[u8]>>) -> Result<Box<[u8]>, TableError> {
+        match self.missing_value(idx)? {
+            Some(value) => Ok(Box::from(value)),
+            None => {
+                set_null(validity.get_or_insert_with(|| all_valid(self.columns.len())), idx);
+                Ok(Box::default())
+            }
+        }
+    }
+
+    // As `missing`, borrowing the value, or None for a null
+    #[inline(always)]
+    fn missing_value(&self, idx: usize) -> Result<Option<&[u8]>, TableError> {
+        let config = &self.columns[idx].config;
+        if self.is_stamped(idx) {
+            // An empty value is never a valid timestamp, so it marks "stamp me"
+            return Ok(Some(&[]));
+        }
+        if self.strict {
+            return Err(TableError::MissingField(self.columns[idx].name.to_string()));
+        }
+        match &config.default {
+            Some(default) => Ok(Some(default)),
+            None if config.nullable => Ok(None),
+            None => Err(TableError::MissingField(self.columns[idx].name.to_string())),
+        }
+    }
+
+    /// Same checks as `record_row`, without taking the record apart.
+    pub(crate) fn check_record<K>(&self, record: &HashMap<K, Box<[u8]>>) -> Result<(), TableError>
+    where
+        K: Borrow<str> + Eq + Hash,
+    {
+        let mut present = 0;
+        for (idx, column) in self.columns.iter().enumerate() {
+            match record.get(&*column.name) {
+                Some(data) => {
+                    present += 1;
+                    // Checked as `prepare_row` checks, after `stamp` replaces what it stamps
+                    if !self.stamps(idx, data) {
+                        column.config.validate(&column.name, data)?;
+                    }
+                }
+                None => {
+                    self.missing(idx, &mut None)?;
+                }
+            }
+        }
+        if present < record.len() {
+            let unknown = record.keys()
+                .map(|name| name.borrow())
+                .find(|&name| self.columns.binary_search_by(|column| (*column.name).cmp(name)).is_err());
+            return Err(TableError::UnknownField(unknown.unwrap_or_default().to_owned()));
+        }
+        Ok(())
+    }
+
+    /// Move a checked record's values into a row, leaving it empty.
+    #[inline(always)]
+    pub(crate) fn take_row<K>(&self, record: &mut HashMap<K, Box<[u8]>>) -> Row
+    where
+        K: Borrow<str> + Eq + Hash,
+    {
+        let mut validity = None;
+        let mut row: Vec<Box<[u8]>> = self.columns.iter().enumerate()
+            .map(|(idx, column)| match record.remove(&*column.name) {
+                Some(data) => data,
+                None => self.missing(idx, &mut validity).unwrap_or_default(),
+            })
+            .collect();
+        row.extend(validity);
+        row.into_boxed_slice()
+    }
+
+    /// Stamp and validate a row before its first store attempt.
+    #[inline(always)]
+    pub(crate) fn prepare_row(&self, row: &mut Row) -> Result<(), TableError> {
+        self.stamp(row);
+        self.check_row(row)
+    }
+
+    /// Check every value of a complete row against its column, and that
+    /// only nullable columns are null.
+    pub(crate) fn check_row(&self, row: &Row) -> Result<(), TableError> {
+        let columns = self.columns.len();
+        let validity = match row.len().checked_sub(columns) {
+            Some(0) => None,
+            Some(1) if self.nullable && row[columns].len() == validity_len(columns) => Some(&*row[columns]),
+            _ => return Err(TableError::SchemaMismatch("row does not match the table's columns".to_owned())),
+        };
+        for (idx, (column, data)) in self.columns.iter().zip(row.iter()).enumerate() {
+            match validity {
+                Some(validity) if !is_valid(validity, idx) => {
+                    if !column.config.nullable {
+                        return Err(TableError::MissingField(column.name.to_string()));
+                    }
+                }
+                _ => column.config.validate(&column.name, data)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Check a typed record against the columns, as `check_record` does,
+    /// without storing it.
+    #[inline(always)]
+    pub(crate) fn check_typed<T: TableRecord>(&self, record: &T) -> Result<(), TableError> {
+        let mut writer = RecordWriter { layout: self, next: 0, store: None::<fn(usize, Option<&[u8]>)> };
+        record.encode(&mut writer)?;
+        match self.columns.get(writer.next) {
+            Some(column) => Err(TableError::MissingField(column.name.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// Hand each value of a record checked with `check_typed` to
+    /// `store(idx, value)`, with the ingest time stamped in and None for a
+    /// null.
+    #[inline(always)]
+    pub(crate) fn store_typed<T: TableRecord>(&self, record: &T, store: impl FnMut(usize, Option<&[u8]>)) {
+        let mut writer = RecordWriter { layout: self, next: 0, store: Some(store) };
+        record.encode(&mut writer).expect("a record checked against this layout");
+    }
+
+    /// Fill in the ingest time, and the event time if the producer left it out.
+    #[inline(always)]
+    pub(crate) fn stamp(&self, row: &mut Row) {
+        let event_time_missing = self.time_column.is_some_and(|idx| row[idx].is_empty());
+        if !event_time_missing && self.ingest_time_column.is_none() {
+            return;
+        }
+
+        let now_ns = self.clock.now_ns();
+        for idx in [self.time_column, self.ingest_time_column].into_iter().flatten() {
+            if self.stamps(idx, &row[idx]) {
+                row[idx] = now_ns.encode(self.columns[idx].config.endianness);
+            }
+        }
+    }
+
+    // Whether `stamp` replaces `data` in column `idx`: ingest time always,
+    // event time when it was left empty
+    #[inline(always)]
+    fn stamps(&self, idx: usize, data: &[u8]) -> bool {
+        Some(idx) == self.ingest_time_column || (Some(idx) == self.time_column && data.is_empty())
+    }
+
+    #[inline(always)]
+    fn is_stamped(&self, idx: usize) -> bool {
+        Some(idx) == self.time_column || Some(idx) == self.ingest_time_column
+    }
+
+    /// Name of the designated time column, if there is one.
+    #[inline(always)]
+    pub(crate) fn time_column_name(&self) -> Option<&str> {
+        self.time_column.map(|idx| &*self.columns[idx].name)
+    }
+
+    #[inline(always)]
+    pub(crate) fn ingest_time_column_name(&self) -> Option<&str> {
+        self.ingest_time_column.map(|idx| &*self.columns[idx].name)
+    }
+}
+
+// Feeds a typed record's values to `store`, or only checks them without one
+struct RecordWriter<'a, F> {
+    layout: &'a RowLayout,
+    next: usize,
+    store: Option<F>,
+}
+
+impl<F: FnMut(usize, Option<&[u8]>)> FieldWriter for RecordWriter<'_, F> {
+    #[inline(always)]
+    fn columns(&self) -> &[Column] {
+        &self.layout.columns
+    }
+
+    #[inline(always)]
+    fn put(&mut self, value: Option<&[u8]>) -> Result<(), TableError> {
+        let idx = self.next;
+        let column = self.layout.columns.get(idx)
+            .ok_or_else(|| TableError::SchemaMismatch("record has more fields than the table".to_owned()))?;
+        self.next += 1;
+        let Some(store) = self.store.as_mut() else {
+            // Ingest time is always replaced, so whatever was sent is ignored
+            if Some(idx) != self.layout.ingest_time_column {
+                match value {
+                    Some(value) => column.config.validate(&column.name, value)?,
+                    None if column.config.nullable => {}
+                    None => return Err(TableError::MissingField(column.name.to_string())),
+                }
+            }
+            return Ok(());
+        };
+        if Some(idx) == self.layout.ingest_time_column {
+            store(idx, Some(&timestamp_bytes(self.layout.clock.now_ns(), column.config.endianness)));
+        } else {
+            store(idx, value);
+        }
+        Ok(())
+    }
+}
+
+// The value given for column `idx`, the last if it was given more than once
+#[inline(always)]
+fn given<'a>(values: &[(FieldId, &'a [u8])], idx: usize) -> Option<&'a [u8]> {
+    values.iter().rev().find(|(id, _)| id.index == idx).map(|&(_, data)| data)
+}
+
+#[inline(always)]
+fn timestamp_bytes(now_ns: u64, endianness: Endianness) -> [u8; 8] {
+    match endianness {
+        Endianness::Little => now_ns.to_le_bytes(),
+        Endianness::Big => now_ns.to_be_bytes(),
+    }
+}
+
+/// Bytes in the validity bitmap of a row of `columns` columns.
+#[inline(always)]
+pub(crate) fn validity_len(columns: usize) -> usize {
+    columns.div_ceil(8)
+}
+
+/// A validity bitmap with every column of `columns` set.
+#[inline(always)]
+pub(crate) fn all_valid(columns: usize) -> Box<[u8]> {
+    (0..validity_len(columns))
+        .map(|byte| match columns - byte * 8 {
+            bits if bits >= 8 => 0xFF,
+            bits => (1u8 << bits) - 1,
+        })
+        .collect()
+}
+
+#[inline(always)]
+pub(crate) fn is_valid(validity: &[u8], idx: usize) -> bool {
+    validity[idx / 8] & (1 << (idx % 8)) != 0
+}
+
+#[inline(always)]
+pub(crate) fn set_null(validity: &mut [u8], idx: usize) {
+    validity[idx / 8] &= !(1 << (idx % 8));
+}
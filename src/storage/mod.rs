@@ -1 +1,17 @@
-pub mod table; 
\ No newline at end of file
+pub(crate) mod catalog;
+pub(crate) mod checksum;
+pub(crate) mod columns;
+pub mod database;
+pub(crate) mod layout;
+pub mod record;
+pub mod scan;
+pub mod schema;
+pub mod shared_table;
+pub(crate) mod snapshot;
+#[cfg(feature = "async")]
+pub mod stream;
+pub mod subscription;
+pub mod table;
+pub mod table_record;
+pub(crate) mod versions;
+pub mod wal;
\ No newline at end of file
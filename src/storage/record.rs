@@ -0,0 +1,195 @@
+use std::sync::Arc;
+
+use crate::error::TableError;
+use crate::storage::layout::is_valid;
+use crate::storage::schema::{Column, DataType, FieldValue};
+use crate::storage::table::Row;
+use crate::storage::table_record::FieldReader;
+
+/// A row read back from a table, paired with the table's fields at the time
+/// it was read.
+///
+/// A nullable field may have no value in a row. `get` and the `*_opt`
+/// readers return None for it, while the other readers fail with `Null`.
+pub struct Record {
+    columns: Arc<[Column]>,
+    values: Row,
+}
+
+impl Record {
+    #[inline(always)]
+    pub(crate) fn new(columns: Arc<[Column]>, values: Row) -> Self {
+        Self { columns, values }
+    }
+
+    #[inline(always)]
+    fn position(&self, field_name: &str) -> Result<usize, TableError> {
+        self.columns.binary_search_by(|column| (*column.name).cmp(field_name))
+            .map_err(|_| TableError::UnknownField(field_name.to_owned()))
+    }
+
+    // None if the field is null in this row
+    #[inline(always)]
+    fn value(&self, idx: usize) -> Option<&[u8]> {
+        // Rows without nulls carry no validity bitmap
+        match self.values.get(self.columns.len()) {
+            Some(validity) if !is_valid(validity, idx) => None,
+            _ => Some(&self.values[idx]),
+        }
+    }
+
+    #[inline(always)]
+    fn null(&self, idx: usize) -> TableError {
+        null(&self.columns[idx])
+    }
+
+    /// Raw bytes of a field, or None if it is unknown or null.
+    #[inline(always)]
+    pub fn get(&self, field_name: &str) -> Option<&[u8]> {
+        self.position(field_name).ok().and_then(|idx| self.value(idx))
+    }
+
+    /// Whether a field is null in this row. Fails if it is unknown.
+    #[inline(always)]
+    pub fn is_null(&self, field_name: &str) -> Result<bool, TableError> {
+        self.position(field_name).map(|idx| self.value(idx).is_none())
+    }
+
+    /// Decode a fixed-width field. Fails if the field is unknown, null, or
+    /// declared with a type that cannot hold `T`.
+    #[inline(always)]
+    pub fn get_as<T: FieldValue>(&self, field_name: &str) -> Result<T, TableError> {
+        let idx = self.position(field_name)?;
+        self.decode(idx)?.ok_or_else(|| self.null(idx))
+    }
+
+    /// Decode a fixed-width field, or None if it is null.
+    #[inline(always)]
+    pub fn get_opt<T: FieldValue>(&self, field_name: &str) -> Result<Option<T>, TableError> {
+        self.decode(self.position(field_name)?)
+    }
+
+    #[inline(always)]
+    fn decode<T: FieldValue>(&self, idx: usize) -> Result<Option<T>, TableError> {
+        decode_value(&self.columns[idx], self.value(idx))
+    }
+
+    /// Borrow a `bytes(N)` or `varbinary` field.
+    #[inline(always)]
+    pub fn get_bytes(&self, field_name: &str) -> Result<&[u8], TableError> {
+        let idx = self.position(field_name)?;
+        self.bytes(idx)?.ok_or_else(|| self.null(idx))
+    }
+
+    /// Borrow a `bytes(N)` or `varbinary` field, or None if it is null.
+    #[inline(always)]
+    pub fn get_bytes_opt(&self, field_name: &str) -> Result<Option<&[u8]>, TableError> {
+        self.bytes(self.position(field_name)?)
+    }
+
+    #[inline(always)]
+    fn bytes(&self, idx: usize) -> Result<Option<&[u8]>, TableError> {
+        bytes_value(&self.columns[idx], self.value(idx))
+    }
+
+    /// Borrow a `utf8` field.
+    #[inline(always)]
+    pub fn get_str(&self, field_name: &str) -> Result<&str, TableError> {
+        let idx = self.position(field_name)?;
+        self.str(idx)?.ok_or_else(|| self.null(idx))
+    }
+
+    /// Borrow a `utf8` field, or None if it is null.
+    #[inline(always)]
+    pub fn get_str_opt(&self, field_name: &str) -> Result<Option<&str>, TableError> {
+        self.str(self.position(field_name)?)
+    }
+
+    #[inline(always)]
+    fn str(&self, idx: usize) -> Result<Option<&str>, TableError> {
+        str_value(&self.columns[idx], self.value(idx))
+    }
+
+    /// Number of fields, null or not.
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.columns.len()
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    /// Field names and raw bytes in column order, leaving out nulls.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[u8])> {
+        self.columns.iter().enumerate()
+            .filter_map(|(idx, column)| Some((&*column.name, self.value(idx)?)))
+    }
+
+    /// Consume the record into its raw row: a value per column, then the
+    /// validity bitmap if any field is null.
+    #[inline(always)]
+    pub fn into_row(self) -> Row {
+        self.values
+    }
+}
+
+impl FieldReader for Record {
+    #[inline(always)]
+    fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    #[inline(always)]
+    fn value(&self, idx: usize) -> Option<&[u8]> {
+        Record::value(self, idx)
+    }
+}
+
+/// The error for reading `column` as a value where it is null.
+#[inline(always)]
+pub(crate) fn null(column: &Column) -> TableError {
+    TableError::Null(column.name.to_string())
+}
+
+#[inline(always)]
+fn type_mismatch(column: &Column) -> TableError {
+    TableError::TypeMismatch {
+        field: column.name.to_string(),
+        expected: column.config.data_type,
+    }
+}
+
+/// Decode `value`, stored for `column`, as a fixed-width `T`; None if null.
+#[inline(always)]
+pub(crate) fn decode_value<T: FieldValue>(column: &Column, value: Option<&[u8]>) -> Result<Option<T>, TableError> {
+    let config = &column.config;
+    if !T::matches(config.data_type) {
+        return Err(type_mismatch(column));
+    }
+    value
+        .map(|value| T::decode(value, config.endianness).ok_or_else(|| type_mismatch(column)))
+        .transpose()
+}
+
+/// `value` if `column` is a `bytes(N)` or `varbinary` field.
+#[inline(always)]
+pub(crate) fn bytes_value<'a>(column: &Column, value: Option<&'a [u8]>) -> Result<Option<&'a [u8]>, TableError> {
+    match column.config.data_type {
+        DataType::Bytes(_) | DataType::VarBinary => Ok(value),
+        _ => Err(type_mismatch(column)),
+    }
+}
+
+/// `value` as text if `column` is a `utf8` field.
+#[inline(always)]
+pub(crate) fn str_value<'a>(column: &Column, value: Option<&'a [u8]>) -> Result<Option<&'a str>, TableError> {
+    match column.config.data_type {
+        // Validated on write
+        DataType::Utf8 => value
+            .map(|value| std::str::from_utf8(value).map_err(|_| type_mismatch(column)))
+            .transpose(),
+        _ => Err(type_mismatch(column)),
+    }
+}
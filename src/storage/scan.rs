@@ -0,0 +1,59 @@
+use std::ops::Range;
+
+use crate::memory::low_latency_mpmc_ring::LowLatencyMpmcRing;
+use crate::storage::record::Record;
+use crate::storage::schema::FieldValue;
+use crate::storage::table::Row;
+use crate::storage::versions::SchemaVersions;
+
+/// Non-destructive iterator over the rows a table still holds whose time
+/// column falls in `[from_ts, to_ts)`, oldest sequence first.
+///
+/// Rows consumed or evicted while the scan is running are skipped; rows
+/// written after the scan started are not visited. Each record has the
+/// fields the table has when it is visited.
+pub struct Scan<'a> {
+    rows: &'a LowLatencyMpmcRing<Row>,
+    versions: &'a SchemaVersions,
+    time_range: Range<u64>,
+    seqs: Range<usize>,
+}
+
+impl<'a> Scan<'a> {
+    #[inline(always)]
+    pub(crate) fn new(rows: &'a LowLatencyMpmcRing<Row>, versions: &'a SchemaVersions, time_range: Range<u64>) -> Self {
+        Self {
+            seqs: rows.retained(),
+            rows,
+            versions,
+            time_range,
+        }
+    }
+}
+
+impl Iterator for Scan<'_> {
+    type Item = Record;
+
+    fn next(&mut self) -> Option<Record> {
+        for seq in self.seqs.by_ref() {
+            // Only rows inside the range are copied out; the filter reads the
+            // time column in place. Safe while `peek_with` pins the slot, and
+            // the version is loaded after pinning so it is no older than the row.
+            let record = self.rows.peek_with(seq, |boxed| unsafe {
+                let version = self.versions.current();
+                let time_column = version.layout.time_column?;
+                let endianness = version.layout.columns[time_column].config.endianness;
+                let ts = u64::decode(self.versions.value(seq, boxed, version, time_column), endianness)?;
+                self.time_range.contains(&ts).then(|| self.versions.copy(seq, boxed))
+            });
+            if let Some(Some(record)) = record {
+                return Some(record);
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.seqs.len()))
+    }
+}
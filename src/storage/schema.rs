@@ -0,0 +1,239 @@
+use std::sync::Arc;
+
+use crate::error::TableError;
+use crate::storage::table::FieldConfig;
+
+/// Logical type of a field's bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataType {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
+    Bool,
+    /// Nanoseconds since the Unix epoch, stored as a u64
+    TimestampNs,
+    /// Exactly N opaque bytes
+    Bytes(usize),
+    /// Variable-length opaque bytes
+    VarBinary,
+    /// Variable-length UTF-8 text
+    Utf8,
+}
+
+impl DataType {
+    /// Encoded width in bytes, or None for variable-length types.
+    #[inline(always)]
+    pub const fn fixed_width(&self) -> Option<usize> {
+        match self {
+            DataType::I8 | DataType::U8 | DataType::Bool => Some(1),
+            DataType::I16 | DataType::U16 => Some(2),
+            DataType::I32 | DataType::U32 | DataType::F32 => Some(4),
+            DataType::I64 | DataType::U64 | DataType::F64 | DataType::TimestampNs => Some(8),
+            DataType::Bytes(n) => Some(*n),
+            DataType::VarBinary | DataType::Utf8 => None,
+        }
+    }
+}
+
+/// Byte order of multi-byte numeric fields.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Endianness {
+    #[default]
+    Little,
+    Big,
+}
+
+/// A named field within a table's row layout.
+#[derive(Clone)]
+pub struct Column {
+    pub name: Arc<str>,
+    pub config: FieldConfig,
+}
+
+/// A field resolved against a schema once, so writes can address it by
+/// position instead of hashing its name. Only meaningful for the schema it
+/// was resolved from: once a table's fields change, writes with ids from an
+/// earlier version fail with `SchemaMismatch`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FieldId {
+    pub(crate) index: usize,
+    pub(crate) version: u32,
+}
+
+impl FieldId {
+    /// Position of the field in the table's column order.
+    #[inline(always)]
+    pub fn index(self) -> usize {
+        self.index
+    }
+}
+
+/// The fields of a table. Names are owned, so schemas can come from a config
+/// file or a request at runtime, and are shared by every row and record read
+/// back. Built with `Schema::builder`; a table's schema changes only through
+/// `Table::add_field` and `Table::drop_field`, each giving a new version.
+#[derive(Clone)]
+pub struct Schema {
+    columns: Arc<[Column]>,  // Sorted by name
+    version: u32,
+}
+
+impl Schema {
+    #[inline(always)]
+    pub fn builder() -> SchemaBuilder {
+        SchemaBuilder { columns: Vec::new() }
+    }
+
+    /// Resolve `field_name` for writes by `FieldId`.
+    #[inline(always)]
+    pub fn field_id(&self, field_name: &str) -> Option<FieldId> {
+        self.columns.binary_search_by(|column| (*column.name).cmp(field_name))
+            .ok()
+            .map(|index| FieldId { index, version: self.version })
+    }
+
+    /// Config of the field called `field_name`.
+    #[inline(always)]
+    pub fn field(&self, field_name: &str) -> Option<&FieldConfig> {
+        self.field_id(field_name).map(|id| &self.columns[id.index].config)
+    }
+
+    /// Fields in column order, which is by name.
+    #[inline(always)]
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// Always false: a schema has at least one field.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    /// 0 for a built schema, and one more for each change to a table's fields.
+    #[inline(always)]
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    #[inline(always)]
+    pub(crate) fn shared_columns(&self) -> Arc<[Column]> {
+        Arc::clone(&self.columns)
+    }
+
+    #[inline(always)]
+    pub(crate) fn with_version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+}
+
+/// Collects fields for a `Schema`, checking them in `build`.
+pub struct SchemaBuilder {
+    columns: Vec<Column>,
+}
+
+impl SchemaBuilder {
+    #[inline(always)]
+    pub fn field(mut self, name: impl Into<Arc<str>>, config: FieldConfig) -> Self {
+        self.columns.push(Column { name: name.into(), config });
+        self
+    }
+
+    /// Fails with `InvalidSchema` if there are no fields, a field has an
+    /// empty name, or two fields share a name.
+    pub fn build(mut self) -> Result<Schema, TableError> {
+        if self.columns.is_empty() {
+            return Err(TableError::InvalidSchema("a schema needs at least one field".to_owned()));
+        }
+        if self.columns.iter().any(|column| column.name.is_empty()) {
+            return Err(TableError::InvalidSchema("field names must not be empty".to_owned()));
+        }
+        // Column order is fixed here so every row shares one layout
+        self.columns.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+        if let Some(pair) = self.columns.windows(2).find(|pair| pair[0].name == pair[1].name) {
+            return Err(TableError::InvalidSchema(format!("duplicate field `{}`", pair[0].name)));
+        }
+        Ok(Schema { columns: self.columns.into(), version: 0 })
+    }
+}
+
+/// Fixed-width primitives that can be encoded into and decoded from a field.
+pub trait FieldValue: Sized + Copy {
+    /// Whether a field of the given type can hold this value.
+    fn matches(data_type: DataType) -> bool;
+    fn decode(bytes: &[u8], endianness: Endianness) -> Option<Self>;
+    fn encode(self, endianness: Endianness) -> Box<[u8]>;
+}
+
+macro_rules! impl_field_value {
+    ($ty:ty, $($data_type:pat_param)|+) => {
+        impl FieldValue for $ty {
+            #[inline(always)]
+            fn matches(data_type: DataType) -> bool {
+                matches!(data_type, $($data_type)|+)
+            }
+
+            #[inline(always)]
+            fn decode(bytes: &[u8], endianness: Endianness) -> Option<Self> {
+                let bytes = bytes.try_into().ok()?;
+                Some(match endianness {
+                    Endianness::Little => <$ty>::from_le_bytes(bytes),
+                    Endianness::Big => <$ty>::from_be_bytes(bytes),
+                })
+            }
+
+            #[inline(always)]
+            fn encode(self, endianness: Endianness) -> Box<[u8]> {
+                match endianness {
+                    Endianness::Little => Box::from(self.to_le_bytes()),
+                    Endianness::Big => Box::from(self.to_be_bytes()),
+                }
+            }
+        }
+    };
+}
+
+impl_field_value!(i8, DataType::I8);
+impl_field_value!(i16, DataType::I16);
+impl_field_value!(i32, DataType::I32);
+impl_field_value!(i64, DataType::I64);
+impl_field_value!(u8, DataType::U8);
+impl_field_value!(u16, DataType::U16);
+impl_field_value!(u32, DataType::U32);
+impl_field_value!(u64, DataType::U64 | DataType::TimestampNs);
+impl_field_value!(f32, DataType::F32);
+impl_field_value!(f64, DataType::F64);
+
+impl FieldValue for bool {
+    #[inline(always)]
+    fn matches(data_type: DataType) -> bool {
+        data_type == DataType::Bool
+    }
+
+    #[inline(always)]
+    fn decode(bytes: &[u8], _endianness: Endianness) -> Option<Self> {
+        match bytes {
+            [0] => Some(false),
+            [1] => Some(true),
+            _ => None,
+        }
+    }
+
+    #[inline(always)]
+    fn encode(self, _endianness: Endianness) -> Box<[u8]> {
+        Box::from([self as u8])
+    }
+}
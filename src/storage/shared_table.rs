@@ -0,0 +1,216 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use crate::error::TableError;
+use crate::memory::low_latency_mpmc_ring::OverflowPolicy;
+use crate::memory::slot_ring::SlotRing;
+use crate::storage::layout::RowLayout;
+use crate::storage::record::Record;
+use crate::storage::schema::{Column, FieldId};
+use crate::storage::snapshot::data_type_tag;
+use crate::storage::table::{Row, TableConfig};
+use crate::storage::table_record::{FieldReader, TableRecord};
+
+/// A table whose rows live in a named shared-memory ring, so a producer and
+/// a consumer in separate processes can use the same table. Created with
+/// `Table::create_shared` and opened elsewhere with `Table::attach_shared`.
+///
+/// Every field must be fixed-width and not nullable: a row is one ring slot
+/// holding the values back to back in column order. Writes are stamped and
+/// validated as for `Table`, and fail with `Full` when the ring is full.
+/// Consumer groups, scans, the WAL and snapshots need per-process state and
+/// are only available on heap tables.
+pub struct SharedTable {
+    pub name: Arc<str>,
+    layout: RowLayout,
+    offsets: Box<[usize]>,  // Start of each column within a slot
+    rows: SlotRing,
+}
+
+impl SharedTable {
+    pub(crate) fn create(name: Arc<str>, config: TableConfig) -> Result<Self, TableError> {
+        let capacity = config.checked_capacity()?;
+        let (layout, offsets, slot_size) = Self::slot_layout(&name, &config)?;
+        let rows = SlotRing::create_shared(&region_name(&name), capacity, slot_size, fingerprint(&layout.columns))?;
+        Ok(Self { name, layout, offsets, rows })
+    }
+
+    pub(crate) fn attach(name: Arc<str>, config: TableConfig) -> Result<Self, TableError> {
+        let (layout, offsets, _) = Self::slot_layout(&name, &config)?;
+        let rows = SlotRing::attach_shared(&region_name(&name), fingerprint(&layout.columns))?;
+        Ok(Self { name, layout, offsets, rows })
+    }
+
+    // Column offsets within a slot, and the slot size
+    fn slot_layout(name: &str, config: &TableConfig) -> Result<(RowLayout, Box<[usize]>, usize), TableError> {
+        if config.wal.is_some() {
+            return Err(TableError::SchemaMismatch(format!("shared table `{}` cannot have a WAL", name)));
+        }
+        if !matches!(config.overflow_policy, OverflowPolicy::Reject) {
+            return Err(TableError::SchemaMismatch(format!(
+                "shared table `{}` only supports OverflowPolicy::Reject, not {:?}",
+                name, config.overflow_policy
+            )));
+        }
+        let layout = RowLayout::try_new(config)?;
+        let mut offsets = Vec::with_capacity(layout.columns.len());
+        let mut slot_size = 0;
+        for column in layout.columns.iter() {
+            if column.config.data_type.fixed_width().is_none() {
+                return Err(TableError::SchemaMismatch(format!(
+                    "shared table `{}` can only hold fixed-width fields, `{}` is {:?}",
+                    name, column.name, column.config.data_type
+                )));
+            }
+            if column.config.nullable {
+                return Err(TableError::SchemaMismatch(format!(
+                    "shared table `{}` cannot hold nulls, `{}` is nullable",
+                    name, column.name
+                )));
+            }
+            offsets.push(slot_size);
+            slot_size += column.config.field_size_bytes;
+        }
+        Ok((layout, offsets.into(), slot_size))
+    }
+
+    /// Write one record as a single row, as `Table::write_record` does.
+    #[inline(always)]
+    pub fn write_record<K>(&self, record: HashMap<K, Box<[u8]>>) -> Result<(), TableError>
+    where
+        K: Borrow<str> + Eq + Hash,
+    {
+        self.insert_row(self.layout.record_row(record)?)
+    }
+
+    /// Write one record by `FieldId`, as `Table::write_fields` does.
+    #[inline(always)]
+    pub fn write_fields(&self, values: impl IntoIterator<Item = (FieldId, Box<[u8]>)>) -> Result<(), TableError> {
+        self.insert_row(self.layout.field_row(values)?)
+    }
+
+    /// Write a typed record into a table created from `T::table_config`,
+    /// encoding its fields straight into the claimed slot.
+    #[inline(always)]
+    pub fn write<T: TableRecord>(&self, record: &T) -> Result<(), TableError> {
+        self.layout.check_typed(record)?;
+        let mut slot = self.rows.claim()?;
+        // Checked, so never null: no field here is nullable
+        self.layout.store_typed(record, |idx, value| {
+            let value = value.unwrap_or_default();
+            let offset = self.offsets[idx];
+            slot[offset..offset + value.len()].copy_from_slice(value);
+        });
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn insert_row(&self, mut row: Row) -> Result<(), TableError> {
+        self.layout.prepare_row(&mut row)?;
+        let mut slot = self.rows.claim()?;
+        for (&offset, value) in self.offsets.iter().zip(row.iter()) {
+            slot[offset..offset + value.len()].copy_from_slice(value);
+        }
+        Ok(())
+    }
+
+    /// Read the oldest row back as a record, whichever process wrote it.
+    /// Returns `Ok(None)` if the table is empty.
+    #[inline(always)]
+    pub fn read_one_record(&self) -> Result<Option<Record>, TableError> {
+        let Some(slot) = self.rows.read()? else {
+            return Ok(None);
+        };
+        let row = self.layout.columns.iter().zip(self.offsets.iter())
+            .map(|(column, &offset)| Box::from(&slot[offset..offset + column.config.field_size_bytes]))
+            .collect();
+        Ok(Some(Record::new(Arc::clone(&self.layout.columns), row)))
+    }
+
+    /// Read the oldest row as a typed record, decoded straight from its slot.
+    #[inline(always)]
+    pub fn read<T: TableRecord>(&self) -> Result<Option<T>, TableError> {
+        let Some(slot) = self.rows.read()? else {
+            return Ok(None);
+        };
+        T::decode(&SlotFields { table: self, slot: &slot }).map(Some)
+    }
+
+    /// Refuse further writes from every attached process. Rows already
+    /// written can still be read.
+    #[inline(always)]
+    pub fn shutdown(&self) {
+        self.rows.close();
+    }
+
+    #[inline(always)]
+    pub fn is_closed(&self) -> bool {
+        self.rows.is_closed()
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Fields in the order they are stored within a row.
+    #[inline(always)]
+    pub fn columns(&self) -> &[Column] {
+        &self.layout.columns
+    }
+
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.rows.capacity()
+    }
+}
+
+// The values of one row, back to back in a slot the reader holds
+struct SlotFields<'a> {
+    table: &'a SharedTable,
+    slot: &'a [u8],
+}
+
+impl FieldReader for SlotFields<'_> {
+    #[inline(always)]
+    fn columns(&self) -> &[Column] {
+        &self.table.layout.columns
+    }
+
+    #[inline(always)]
+    fn value(&self, idx: usize) -> Option<&[u8]> {
+        let offset = self.table.offsets[idx];
+        Some(&self.slot[offset..offset + self.table.layout.columns[idx].config.field_size_bytes])
+    }
+}
+
+// Shared memory region holding the table `name`
+#[inline(always)]
+fn region_name(name: &str) -> String {
+    format!("orts-{}", name)
+}
+
+// Processes attaching to a table must agree on every column's encoding, so
+// the column descriptors are hashed into all 64 bits of the ring's layout tag
+// (FNV-1a)
+fn fingerprint(columns: &[Column]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xCBF2_9CE4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01B3;
+
+    let mut hash = OFFSET_BASIS;
+    let mut update = |bytes: &[u8]| {
+        for &byte in bytes {
+            hash = (hash ^ u64::from(byte)).wrapping_mul(PRIME);
+        }
+    };
+    for column in columns {
+        let (tag, width) = data_type_tag(column.config.data_type);
+        update(column.name.as_bytes());
+        update(&[0, tag, column.config.endianness as u8]);
+        update(&(width as u64).to_le_bytes());
+        update(&(column.config.field_size_bytes as u64).to_le_bytes());
+    }
+    hash
+}
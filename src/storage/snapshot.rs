@@ -0,0 +1,355 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::error::TableError;
+use crate::storage::checksum::{crc32, Crc32};
+use crate::storage::schema::{Column, DataType, Endianness};
+use crate::storage::table::Row;
+
+const MAGIC: &[u8; 8] = b"ORTSSNAP";
+const VERSION: u32 = 2;
+
+/// A consumer group's cursor as saved in a snapshot.
+pub(crate) struct GroupImage {
+    pub(crate) name: String,
+    pub(crate) position: usize,
+    pub(crate) skipped: usize,
+}
+
+/// Everything a snapshot file holds.
+///
+/// Layout, little-endian, followed by a CRC-32 of all preceding bytes:
+/// magic, version, table name, columns (name, type, endianness, size,
+/// nullable), sequence of the first row, rows (whether a validity bitmap
+/// follows the values, then per value `[len: u32][bytes]`), and the consumer
+/// groups (name, position, skipped). Version 1 files have neither the
+/// nullable flags nor the bitmaps.
+pub(crate) struct SnapshotImage {
+    pub(crate) table: String,
+    pub(crate) columns: Vec<ColumnImage>,
+    pub(crate) start: usize,
+    pub(crate) rows: Vec<Row>,
+    pub(crate) groups: Vec<GroupImage>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct ColumnImage {
+    pub(crate) name: String,
+    pub(crate) data_type: DataType,
+    pub(crate) endianness: Endianness,
+    pub(crate) field_size_bytes: usize,
+    pub(crate) nullable: bool,
+}
+
+impl ColumnImage {
+    #[inline(always)]
+    pub(crate) fn of(column: &Column) -> Self {
+        Self {
+            name: column.name.to_string(),
+            data_type: column.config.data_type,
+            endianness: column.config.endianness,
+            field_size_bytes: column.config.field_size_bytes,
+            nullable: column.config.nullable,
+        }
+    }
+}
+
+/// Tells apart the temp files of writes in flight at once.
+static TEMP_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Write `magic`, whatever `body` writes and a CRC-32 of it all to `path`
+/// atomically: the file only appears once complete and synced, so a crash
+/// mid-write leaves any previous one intact. Each write goes through its own
+/// temp file, so concurrent writes to one path never mix; the last rename
+/// wins.
+pub(crate) fn write_file(
+    path: &Path,
+    magic: &[u8; 8],
+    body: impl FnOnce(&mut Writer) -> Result<(), TableError>,
+) -> Result<(), TableError> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(".{}.{}.tmp", std::process::id(), TEMP_ID.fetch_add(1, Ordering::Relaxed)));
+    let written = File::create(&tmp).map_err(TableError::from).and_then(|file| {
+        let mut out = Writer { out: BufWriter::new(file), crc: Crc32::new() };
+        out.put(magic)?;
+        body(&mut out)?;
+
+        let crc = out.crc.finish();
+        out.out.write_all(&crc.to_le_bytes())?;
+        let file = out.out.into_inner().map_err(|err| err.into_error())?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    });
+    if written.is_err() {
+        let _ = fs::remove_file(&tmp);
+        return written;
+    }
+    sync_parent(path)
+}
+
+/// Sync the directory holding `path`, so a rename into it survives a crash.
+#[cfg(unix)]
+fn sync_parent(path: &Path) -> Result<(), TableError> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    File::open(dir)?.sync_all()?;
+    Ok(())
+}
+
+/// Directories cannot be opened for syncing here; the rename is left to the
+/// file system.
+#[cfg(not(unix))]
+fn sync_parent(_path: &Path) -> Result<(), TableError> {
+    Ok(())
+}
+
+/// Read a file written by `write_file` with `magic`, returning what `body`
+/// wrote. Fails with `Corrupt` if the magic or checksum do not match.
+pub(crate) fn read_file(path: &Path, magic: &[u8; 8]) -> Result<Vec<u8>, TableError> {
+    let mut bytes = fs::read(path)?;
+    let corrupt = |reason: &str| TableError::Corrupt(format!("{}: {}", path.display(), reason));
+    if bytes.len() < magic.len() + 4 || &bytes[..magic.len()] != magic {
+        return Err(corrupt("wrong file type"));
+    }
+    let (body, crc) = bytes.split_at(bytes.len() - 4);
+    if crc32(body) != u32::from_le_bytes(crc.try_into().unwrap()) {
+        return Err(corrupt("checksum mismatch"));
+    }
+    bytes.truncate(bytes.len() - 4);
+    bytes.drain(..magic.len());
+    Ok(bytes)
+}
+
+/// Write `image` to `path` atomically.
+pub(crate) fn write(path: &Path, image: &SnapshotImage) -> Result<(), TableError> {
+    write_file(path, MAGIC, |out| write_image(out, image))
+}
+
+fn write_image(out: &mut Writer, image: &SnapshotImage) -> Result<(), TableError> {
+    out.put_u32(VERSION)?;
+    out.put_str(&image.table)?;
+    out.put_u32(image.columns.len() as u32)?;
+    for column in &image.columns {
+        out.put_str(&column.name)?;
+        out.put_data_type(column.data_type)?;
+        out.put(&[column.endianness as u8])?;
+        out.put_u64(column.field_size_bytes as u64)?;
+        out.put(&[column.nullable as u8])?;
+    }
+    out.put_u64(image.start as u64)?;
+    out.put_u64(image.rows.len() as u64)?;
+    for row in &image.rows {
+        out.put(&[(row.len() > image.columns.len()) as u8])?;
+        for value in row.iter() {
+            out.put_bytes(value)?;
+        }
+    }
+    out.put_u32(image.groups.len() as u32)?;
+    for group in &image.groups {
+        out.put_str(&group.name)?;
+        out.put_u64(group.position as u64)?;
+        out.put_u64(group.skipped as u64)?;
+    }
+    Ok(())
+}
+
+/// Read and checksum a snapshot file, of this version or the one before.
+/// Row values are not yet checked against any table.
+pub(crate) fn read(path: &Path) -> Result<SnapshotImage, TableError> {
+    let body = read_file(path, MAGIC)?;
+    let mut input = Reader { bytes: &body };
+    let parse = |input: &mut Reader| -> Option<SnapshotImage> {
+        let version = input.u32()?;
+        if version != 1 && version != VERSION {
+            return None;
+        }
+        let table = input.string()?;
+        let column_count = input.u32()? as usize;
+        let mut columns = Vec::with_capacity(column_count.min(1024));
+        for _ in 0..column_count {
+            columns.push(ColumnImage {
+                name: input.string()?,
+                data_type: input.data_type()?,
+                endianness: input.endianness()?,
+                field_size_bytes: input.u64()? as usize,
+                nullable: version > 1 && input.flag()?,
+            });
+        }
+
+        let start = input.u64()? as usize;
+        let row_count = input.u64()? as usize;
+        let mut rows = Vec::with_capacity(row_count.min(1 << 20));
+        for _ in 0..row_count {
+            let values = columns.len() + (version > 1 && input.flag()?) as usize;
+            let row: Option<Row> = (0..values).map(|_| input.bytes().map(Box::from)).collect();
+            rows.push(row?);
+        }
+
+        let group_count = input.u32()? as usize;
+        let mut groups = Vec::with_capacity(group_count.min(1024));
+        for _ in 0..group_count {
+            groups.push(GroupImage {
+                name: input.string()?,
+                position: input.u64()? as usize,
+                skipped: input.u64()? as usize,
+            });
+        }
+        input.bytes.is_empty().then_some(SnapshotImage { table, columns, start, rows, groups })
+    };
+    parse(&mut input)
+        .ok_or_else(|| TableError::Corrupt(format!("{}: malformed or unsupported version", path.display())))
+}
+
+#[inline(always)]
+pub(crate) fn data_type_tag(data_type: DataType) -> (u8, usize) {
+    match data_type {
+        DataType::I8 => (0, 0),
+        DataType::I16 => (1, 0),
+        DataType::I32 => (2, 0),
+        DataType::I64 => (3, 0),
+        DataType::U8 => (4, 0),
+        DataType::U16 => (5, 0),
+        DataType::U32 => (6, 0),
+        DataType::U64 => (7, 0),
+        DataType::F32 => (8, 0),
+        DataType::F64 => (9, 0),
+        DataType::Bool => (10, 0),
+        DataType::TimestampNs => (11, 0),
+        DataType::Bytes(n) => (12, n),
+        DataType::VarBinary => (13, 0),
+        DataType::Utf8 => (14, 0),
+    }
+}
+
+#[inline(always)]
+fn data_type_from_tag(tag: u8, width: usize) -> Option<DataType> {
+    Some(match tag {
+        0 => DataType::I8,
+        1 => DataType::I16,
+        2 => DataType::I32,
+        3 => DataType::I64,
+        4 => DataType::U8,
+        5 => DataType::U16,
+        6 => DataType::U32,
+        7 => DataType::U64,
+        8 => DataType::F32,
+        9 => DataType::F64,
+        10 => DataType::Bool,
+        11 => DataType::TimestampNs,
+        12 => DataType::Bytes(width),
+        13 => DataType::VarBinary,
+        14 => DataType::Utf8,
+        _ => return None,
+    })
+}
+
+/// Buffered writer that checksums everything it writes.
+pub(crate) struct Writer {
+    out: BufWriter<File>,
+    crc: Crc32,
+}
+
+impl Writer {
+    #[inline(always)]
+    pub(crate) fn put(&mut self, bytes: &[u8]) -> Result<(), TableError> {
+        self.crc.update(bytes);
+        self.out.write_all(bytes)?;
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub(crate) fn put_u32(&mut self, value: u32) -> Result<(), TableError> {
+        self.put(&value.to_le_bytes())
+    }
+
+    #[inline(always)]
+    pub(crate) fn put_u64(&mut self, value: u64) -> Result<(), TableError> {
+        self.put(&value.to_le_bytes())
+    }
+
+    #[inline(always)]
+    pub(crate) fn put_bytes(&mut self, bytes: &[u8]) -> Result<(), TableError> {
+        self.put_u32(bytes.len() as u32)?;
+        self.put(bytes)
+    }
+
+    #[inline(always)]
+    pub(crate) fn put_str(&mut self, value: &str) -> Result<(), TableError> {
+        self.put_bytes(value.as_bytes())
+    }
+
+    #[inline(always)]
+    pub(crate) fn put_data_type(&mut self, data_type: DataType) -> Result<(), TableError> {
+        let (tag, width) = data_type_tag(data_type);
+        self.put(&[tag])?;
+        self.put_u64(width as u64)
+    }
+}
+
+/// Parses what a `Writer` wrote; every read is None once the bytes run out.
+pub(crate) struct Reader<'a> {
+    pub(crate) bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    #[inline(always)]
+    pub(crate) fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.bytes.len() < n {
+            return None;
+        }
+        let (taken, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Some(taken)
+    }
+
+    #[inline(always)]
+    pub(crate) fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    #[inline(always)]
+    pub(crate) fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    #[inline(always)]
+    pub(crate) fn bytes(&mut self) -> Option<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    #[inline(always)]
+    pub(crate) fn string(&mut self) -> Option<String> {
+        std::str::from_utf8(self.bytes()?).ok().map(str::to_owned)
+    }
+
+    #[inline(always)]
+    pub(crate) fn data_type(&mut self) -> Option<DataType> {
+        let tag = self.take(1)?[0];
+        let width = self.u64()? as usize;
+        data_type_from_tag(tag, width)
+    }
+
+    #[inline(always)]
+    pub(crate) fn endianness(&mut self) -> Option<Endianness> {
+        match self.take(1)?[0] {
+            0 => Some(Endianness::Little),
+            1 => Some(Endianness::Big),
+            _ => None,
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn flag(&mut self) -> Option<bool> {
+        match self.take(1)?[0] {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+}
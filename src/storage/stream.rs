@@ -0,0 +1,103 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+use futures_sink::Sink;
+
+use crate::error::TableError;
+use crate::storage::record::Record;
+use crate::storage::subscription::Subscription;
+use crate::storage::table::{Row, Table};
+
+/// A consumer group's rows as a `Stream`, from `Table::subscribe_stream`.
+/// Works with any executor: the task is woken by the table's ring rather
+/// than by a runtime timer or reactor.
+pub struct RecordStream<'a> {
+    subscription: Subscription<'a>,
+}
+
+impl<'a> RecordStream<'a> {
+    #[inline(always)]
+    pub(crate) fn new(subscription: Subscription<'a>) -> Self {
+        Self { subscription }
+    }
+
+    /// The subscription behind the stream, for its lag and skip counts.
+    #[inline(always)]
+    pub fn subscription(&self) -> &Subscription<'a> {
+        &self.subscription
+    }
+}
+
+impl Stream for RecordStream<'_> {
+    type Item = Record;
+
+    #[inline(always)]
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Record>> {
+        self.subscription.poll_read(cx)
+    }
+}
+
+/// Writes records into a table as a `Sink`, from `Table::sink`. Holds at
+/// most one record that did not fit yet; `poll_ready` and `poll_flush` wait
+/// for room to store it.
+pub struct RecordSink<'a> {
+    table: &'a Table,
+    pending: Option<Row>,
+    version: usize,  // Of the fields the pending row was laid out with
+}
+
+impl<'a> RecordSink<'a> {
+    #[inline(always)]
+    pub(crate) fn new(table: &'a Table) -> Self {
+        Self { table, pending: None, version: 0 }
+    }
+
+    #[inline(always)]
+    fn poll_pending(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), TableError>> {
+        if self.pending.is_none() {
+            return Poll::Ready(Ok(()));
+        }
+        self.table.poll_store_row(cx, self.version, &mut self.pending)
+    }
+}
+
+impl<K> Sink<HashMap<K, Box<[u8]>>> for RecordSink<'_>
+where
+    K: Borrow<str> + Eq + Hash,
+{
+    type Error = TableError;
+
+    #[inline(always)]
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), TableError>> {
+        self.get_mut().poll_pending(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, record: HashMap<K, Box<[u8]>>) -> Result<(), TableError> {
+        let this = self.get_mut();
+        debug_assert!(this.pending.is_none(), "start_send without poll_ready");
+        let version = this.table.version();
+        let mut row = version.layout.record_row(record)?;
+        version.layout.prepare_row(&mut row)?;
+        this.pending = Some(row);
+        this.version = version.index;
+        // Most sends find room at once; only a full table leaves the row pending
+        match this.table.store_row(this.version, &mut this.pending, false) {
+            Err(TableError::Full) => Ok(()),
+            stored => stored,
+        }
+    }
+
+    #[inline(always)]
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), TableError>> {
+        self.get_mut().poll_pending(cx)
+    }
+
+    #[inline(always)]
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), TableError>> {
+        self.get_mut().poll_pending(cx)
+    }
+}
@@ -0,0 +1,87 @@
+#[cfg(feature = "async")]
+use std::task::{Context, Poll};
+
+use crate::error::TableError;
+use crate::storage::record::Record;
+use crate::storage::table::Table;
+use crate::storage::table_record::TableRecord;
+
+/// Membership of a consumer group on a table.
+///
+/// Each group has its own cursor, so every group sees every row. Members of
+/// the same group share the cursor and never receive the same row twice.
+/// Dropping the last member removes the group.
+pub struct Subscription<'a> {
+    table: &'a Table,
+    group: String,
+    cursor: usize,
+}
+
+impl<'a> Subscription<'a> {
+    #[inline(always)]
+    pub(crate) fn new(table: &'a Table, group: String, cursor: usize) -> Self {
+        Self { table, group, cursor }
+    }
+
+    #[inline(always)]
+    pub fn group(&self) -> &str {
+        &self.group
+    }
+
+    /// Read the group's next row. Returns `Ok(None)` once the group has
+    /// caught up with the producers.
+    #[inline(always)]
+    pub fn read_one_record(&self) -> Result<Option<Record>, TableError> {
+        Ok(self.table.read_cursor(self.cursor))
+    }
+
+    /// Read the group's next row as a typed record, decoded straight from
+    /// where its values are stored.
+    #[inline(always)]
+    pub fn read<T: TableRecord>(&self) -> Result<Option<T>, TableError> {
+        self.table.read_cursor_typed(self.cursor)
+    }
+
+    /// Read the group's next row, or register `cx`'s waker to be woken once
+    /// one may have arrived. Ready with `None` once the table is shut down
+    /// and the group has caught up.
+    #[cfg(feature = "async")]
+    pub(crate) fn poll_read(&self, cx: &mut Context<'_>) -> Poll<Option<Record>> {
+        if let Some(record) = self.table.read_cursor(self.cursor) {
+            return Poll::Ready(Some(record));
+        }
+        let closed = self.table.is_closed();
+        self.table.rows().register_waker(cx.waker());
+        match self.table.read_cursor(self.cursor) {
+            Some(record) => Poll::Ready(Some(record)),
+            None if closed => Poll::Ready(None),
+            None => {
+                // Being published, or shut down, without a wake for us
+                let rows = self.table.rows();
+                if rows.claimed_past(rows.cursor_position(self.cursor)) || self.table.is_closed() {
+                    cx.waker().wake_by_ref();
+                }
+                Poll::Pending
+            }
+        }
+    }
+
+    /// Rows written but not yet read by this group.
+    #[inline(always)]
+    pub fn lag(&self) -> usize {
+        self.table.rows().cursor_lag(self.cursor)
+    }
+
+    /// Rows this group never saw because they were evicted or consumed by a
+    /// plain `Table::read_one_record` before the group reached them.
+    #[inline(always)]
+    pub fn skipped(&self) -> usize {
+        self.table.rows().cursor_skipped(self.cursor)
+    }
+}
+
+impl Drop for Subscription<'_> {
+    fn drop(&mut self) {
+        self.table.leave_group(&self.group);
+    }
+}
@@ -1,116 +1,1139 @@
+use std::borrow::Borrow;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::hash::Hash;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 use std::sync::atomic::{AtomicUsize, Ordering};
-use dashmap::DashMap;
+#[cfg(feature = "async")]
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 
-use crate::memory::low_latency_mpmc_ring::LowLatencyMpmcRing;
+#[cfg(feature = "async")]
+use futures_core::ready;
+
+use crate::clock::{Clock, SystemClock};
+use crate::error::TableError;
+use crate::histogram::{Histogram, HistogramSnapshot, Sampler};
+use crate::memory::counters::{RingStats, ShardedCounters};
+use crate::memory::low_latency_mpmc_ring::{LowLatencyMpmcRing, OverflowPolicy, Slot, Topology};
+use crate::memory::wait_strategy::{SpinThenYield, WaitStrategy};
+use crate::storage::layout;
+use crate::storage::record::Record;
+use crate::storage::scan::Scan;
+use crate::storage::schema::{Column, DataType, Endianness, FieldId, Schema};
+use crate::storage::shared_table::SharedTable;
+use crate::storage::snapshot::{self, ColumnImage, GroupImage, SnapshotImage};
+#[cfg(feature = "async")]
+use crate::storage::stream::{RecordSink, RecordStream};
+use crate::storage::subscription::Subscription;
+use crate::storage::table_record::TableRecord;
+use crate::storage::versions::{SchemaVersions, Version};
+use crate::storage::wal::{self, Wal, WalConfig};
 
 // Cache line size for alignment
 const CACHE_LINE_SIZE: usize = 64;
 
+// Events counted in `Table::counters`
+const ROWS_WRITTEN: usize = 0;
+const ROWS_READ: usize = 1;
+const PARTIAL_WRITES: usize = 2;
+
+/// One value per field, laid out in the table's column order.
+pub type Row = Box<[Box<[u8]>]>;
+
 #[derive(Clone)]
 #[repr(align(64))]  // Align to cache line
 pub struct FieldConfig {
+    pub data_type: DataType,
+    pub endianness: Endianness,
+    /// Exact width for fixed-width types, whose values are stored inline in
+    /// a byte column of `ring_capacity * field_size_bytes`; maximum length
+    /// for variable-length ones (0 = unbounded)
     pub field_size_bytes: usize,
     pub ring_capacity: usize,
+    /// Whether a row may hold no value for the field; records that leave it
+    /// out store a null, unless it has a default
+    pub nullable: bool,
+    /// Stored for records that leave the field out
+    pub default: Option<Box<[u8]>>,
+}
+
+impl FieldConfig {
+    #[inline(always)]
+    pub fn new(data_type: DataType, ring_capacity: usize) -> Self {
+        Self {
+            data_type,
+            endianness: Endianness::Little,
+            field_size_bytes: data_type.fixed_width().unwrap_or(0),
+            ring_capacity,
+            nullable: false,
+            default: None,
+        }
+    }
+
+    #[inline(always)]
+    pub fn with_endianness(mut self, endianness: Endianness) -> Self {
+        self.endianness = endianness;
+        self
+    }
+
+    /// Cap the length of a variable-length field.
+    #[inline(always)]
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.field_size_bytes = max_len;
+        self
+    }
+
+    #[inline(always)]
+    pub fn with_nullable(mut self, nullable: bool) -> Self {
+        self.nullable = nullable;
+        self
+    }
+
+    /// Fill the field with `default` when a record leaves it out. Checked
+    /// against the field's type when the table is built.
+    #[inline(always)]
+    pub fn with_default(mut self, default: Box<[u8]>) -> Self {
+        self.default = Some(default);
+        self
+    }
+
+    /// Check that `bytes` is a valid encoding of this field's type.
+    #[inline(always)]
+    pub fn validate(&self, field_name: &str, bytes: &[u8]) -> Result<(), TableError> {
+        let fits = match self.data_type.fixed_width() {
+            Some(width) => bytes.len() == width,
+            None => self.field_size_bytes == 0 || bytes.len() <= self.field_size_bytes,
+        };
+        if !fits {
+            return Err(TableError::SizeMismatch {
+                field: field_name.to_owned(),
+                expected: self.field_size_bytes,
+                actual: bytes.len(),
+            });
+        }
+
+        let well_formed = match self.data_type {
+            DataType::Bool => bytes[0] <= 1,
+            DataType::Utf8 => std::str::from_utf8(bytes).is_ok(),
+            _ => true,
+        };
+        if !well_formed {
+            return Err(TableError::TypeMismatch {
+                field: field_name.to_owned(),
+                expected: self.data_type,
+            });
+        }
+        Ok(())
+    }
 }
 
 #[derive(Clone)]
 pub struct TableConfig {
-    pub fields: HashMap<&'static str, FieldConfig>,  // Use static str for zero-allocation
+    pub schema: Schema,
+    pub overflow_policy: OverflowPolicy,
+    /// Designated event-time column; defaults to the first timestamp_ns field
+    pub time_column: Option<Arc<str>>,
+    /// Column stamped with the arrival time of every write
+    pub ingest_time_column: Option<Arc<str>>,
+    pub clock: Arc<dyn Clock>,
+    /// Log every accepted write; requires opening the table with `Table::open`
+    pub wal: Option<WalConfig>,
+    /// How blocked writers and `read_blocking` wait
+    pub wait_strategy: Arc<dyn WaitStrategy>,
+    // Set through the unsafe `with_topology`, which carries the promise
+    topology: Topology,
+    /// Time one in this many writes, and separately one in this many reads,
+    /// into the table's latency histograms
+    pub latency_sampling: Option<u32>,
+    /// Reject records that leave out any field but the stamped time columns,
+    /// instead of filling in defaults and nulls
+    pub strict: bool,
 }
 
+impl TableConfig {
+    /// Config for a table with `fields`. Panics if there are none; build a
+    /// `Schema` and use `from_schema` to get that back as an error instead.
+    pub fn new<K: Into<Arc<str>>>(fields: HashMap<K, FieldConfig>) -> Self {
+        let schema = fields.into_iter()
+            .fold(Schema::builder(), |builder, (name, config)| builder.field(name, config))
+            .build()
+            .unwrap_or_else(|err| panic!("{}", err));
+        Self::from_schema(schema)
+    }
+
+    #[inline(always)]
+    pub fn from_schema(schema: Schema) -> Self {
+        Self {
+            schema,
+            overflow_policy: OverflowPolicy::Reject,
+            time_column: None,
+            ingest_time_column: None,
+            clock: Arc::new(SystemClock),
+            wal: None,
+            wait_strategy: Arc::new(SpinThenYield::default()),
+            topology: Topology::Mpmc,
+            latency_sampling: None,
+            strict: false,
+        }
+    }
+
+    /// Use `field_name` as the table's time column. Writes that leave it out
+    /// are stamped from the clock.
+    #[inline(always)]
+    pub fn with_time_column(mut self, field_name: impl Into<Arc<str>>) -> Self {
+        self.time_column = Some(field_name.into());
+        self
+    }
+
+    /// Stamp `field_name` with the clock on every write.
+    #[inline(always)]
+    pub fn with_ingest_time_column(mut self, field_name: impl Into<Arc<str>>) -> Self {
+        self.ingest_time_column = Some(field_name.into());
+        self
+    }
+
+    #[inline(always)]
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    #[inline(always)]
+    pub fn with_wait_strategy(mut self, wait_strategy: Arc<dyn WaitStrategy>) -> Self {
+        self.wait_strategy = wait_strategy;
+        self
+    }
+
+    #[inline(always)]
+    pub fn with_wal(mut self, wal: WalConfig) -> Self {
+        self.wal = Some(wal);
+        self
+    }
+
+    #[inline(always)]
+    pub fn with_overflow_policy(mut self, overflow_policy: OverflowPolicy) -> Self {
+        self.overflow_policy = overflow_policy;
+        self
+    }
+
+    /// In strict mode every record must carry every field, so a producer
+    /// that drops one is caught rather than stored with a default or a null.
+    /// Defaults still apply to rows written before a field was added.
+    #[inline(always)]
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Keep write and read latency histograms, timing one write in every
+    /// `one_in` and one read in every `one_in`, counted apart; 1 times every
+    /// call. Each sampled call costs two clock reads and a histogram record.
+    #[inline(always)]
+    pub fn with_latency_sampling(mut self, one_in: u32) -> Self {
+        assert!(one_in > 0, "Latency sampling needs a rate of at least one call in 1");
+        self.latency_sampling = Some(one_in);
+        self
+    }
+
+    /// Declare how many threads write and read the table at once, so it can
+    /// use a ring without CAS loops on that side. A single-consumer table
+    /// needs the Reject or Block policy and has no consumer groups:
+    /// `subscribe` fails with `TooManyGroups`.
+    ///
+    /// # Panics
+    /// `Table::new` panics on a single-consumer topology with the DropOldest
+    /// or Overwrite policy, which `Table::open` reports as `InvalidSchema`.
+    ///
+    /// # Safety
+    /// With a single producer, no two threads may write to the table at the
+    /// same time (`write_record`, `write`, `write_batch`, the async writes);
+    /// with a single consumer, no two threads may read from it at the same
+    /// time (`read_one_record`, `read`, `read_batch`, `read_blocking`, `drain`).
+    #[inline(always)]
+    pub unsafe fn with_topology(mut self, topology: Topology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    #[inline(always)]
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    /// Rows a table built from this config holds: a row lives as long as its
+    /// shortest-lived field allows.
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.schema.columns().iter().map(|column| column.config.ring_capacity).min().unwrap_or(1)
+    }
+
+    // The capacity, or `InvalidSchema` unless it is a power of two a ring
+    // can be built with
+    #[inline(always)]
+    pub(crate) fn checked_capacity(&self) -> Result<usize, TableError> {
+        let capacity = self.capacity();
+        if !capacity.is_power_of_two() {
+            return Err(TableError::InvalidSchema(format!(
+                "ring capacity {} must be a nonzero power of two", capacity
+            )));
+        }
+        Ok(capacity)
+    }
+
+    /// Bytes a table built from this config reserves up front: a ring slot
+    /// and the inline fixed-width values for every row, plus its validity
+    /// bitmap if any field is nullable. Variable-length values are allocated
+    /// as rows arrive and are not counted.
+    pub fn reserved_bytes(&self) -> usize {
+        let columns = self.schema.columns();
+        let inline: usize = columns.iter()
+            .filter(|column| column.config.data_type.fixed_width().is_some())
+            .map(|column| column.config.field_size_bytes)
+            .sum();
+        let validity = match columns.iter().any(|column| column.config.nullable) {
+            true => layout::validity_len(columns.len()),
+            false => 0,
+        };
+        self.capacity() * (std::mem::size_of::<Slot<Row>>() + inline + validity)
+    }
+}
+
+/// Counters of one table, from `Table::stats`, for export to monitoring.
+/// Each one is read separately while the table keeps running, so they need
+/// not add up exactly.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TableStats {
+    /// Rows stored by writes, including any later evicted
+    pub rows_written: u64,
+    /// Rows returned by reads and to consumer groups; a row read by two
+    /// groups counts twice
+    pub rows_read: u64,
+    /// Batch writes that stored only part of their batch
+    pub partial_writes: u64,
+    /// Rows discarded by the overflow policy to make room for newer ones
+    pub evicted: u64,
+    /// Rows held right now
+    pub record_count: usize,
+    /// Counters of the ring holding the rows
+    pub ring: RingStats,
+}
+
+// Latency histograms kept when the config asks for sampling
+struct LatencySampling {
+    // Apart, so a thread alternating writes and reads still samples both
+    write_sampler: Sampler,
+    read_sampler: Sampler,
+    write: Histogram,
+    read: Histogram,
+}
+
+// A named cursor and how many subscriptions currently share it
+struct ConsumerGroup {
+    cursor: usize,
+    members: usize,
+}
+
+/// A table stores whole rows in a single MPMC ring: one claimed sequence
+/// number covers every field of a record, so concurrent producers can never
+/// interleave the fields of different records. Fixed-width values live
+/// inline in per-field byte columns indexed by that sequence number; only
+/// variable-length values are boxed.
+///
+/// Fields can be added and dropped while the table runs. Each row remembers
+/// the version of the fields it was written with and is read back with the
+/// current ones.
 #[repr(align(64))]  // Align to cache line for better performance
 pub struct Table {
-    pub name: &'static str,  // Use static str
-    pub field_configs: HashMap<&'static str, FieldConfig>,
-    pub field_buffers: DashMap<&'static str, Arc<LowLatencyMpmcRing<Box<[u8]>>>>,
+    pub name: Arc<str>,
     pub record_count: AtomicUsize,
+    rows: LowLatencyMpmcRing<Row>,  // Variable-length values only
+    versions: SchemaVersions,  // Layouts and inline values
+    groups: Mutex<HashMap<String, ConsumerGroup>>,
+    wal: Option<Arc<Mutex<Wal>>>,  // Shared with its flusher, if any
+    counters: ShardedCounters<3>,
+    latency: Option<LatencySampling>,
     _padding: [u8; CACHE_LINE_SIZE - 32],
 }
 
 impl Table {
+    /// Create an in-memory table. Tables with a WAL are created with `open`.
+    ///
+    /// # Panics
+    /// If the config has a WAL, a time column is not a timestamp_ns field,
+    /// a field's width or default does not fit its type, the capacity is not
+    /// a nonzero power of two, or a single-consumer topology is paired with
+    /// an evicting policy. `open` reports all but the first as
+    /// `InvalidSchema` instead.
+    #[inline(always)]
+    pub fn new(name: impl Into<Arc<str>>, config: TableConfig) -> Self {
+        assert!(config.wal.is_none(), "tables with a WAL must be created with Table::open");
+        Self::build(name, config).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Create a table, replaying its WAL if the config has one so rows that
+    /// had not been read before a crash or restart come back with their
+    /// original sequence numbers. Rows read after the last write are not
+    /// known to the log and are delivered again. Under `GroupCommit` a
+    /// background thread syncs windows that writes stopped in. Fails with
+    /// `InvalidSchema` where `new` would panic.
+    pub fn open(name: impl Into<Arc<str>>, config: TableConfig) -> Result<Self, TableError> {
+        let wal_config = config.wal.clone();
+        let mut table = Self::build(name, config)?;
+        let Some(wal_config) = wal_config else {
+            return Ok(table);
+        };
+
+        let (wal, replay) = Wal::open(&wal_config, &table.version().layout, table.rows.capacity())?;
+        table.rows.start_at(replay.start);
+        *table.record_count.get_mut() = replay.rows.len();
+        for row in replay.rows {
+            table.rows.try_enqueue_with(|seq| unsafe { table.versions.put(0, seq, row) })?;
+        }
+        let wal = Arc::new(Mutex::new(wal));
+        wal::spawn_flusher(&wal, &wal_config)?;
+        table.wal = Some(wal);
+        Ok(table)
+    }
+
+    /// Create a table from a file written by `snapshot`. Fails with `Corrupt`
+    /// if the checksum does not match and `SchemaMismatch` if the saved
+    /// columns differ from `config`'s. Consumer groups resume from their
+    /// saved cursors and are kept until their first member leaves.
+    pub fn restore(name: impl Into<Arc<str>>, config: TableConfig, path: impl AsRef<Path>) -> Result<Self, TableError> {
+        assert!(config.wal.is_none(), "a restored table cannot replay a WAL as well");
+        let image = snapshot::read(path.as_ref())?;
+        let mut table = Self::build(name, config)?;
+
+        let columns = table.version().layout.columns.clone();
+        let expected: Vec<ColumnImage> = columns.iter().map(ColumnImage::of).collect();
+        if image.columns != expected {
+            return Err(TableError::SchemaMismatch(format!(
+                "snapshot of `{}` has columns {:?}, table expects {:?}",
+                image.table, image.columns, expected
+            )));
+        }
+        if image.rows.len() > table.rows.capacity() {
+            return Err(TableError::SchemaMismatch(format!(
+                "snapshot holds {} rows, table capacity is {}",
+                image.rows.len(), table.rows.capacity()
+            )));
+        }
+        for row in &image.rows {
+            table.version().layout.check_row(row).map_err(|err| TableError::Corrupt(err.to_string()))?;
+        }
+
+        table.rows.start_at(image.start);
+        *table.record_count.get_mut() = image.rows.len();
+        for row in image.rows {
+            table.rows.try_enqueue_with(|seq| unsafe { table.versions.put(0, seq, row) })?;
+        }
+        let groups = table.groups.get_mut().unwrap_or_else(PoisonError::into_inner);
+        for group in image.groups {
+            let cursor = table.rows.add_cursor_at(group.position, group.skipped)
+                .ok_or(TableError::TooManyGroups)?;
+            groups.insert(group.name, ConsumerGroup { cursor, members: 0 });
+        }
+        Ok(table)
+    }
+
+    /// Create a table whose rows live in shared memory, for producers and
+    /// consumers in other processes to `attach_shared` by `name`. Every field
+    /// must be fixed-width and not nullable, and the table can have neither
+    /// a WAL nor an overflow policy other than `Reject`, or this fails with
+    /// `SchemaMismatch`. It fails with `InvalidSchema` for a config `Table::new`
+    /// would panic on, with `InvalidName` if `name` cannot name a file, and
+    /// with `Io` if a shared table of that name already exists.
+    /// The name is released when the returned table is dropped.
+    #[inline(always)]
+    pub fn create_shared(name: impl Into<Arc<str>>, config: TableConfig) -> Result<SharedTable, TableError> {
+        SharedTable::create(name.into(), config)
+    }
+
+    /// Attach to a table another process made with `create_shared`. Fails
+    /// with `SchemaMismatch` unless `config` declares the same fields.
     #[inline(always)]
-    pub fn new(name: &'static str, config: TableConfig) -> Self {
-        let mut table = Self {
-            name,
-            field_configs: HashMap::with_capacity(config.fields.len()),
-            field_buffers: DashMap::with_capacity(config.fields.len()),
+    pub fn attach_shared(name: impl Into<Arc<str>>, config: TableConfig) -> Result<SharedTable, TableError> {
+        SharedTable::attach(name.into(), config)
+    }
+
+    fn build(name: impl Into<Arc<str>>, config: TableConfig) -> Result<Self, TableError> {
+        let capacity = config.checked_capacity()?;
+        if config.topology.single_consumer()
+            && !matches!(config.overflow_policy, OverflowPolicy::Reject | OverflowPolicy::Block { .. })
+        {
+            return Err(TableError::InvalidSchema(
+                "a single-consumer table needs the Reject or Block policy".to_owned(),
+            ));
+        }
+        let versions = SchemaVersions::new(&config, capacity)?;
+        let rows = LowLatencyMpmcRing::with_policy(capacity, config.overflow_policy)
+            .with_wait_strategy(config.wait_strategy);
+        // SAFETY: whoever set the topology promised to keep to it
+        let rows = unsafe { rows.with_topology(config.topology) };
+
+        Ok(Self {
+            name: name.into(),
             record_count: AtomicUsize::new(0),
+            groups: Mutex::new(HashMap::new()),
+            wal: None,
+            rows,
+            versions,
+            counters: ShardedCounters::new(),
+            latency: config.latency_sampling.map(|one_in| LatencySampling {
+                write_sampler: Sampler::new(one_in),
+                read_sampler: Sampler::new(one_in),
+                write: Histogram::new(),
+                read: Histogram::new(),
+            }),
             _padding: [0; CACHE_LINE_SIZE - 32],
-        };
+        })
+    }
 
-        // Pre-allocate all buffers at once
-        for (field_name, fc) in config.fields {
-            let ring = Arc::new(LowLatencyMpmcRing::new(fc.ring_capacity));
-            table.field_configs.insert(field_name, fc);
-            table.field_buffers.insert(field_name, ring);
+    /// Write one record as a single row. Every field must be present and
+    /// match its declared type, except the time columns, which the table
+    /// stamps itself; `Full` means the caller should back off.
+    #[inline(always)]
+    pub fn write_record<K>(&self, record: HashMap<K, Box<[u8]>>) -> Result<(), TableError>
+    where
+        K: Borrow<str> + Eq + Hash,
+    {
+        let timer = self.start_timer(|latency| &latency.write_sampler);
+        let version = self.version();
+        self.insert_row(version, version.layout.record_row(record)?)?;
+        if let Some((latency, started)) = timer {
+            latency.write.record_duration(started.elapsed());
         }
+        Ok(())
+    }
 
-        table
+    /// Write one record given as values for fields resolved with `field_id`,
+    /// so no field name is hashed or compared. Otherwise as `write_record`.
+    #[inline(always)]
+    pub fn write_fields(&self, values: impl IntoIterator<Item = (FieldId, Box<[u8]>)>) -> Result<(), TableError> {
+        let timer = self.start_timer(|latency| &latency.write_sampler);
+        let version = self.version();
+        self.insert_row(version, version.layout.field_row(values)?)?;
+        if let Some((latency, started)) = timer {
+            latency.write.record_duration(started.elapsed());
+        }
+        Ok(())
     }
 
+    /// Write one record given as borrowed values for fields resolved with
+    /// `field_id`, encoded straight into the row's slot once it is claimed:
+    /// only variable-length values are copied into boxes, so records of
+    /// fixed-width fields are written without allocating. Otherwise as
+    /// `write_fields`.
     #[inline(always)]
-    pub fn write_record(&self, record: HashMap<&'static str, Box<[u8]>>) -> bool {
-        // Fast path: check capacity first
-        if self.record_count.load(Ordering::Relaxed) >= self.capacity() {
-            return false;
+    pub fn write_values(&self, values: &[(FieldId, &[u8])]) -> Result<(), TableError> {
+        let timer = self.start_timer(|latency| &latency.write_sampler);
+        let version = self.version();
+        version.layout.check_values(values)?;
+        self.store_with(true, |seq, wal| unsafe { self.versions.put_values(version.index, seq, values, wal) })?;
+        if let Some((latency, started)) = timer {
+            latency.write.record_duration(started.elapsed());
         }
+        Ok(())
+    }
+
+    /// Write one record like `write_record`, but wait without blocking the
+    /// thread while the table is full, whatever its overflow policy. Fails
+    /// with `Closed` if the table shuts down first.
+    #[cfg(feature = "async")]
+    pub async fn write_record_async<K>(&self, record: HashMap<K, Box<[u8]>>) -> Result<(), TableError>
+    where
+        K: Borrow<str> + Eq + Hash,
+    {
+        let version = self.version();
+        let mut row = version.layout.record_row(record)?;
+        version.layout.prepare_row(&mut row)?;
+        let mut row = Some(row);
+        std::future::poll_fn(|cx| self.poll_store_row(cx, version.index, &mut row)).await
+    }
 
-        // Pre-check all buffers to avoid partial writes
-        for (field_name, _) in &record {
-            if let Some(ring_arc) = self.field_buffers.get(field_name) {
-                if ring_arc.is_full() {
-                    return false;
+    /// A `Sink` that writes records like `write_record_async`.
+    #[cfg(feature = "async")]
+    #[inline(always)]
+    pub fn sink(&self) -> RecordSink<'_> {
+        RecordSink::new(self)
+    }
+
+    /// Write many records, claiming ring slots for as many as fit with one
+    /// CAS instead of one per record. Every record is checked before any is
+    /// stored. Stored records are drained from the front of `records`; on
+    /// `PartialWrite` or `Full` the rest stay there to be retried.
+    pub fn write_batch<K>(&self, records: &mut Vec<HashMap<K, Box<[u8]>>>) -> Result<(), TableError>
+    where
+        K: Borrow<str> + Eq + Hash,
+    {
+        let version = self.version();
+        for record in records.iter() {
+            version.layout.check_record(record)?;
+        }
+
+        let total = records.len();
+        let unlocked_wait = self.unlocked_wait(true);
+        self.record_count.fetch_add(total, Ordering::Release);
+        let (result, committed, refused) = {
+            let mut rows = records.iter_mut().map(|record| {
+                let mut row = version.layout.take_row(record);
+                version.layout.stamp(&mut row);
+                row
+            });
+            let (result, committed) = loop {
+                // Holding the log across the enqueue keeps log order equal to sequence order
+                let mut wal = self.wal.as_ref().map(Self::lock_wal);
+                let mut first_seq = None;
+                let mut put = |seq, row: Row| {
+                    first_seq.get_or_insert(seq);
+                    if let Some(wal) = wal.as_deref_mut() {
+                        wal.encode(&row);
+                    }
+                    unsafe { self.versions.put(version.index, seq, row) }
+                };
+                let result = match unlocked_wait {
+                    None => self.rows.enqueue_batch_with(&mut rows, &mut put),
+                    Some(_) => {
+                        self.rows.try_enqueue_batch_with(&mut rows, &mut put);
+                        Ok(0)
+                    }
+                };
+                let committed = match (wal.as_mut(), first_seq) {
+                    (Some(wal), Some(seq)) => wal.commit(seq, self.rows.retained().start),
+                    _ => Ok(()),
+                };
+                drop(wal);
+
+                let Some(deadline) = unlocked_wait else {
+                    break (result, committed);
+                };
+                if rows.len() == 0 || committed.is_err() {
+                    break (result, committed);
                 }
-            }
+                if let Err(err) = self.rows.wait_enqueue_ready(deadline) {
+                    let err = match (err, total - rows.len()) {
+                        (TableError::Full, 0) => TableError::Full,
+                        (TableError::Full, written) => TableError::PartialWrite { written },
+                        (err, _) => err,
+                    };
+                    break (Err(err), committed);
+                }
+            };
+            (result, committed, rows.len())
+        };
+        let evicted = *result.as_ref().unwrap_or(&0);
+        if evicted + refused > 0 {
+            self.record_count.fetch_sub(evicted + refused, Ordering::Release);
+        }
+        let written = total - refused;
+        records.drain(..written);
+        self.counters.add(ROWS_WRITTEN, written as u64);
+        if let Err(TableError::PartialWrite { .. }) = result {
+            self.counters.add(PARTIAL_WRITES, 1);
+        }
+
+        committed?;
+        result.map(|_| ())
+    }
+
+    /// Write a typed record into a table created from `T::table_config`.
+    /// Its fields are encoded straight into the row's slot, so records of
+    /// fixed-width fields are written without allocating.
+    #[inline(always)]
+    pub fn write<T: TableRecord>(&self, record: &T) -> Result<(), TableError> {
+        let timer = self.start_timer(|latency| &latency.write_sampler);
+        let version = self.version();
+        version.layout.check_typed(record)?;
+        self.store_with(true, |seq, wal| unsafe { self.versions.put_typed(version.index, seq, record, wal) })?;
+        if let Some((latency, started)) = timer {
+            latency.write.record_duration(started.elapsed());
         }
+        Ok(())
+    }
 
-        // All checks passed, perform the write
-        for (field_name, data) in record {
-            if let Some(ring_arc) = self.field_buffers.get(field_name) {
-                if !ring_arc.try_enqueue(data) {
-                    return false;
+    #[inline(always)]
+    fn insert_row(&self, version: &Version, mut row: Row) -> Result<(), TableError> {
+        version.layout.prepare_row(&mut row)?;
+        self.store_row(version.index, &mut Some(row), true)
+    }
+
+    /// Log and enqueue a row prepared with version `version` of the fields.
+    /// With `apply_policy` unset a full ring fails at once with `Full`, and
+    /// the row is left in `row` for a retry.
+    pub(crate) fn store_row(&self, version: usize, row: &mut Option<Row>, apply_policy: bool) -> Result<(), TableError> {
+        self.store_with(apply_policy, |seq, wal| {
+            let row = row.take().expect("a row to store");
+            if let Some(wal) = wal {
+                wal.encode(&row);
+            }
+            unsafe { self.versions.put(version, seq, row) }
+        })
+    }
+
+    // Enqueue the row `put(seq, wal)` stores into claimed slot `seq`, which
+    // it also encodes into the log if the table has one
+    #[inline(always)]
+    fn store_with(&self, apply_policy: bool, put: impl FnOnce(usize, Option<&mut Wal>) -> Row) -> Result<(), TableError> {
+        let unlocked_wait = self.unlocked_wait(apply_policy);
+        let mut put = Some(put);
+        // Count first so a racing reader can never observe the count underflow
+        self.record_count.fetch_add(1, Ordering::Release);
+        let (stored, mut wal, logged) = loop {
+            // Holding the log across the enqueue keeps log order equal to sequence order
+            let mut wal = self.wal.as_ref().map(Self::lock_wal);
+            let mut logged = None;
+            let make = |seq| {
+                logged = Some(seq);
+                put.take().expect("a row to store")(seq, wal.as_deref_mut())
+            };
+            let stored = match unlocked_wait {
+                None if apply_policy => self.rows.enqueue_with(make),
+                _ => self.rows.try_enqueue_with(make).map(|()| 0),
+            };
+            match (stored, unlocked_wait) {
+                (Err(TableError::Full), Some(deadline)) => {
+                    drop(wal);
+                    if let Err(err) = self.rows.wait_enqueue_ready(deadline) {
+                        break (Err(err), None, None);
+                    }
                 }
+                (stored, _) => break (stored, wal, logged),
+            }
+        };
+        match stored {
+            Ok(evicted) => {
+                self.counters.add(ROWS_WRITTEN, 1);
+                if evicted > 0 {
+                    self.record_count.fetch_sub(evicted, Ordering::Release);
+                }
+            }
+            Err(err) => {
+                self.record_count.fetch_sub(1, Ordering::Release);
+                return Err(err);
             }
         }
-        
-        self.record_count.fetch_add(1, Ordering::Release);
-        true
+
+        // An error here leaves the row readable, and logged again by the next commit
+        if let (Some(wal), Some(seq)) = (wal.as_mut(), logged) {
+            wal.commit(seq, self.rows.retained().start)?;
+        }
+        Ok(())
     }
 
+    // How long a writer blocked on a full table waits, if it waits with the
+    // log unlocked: a Block table with a log is only tried, and waited on
+    // between tries, so neither other writers nor the log's flusher stall
+    // for as long as the table stays full
     #[inline(always)]
-    pub fn read_one_record(&self) -> Option<HashMap<&'static str, Box<[u8]>>> {
-        // Fast path: check if empty
-        if self.record_count.load(Ordering::Relaxed) == 0 {
-            return None;
+    fn unlocked_wait(&self, apply_policy: bool) -> Option<Option<Instant>> {
+        match self.rows.policy() {
+            OverflowPolicy::Block { timeout } if apply_policy && self.wal.is_some() => {
+                Some(timeout.map(|timeout| Instant::now() + timeout))
+            }
+            _ => None,
         }
+    }
 
-        let mut out = HashMap::with_capacity(self.field_buffers.len());
-        
-        // Pre-check all buffers to avoid partial reads
-        for item in self.field_buffers.iter() {
-            if item.value().is_empty() {
-                return None;
+    /// Store a prepared row, or register `cx`'s waker to retry once a slot
+    /// frees up. The row stays in `row` until it is stored.
+    #[cfg(feature = "async")]
+    pub(crate) fn poll_store_row(
+        &self,
+        cx: &mut Context<'_>,
+        version: usize,
+        row: &mut Option<Row>,
+    ) -> Poll<Result<(), TableError>> {
+        loop {
+            match self.store_row(version, row, false) {
+                Err(TableError::Full) => {}
+                stored => return Poll::Ready(stored),
             }
+            // Another producer may take the freed slot first; then we wait again
+            ready!(self.rows.poll_enqueue_ready(cx))?;
         }
+    }
 
-        // All checks passed, perform the read
-        for item in self.field_buffers.iter() {
-            let field_name = *item.key();
-            if let Some(bytes) = item.value().try_dequeue() {
-                out.insert(field_name, bytes);
-            } else {
-                return None;
-            }
+    /// Write a point-in-time image of the rows, schema and consumer group
+    /// cursors to `path`, replacing it atomically. Producers and consumers are
+    /// never paused: rows are copied like a scan, and the image is a
+    /// contiguous run of rows the table held at one instant during the call.
+    pub fn snapshot(&self, path: impl AsRef<Path>) -> Result<(), TableError> {
+        let (start, rows) = self.rows.copy_retained(|seq, boxed| unsafe { self.versions.copy_raw(seq, boxed) });
+        // Loaded after the copy, so it is no older than any copied row, and
+        // every row and the column list come from this one version
+        let version = self.version();
+        let rows: Vec<Row> = rows.into_iter()
+            .map(|(written, row)| self.versions.upgrade_raw(written, version, row).into_row())
+            .collect();
+        let columns = version.layout.columns.iter().map(ColumnImage::of).collect();
+        let end = start + rows.len();
+        let groups = self.groups.lock().unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .map(|(name, group)| GroupImage {
+                name: name.clone(),
+                // Rows past the image are not in it, so cap what groups have read
+                position: self.rows.cursor_position(group.cursor).clamp(start, end),
+                skipped: self.rows.cursor_skipped(group.cursor),
+            })
+            .collect();
+
+        snapshot::write(path.as_ref(), &SnapshotImage {
+            table: self.name.to_string(),
+            columns,
+            start,
+            rows,
+            groups,
+        })
+    }
+
+    /// Stop accepting writes: later writes, and writers blocked on a full
+    /// table, fail with `Closed`. Rows already stored stay readable, through
+    /// `read_one_record`, `drain` or subscriptions, until they are consumed,
+    /// and the WAL is synced. Calling it again is harmless.
+    pub fn shutdown(&self) -> Result<(), TableError> {
+        self.rows.close();
+        self.sync_wal()
+    }
+
+    #[inline(always)]
+    pub fn is_closed(&self) -> bool {
+        self.rows.is_closed()
+    }
+
+    /// Read rows until the table is empty, typically after `shutdown`.
+    pub fn drain(&self) -> impl Iterator<Item = Record> + '_ {
+        std::iter::from_fn(|| self.read_one_record().ok().flatten())
+    }
+
+    /// Close the WAL after `shutdown`, so another table can replay it. Rows
+    /// stored before stay readable.
+    pub(crate) fn close_wal(&self) -> Result<(), TableError> {
+        match self.wal.as_ref().map(Self::lock_wal) {
+            Some(mut wal) => wal.close(),
+            None => Ok(()),
         }
+    }
 
+    /// Undo `close_wal`, and `shutdown` too if `accept_writes` is set.
+    pub(crate) fn reopen(&self, accept_writes: bool) -> Result<(), TableError> {
+        if let Some(mut wal) = self.wal.as_ref().map(Self::lock_wal) {
+            wal.reopen()?;
+        }
+        if accept_writes {
+            self.rows.reopen();
+        }
+        Ok(())
+    }
+
+    /// Force every logged write to stable storage, whatever the sync policy.
+    /// A no-op for tables without a WAL.
+    pub fn sync_wal(&self) -> Result<(), TableError> {
+        match self.wal.as_ref().map(Self::lock_wal) {
+            Some(mut wal) => wal.sync(),
+            None => Ok(()),
+        }
+    }
+
+    #[inline(always)]
+    fn lock_wal(wal: &Arc<Mutex<Wal>>) -> MutexGuard<'_, Wal> {
+        wal.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Read the oldest row back as a record. Returns `Ok(None)` if the table
+    /// is empty.
+    #[inline(always)]
+    pub fn read_one_record(&self) -> Result<Option<Record>, TableError> {
+        let timer = self.start_timer(|latency| &latency.read_sampler);
+        let Some(record) = self.rows.try_dequeue_with(|seq, boxed| unsafe { self.versions.take(seq, boxed) })? else {
+            return Ok(None);
+        };
         self.record_count.fetch_sub(1, Ordering::Release);
-        Some(out)
+        self.counters.add(ROWS_READ, 1);
+
+        if let Some((latency, started)) = timer {
+            latency.read.record_duration(started.elapsed());
+        }
+        Ok(Some(record))
+    }
+
+    /// A consumer group subscription that yields the group's rows as a
+    /// `Stream`, waiting without blocking the thread while it is caught up.
+    /// The stream ends once the table is shut down and the group has read
+    /// every row.
+    #[cfg(feature = "async")]
+    pub fn subscribe_stream(&self, group: &str) -> Result<RecordStream<'_>, TableError> {
+        self.subscribe(group).map(RecordStream::new)
+    }
+
+    /// Read the oldest row, waiting with the table's wait strategy for one to
+    /// be written if the table is empty. Returns `Ok(None)` if none arrives
+    /// within `timeout` (`None` waits indefinitely), and fails with `Closed`
+    /// once the table is shut down and empty.
+    pub fn read_blocking(&self, timeout: Option<Duration>) -> Result<Option<Record>, TableError> {
+        let record = self.rows.dequeue_timeout_with(timeout, |seq, boxed| unsafe { self.versions.take(seq, boxed) })?;
+        let Some(record) = record else {
+            return Ok(None);
+        };
+        self.record_count.fetch_sub(1, Ordering::Release);
+        self.counters.add(ROWS_READ, 1);
+        Ok(Some(record))
+    }
+
+    /// Read up to `max` of the oldest rows into `out`, claiming them with a
+    /// single CAS. Returns how many were read.
+    pub fn read_batch(&self, out: &mut Vec<Record>, max: usize) -> Result<usize, TableError> {
+        out.reserve(max.min(self.rows.capacity()));
+        let read = self.rows.dequeue_batch_with(max, |seq, boxed| {
+            out.push(unsafe { self.versions.take(seq, boxed) });
+        });
+        if read > 0 {
+            self.record_count.fetch_sub(read, Ordering::Release);
+            self.counters.add(ROWS_READ, read as u64);
+        }
+        Ok(read)
+    }
+
+    /// Read the oldest row as a typed record, decoded straight from where
+    /// its values are stored. Returns `Ok(None)` if the table is empty. The
+    /// row is consumed even if it fails to decode.
+    #[inline(always)]
+    pub fn read<T: TableRecord>(&self) -> Result<Option<T>, TableError> {
+        let timer = self.start_timer(|latency| &latency.read_sampler);
+        let Some(record) = self.rows.try_dequeue_with(|seq, boxed| unsafe { self.versions.decode::<T>(seq, &boxed) })? else {
+            return Ok(None);
+        };
+        self.record_count.fetch_sub(1, Ordering::Release);
+        self.counters.add(ROWS_READ, 1);
+
+        if let Some((latency, started)) = timer {
+            latency.read.record_duration(started.elapsed());
+        }
+        record.map(Some)
+    }
+
+    /// Join consumer group `group`, creating it at the oldest retained row if
+    /// it does not exist yet. Every group sees every row; subscriptions in the
+    /// same group share one cursor and so split the rows between them.
+    ///
+    /// Rows stay in the table until every group has read them, so a slow
+    /// group holds back producers under Reject and Block, and falls behind
+    /// (see `Subscription::skipped`) under DropOldest and Overwrite. The group
+    /// is removed when its last subscription is dropped.
+    pub fn subscribe(&self, group: &str) -> Result<Subscription<'_>, TableError> {
+        let mut groups = self.groups.lock().unwrap_or_else(PoisonError::into_inner);
+        let cursor = match groups.get_mut(group) {
+            Some(existing) => {
+                existing.members += 1;
+                existing.cursor
+            }
+            None => {
+                let cursor = self.rows.add_cursor().ok_or(TableError::TooManyGroups)?;
+                groups.insert(group.to_owned(), ConsumerGroup { cursor, members: 1 });
+                cursor
+            }
+        };
+        Ok(Subscription::new(self, group.to_owned(), cursor))
+    }
+
+    #[inline(always)]
+    pub(crate) fn read_cursor(&self, cursor: usize) -> Option<Record> {
+        self.read_cursor_with(cursor, |seq, boxed| unsafe { self.versions.copy(seq, boxed) })
+    }
+
+    /// Decode the next row of `cursor` as a typed record, as `read` does.
+    #[inline(always)]
+    pub(crate) fn read_cursor_typed<T: TableRecord>(&self, cursor: usize) -> Result<Option<T>, TableError> {
+        self.read_cursor_with(cursor, |seq, boxed| unsafe { self.versions.decode::<T>(seq, boxed) })
+            .transpose()
+    }
+
+    #[inline(always)]
+    fn read_cursor_with<R>(&self, cursor: usize, f: impl FnMut(usize, &Row) -> R) -> Option<R> {
+        let read = self.rows.read_cursor_with(cursor, f);
+        if read.is_some() {
+            self.counters.add(ROWS_READ, 1);
+        }
+        self.release_consumed();
+        read
+    }
+
+    pub(crate) fn leave_group(&self, group: &str) {
+        let mut groups = self.groups.lock().unwrap_or_else(PoisonError::into_inner);
+        let Some(existing) = groups.get_mut(group) else {
+            return;
+        };
+        existing.members -= 1;
+        if existing.members == 0 {
+            self.rows.remove_cursor(existing.cursor);
+            groups.remove(group);
+            drop(groups);
+            // Rows only this group was holding back can go now
+            self.release_consumed();
+        }
+    }
+
+    #[inline(always)]
+    fn release_consumed(&self) {
+        let released = self.rows.release_consumed();
+        if released > 0 {
+            self.record_count.fetch_sub(released, Ordering::Release);
+        }
+    }
+
+    /// The version of the fields new rows are written with.
+    #[inline(always)]
+    pub(crate) fn version(&self) -> &Version {
+        self.versions.current()
+    }
+
+    #[inline(always)]
+    pub(crate) fn rows(&self) -> &LowLatencyMpmcRing<Row> {
+        &self.rows
+    }
+
+    /// Iterate, without consuming, over the retained rows whose time column is
+    /// in `[from_ts, to_ts)`. Rows are visited in write order, not time order.
+    #[inline(always)]
+    pub fn scan(&self, from_ts: u64, to_ts: u64) -> Result<Scan<'_>, TableError> {
+        self.version().layout.time_column.ok_or(TableError::NoTimeColumn)?;
+        Ok(Scan::new(&self.rows, &self.versions, from_ts..to_ts))
+    }
+
+    /// Fields in the order they are stored within a row.
+    #[inline(always)]
+    pub fn columns(&self) -> &[Column] {
+        &self.version().layout.columns
+    }
+
+    /// The current fields.
+    #[inline(always)]
+    pub fn schema(&self) -> &Schema {
+        &self.version().schema
+    }
+
+    /// Resolve `field_name` once for `write_fields`. Resolve it again after
+    /// the table's fields change.
+    #[inline(always)]
+    pub fn field_id(&self, field_name: &str) -> Result<FieldId, TableError> {
+        self.schema().field_id(field_name).ok_or_else(|| TableError::UnknownField(field_name.to_owned()))
+    }
+
+    /// Add a field without pausing producers or consumers. Rows written
+    /// before the field was added read it back as `default`, or as null if
+    /// there is no `default`, in which case the field must be nullable. Rows
+    /// from writes that started before this returns may read it back the same
+    /// way. Later writes fill it in as for any other field, with `config`'s
+    /// own default if they leave it out.
+    ///
+    /// Fails with `InvalidSchema` if the name is taken, there is no
+    /// `default` and the field is not nullable, it holds fewer rows than the
+    /// table, or the fields have changed too often; with `SizeMismatch` or
+    /// `TypeMismatch` if a default does not fit the field; with
+    /// `SchemaMismatch` for tables with a WAL, whose log holds rows of one
+    /// layout; and with `Closed` once the table is shut down. `FieldId`s
+    /// resolved before no longer work.
+    pub fn add_field(
+        &self,
+        field_name: impl Into<Arc<str>>,
+        config: FieldConfig,
+        default: Option<Box<[u8]>>,
+    ) -> Result<(), TableError> {
+        self.check_evolvable()?;
+        self.versions.add_field(field_name.into(), config, default)
+    }
+
+    /// Drop a field without pausing producers or consumers. Records read
+    /// from now on leave it out, whenever their row was written, and writes
+    /// that include it fail with `UnknownField`. Its inline values stay
+    /// allocated until the table is dropped.
+    ///
+    /// Fails with `UnknownField` if there is no such field, `InvalidSchema`
+    /// for the last field or a time column, and as `add_field` otherwise.
+    pub fn drop_field(&self, field_name: &str) -> Result<(), TableError> {
+        self.check_evolvable()?;
+        self.versions.drop_field(field_name)
+    }
+
+    fn check_evolvable(&self) -> Result<(), TableError> {
+        if self.is_closed() {
+            return Err(TableError::Closed);
+        }
+        if self.wal.is_some() {
+            return Err(TableError::SchemaMismatch(format!(
+                "`{}` has a WAL, which cannot hold rows of more than one layout",
+                self.name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Name of the designated time column, if the table has one.
+    #[inline(always)]
+    pub fn time_column(&self) -> Option<&str> {
+        self.version().layout.time_column_name()
+    }
+
+    #[inline(always)]
+    pub fn ingest_time_column(&self) -> Option<&str> {
+        self.version().layout.ingest_time_column_name()
+    }
+
+    #[inline(always)]
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.version().layout.clock
     }
 
     #[inline(always)]
     pub fn capacity(&self) -> usize {
-        self.field_configs.values().next().map_or(0, |fc| fc.ring_capacity)
+        self.rows.capacity()
     }
-} 
\ No newline at end of file
+
+    #[inline(always)]
+    pub fn overflow_policy(&self) -> OverflowPolicy {
+        self.rows.policy()
+    }
+
+    #[inline(always)]
+    pub fn topology(&self) -> Topology {
+        self.rows.topology()
+    }
+
+    /// Rows discarded by the overflow policy to make room for newer ones.
+    #[inline(always)]
+    pub fn evicted_count(&self) -> usize {
+        self.rows.evicted()
+    }
+
+    /// Latency of the sampled `write_record` and `write` calls that stored a
+    /// row, in nanoseconds. None unless the config enables latency sampling.
+    pub fn write_latency(&self) -> Option<HistogramSnapshot> {
+        self.latency.as_ref().map(|latency| latency.write.snapshot())
+    }
+
+    /// Latency of the sampled `read_one_record` calls that returned a row,
+    /// including `read` and `drain`, in nanoseconds. Reads of an empty table
+    /// are not recorded. None unless the config enables latency sampling.
+    pub fn read_latency(&self) -> Option<HistogramSnapshot> {
+        self.latency.as_ref().map(|latency| latency.read.snapshot())
+    }
+
+    // When `sampler` samples this call, the histograms and the time it started
+    #[inline(always)]
+    fn start_timer(&self, sampler: impl FnOnce(&LatencySampling) -> &Sampler) -> Option<(&LatencySampling, Instant)> {
+        let latency = self.latency.as_ref()?;
+        sampler(latency).sample().then(|| (latency, Instant::now()))
+    }
+
+    /// Read the table's and its ring's counters.
+    pub fn stats(&self) -> TableStats {
+        TableStats {
+            rows_written: self.counters.sum(ROWS_WRITTEN),
+            rows_read: self.counters.sum(ROWS_READ),
+            partial_writes: self.counters.sum(PARTIAL_WRITES),
+            evicted: self.rows.evicted() as u64,
+            record_count: self.record_count.load(Ordering::Acquire),
+            ring: self.rows.stats(),
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use crate::error::TableError;
+use crate::storage::record::{self, Record};
+use crate::storage::schema::{Column, DataType, Endianness};
+use crate::storage::table::TableConfig;
+
+pub use open_rust_timeseries_db_derive::TableRecord;
+
+/// A Rust type that maps onto a single field.
+pub trait ColumnType: Sized {
+    const DATA_TYPE: DataType;
+
+    /// Whether the field may be null, as it is for `Option`s.
+    const NULLABLE: bool = false;
+
+    /// Hand the field's encoding to `put`, or `None` for a null, without
+    /// allocating.
+    fn encode_field<R>(&self, endianness: Endianness, put: impl FnOnce(Option<&[u8]>) -> R) -> R;
+
+    /// Decode the bytes stored for `column`, or `None` if it is null there.
+    fn decode_field(column: &Column, value: Option<&[u8]>) -> Result<Self, TableError>;
+}
+
+/// Where `TableRecord::encode` stores a record's values.
+pub trait FieldWriter {
+    /// The columns being written, in order.
+    fn columns(&self) -> &[Column];
+
+    /// Store `value` as the next column's value, or a null for `None`.
+    fn put(&mut self, value: Option<&[u8]>) -> Result<(), TableError>;
+}
+
+/// Stored values that `TableRecord::decode` borrows a record's fields from.
+pub trait FieldReader {
+    /// The columns being read, in order.
+    fn columns(&self) -> &[Column];
+
+    /// The bytes of column `idx`, or `None` if it is null in this row.
+    fn value(&self, idx: usize) -> Option<&[u8]>;
+
+    /// The column named `field_name` and its bytes, looked up by name unless
+    /// it is at `idx`.
+    #[inline(always)]
+    fn field(&self, idx: usize, field_name: &str) -> Result<(&Column, Option<&[u8]>), TableError> {
+        let columns = self.columns();
+        let idx = match columns.get(idx) {
+            Some(column) if &*column.name == field_name => idx,
+            // Fields added to the table since shift the others
+            _ => columns.binary_search_by(|column| (*column.name).cmp(field_name))
+                .map_err(|_| TableError::UnknownField(field_name.to_owned()))?,
+        };
+        Ok((&columns[idx], self.value(idx)))
+    }
+}
+
+/// A struct that maps onto a table row, one field per column.
+///
+/// Usually derived with `#[derive(TableRecord)]`, which encodes straight into
+/// the slot a row is stored in and decodes straight from the stored bytes,
+/// with no intermediate `HashMap`, `Row` or `Record`. Records of fixed-width
+/// fields are written and read without allocating.
+pub trait TableRecord: Sized {
+    /// Schema with one field per struct field.
+    fn table_config(ring_capacity: usize) -> TableConfig;
+
+    /// Put every field into `writer`, in column order. Fails if the columns
+    /// are not the ones produced by `table_config`.
+    fn encode<W: FieldWriter>(&self, writer: &mut W) -> Result<(), TableError>;
+
+    /// Decode from stored values. Fails if a field is missing or mistyped.
+    fn decode<R: FieldReader>(reader: &R) -> Result<Self, TableError>;
+
+    /// Decode from a record, as `decode`.
+    #[inline(always)]
+    fn decode_record(record: &Record) -> Result<Self, TableError> {
+        Self::decode(record)
+    }
+}
+
+macro_rules! impl_column_type {
+    ($($ty:ty => $data_type:expr),+ $(,)?) => {
+        $(
+            impl ColumnType for $ty {
+                const DATA_TYPE: DataType = $data_type;
+
+                #[inline(always)]
+                fn encode_field<R>(&self, endianness: Endianness, put: impl FnOnce(Option<&[u8]>) -> R) -> R {
+                    match endianness {
+                        Endianness::Little => put(Some(&self.to_le_bytes())),
+                        Endianness::Big => put(Some(&self.to_be_bytes())),
+                    }
+                }
+
+                #[inline(always)]
+                fn decode_field(column: &Column, value: Option<&[u8]>) -> Result<Self, TableError> {
+                    record::decode_value::<$ty>(column, value)?.ok_or_else(|| record::null(column))
+                }
+            }
+        )+
+    };
+}
+
+impl_column_type!(
+    i8 => DataType::I8,
+    i16 => DataType::I16,
+    i32 => DataType::I32,
+    i64 => DataType::I64,
+    u8 => DataType::U8,
+    u16 => DataType::U16,
+    u32 => DataType::U32,
+    u64 => DataType::U64,
+    f32 => DataType::F32,
+    f64 => DataType::F64,
+);
+
+impl ColumnType for bool {
+    const DATA_TYPE: DataType = DataType::Bool;
+
+    #[inline(always)]
+    fn encode_field<R>(&self, _endianness: Endianness, put: impl FnOnce(Option<&[u8]>) -> R) -> R {
+        put(Some(&[*self as u8]))
+    }
+
+    #[inline(always)]
+    fn decode_field(column: &Column, value: Option<&[u8]>) -> Result<Self, TableError> {
+        record::decode_value::<bool>(column, value)?.ok_or_else(|| record::null(column))
+    }
+}
+
+impl<const N: usize> ColumnType for [u8; N] {
+    const DATA_TYPE: DataType = DataType::Bytes(N);
+
+    #[inline(always)]
+    fn encode_field<R>(&self, _endianness: Endianness, put: impl FnOnce(Option<&[u8]>) -> R) -> R {
+        put(Some(self))
+    }
+
+    #[inline(always)]
+    fn decode_field(column: &Column, value: Option<&[u8]>) -> Result<Self, TableError> {
+        let bytes = record::bytes_value(column, value)?.ok_or_else(|| record::null(column))?;
+        bytes.try_into().map_err(|_| TableError::SizeMismatch {
+            field: column.name.to_string(),
+            expected: N,
+            actual: bytes.len(),
+        })
+    }
+}
+
+impl ColumnType for Vec<u8> {
+    const DATA_TYPE: DataType = DataType::VarBinary;
+
+    #[inline(always)]
+    fn encode_field<R>(&self, _endianness: Endianness, put: impl FnOnce(Option<&[u8]>) -> R) -> R {
+        put(Some(self))
+    }
+
+    #[inline(always)]
+    fn decode_field(column: &Column, value: Option<&[u8]>) -> Result<Self, TableError> {
+        let bytes = record::bytes_value(column, value)?.ok_or_else(|| record::null(column))?;
+        Ok(bytes.to_vec())
+    }
+}
+
+impl ColumnType for String {
+    const DATA_TYPE: DataType = DataType::Utf8;
+
+    #[inline(always)]
+    fn encode_field<R>(&self, _endianness: Endianness, put: impl FnOnce(Option<&[u8]>) -> R) -> R {
+        put(Some(self.as_bytes()))
+    }
+
+    #[inline(always)]
+    fn decode_field(column: &Column, value: Option<&[u8]>) -> Result<Self, TableError> {
+        let text = record::str_value(column, value)?.ok_or_else(|| record::null(column))?;
+        Ok(text.to_owned())
+    }
+}
+
+impl<T: ColumnType> ColumnType for Option<T> {
+    const DATA_TYPE: DataType = T::DATA_TYPE;
+    const NULLABLE: bool = true;
+
+    #[inline(always)]
+    fn encode_field<R>(&self, endianness: Endianness, put: impl FnOnce(Option<&[u8]>) -> R) -> R {
+        match self {
+            Some(value) => value.encode_field(endianness, put),
+            None => put(None),
+        }
+    }
+
+    #[inline(always)]
+    fn decode_field(column: &Column, value: Option<&[u8]>) -> Result<Self, TableError> {
+        match value {
+            Some(_) => T::decode_field(column, value).map(Some),
+            None => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,449 @@
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError};
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+
+use crate::error::TableError;
+use crate::storage::columns::InlineColumns;
+use crate::storage::layout::{self, RowLayout};
+use crate::storage::record::Record;
+use crate::storage::schema::{Column, FieldId, Schema};
+use crate::storage::table::{FieldConfig, Row, TableConfig};
+use crate::storage::table_record::{FieldReader, TableRecord};
+use crate::storage::wal::Wal;
+
+// Versions a table can go through; each slot tags its row's version in a byte
+const MAX_VERSIONS: usize = 256;
+
+/// One generation of a table's fields: the layout rows written under it
+/// follow, and the byte columns holding their fixed-width values.
+pub(crate) struct Version {
+    pub(crate) index: usize,
+    pub(crate) schema: Schema,
+    pub(crate) layout: RowLayout,
+    inline: InlineColumns,
+    fields: Box<[u32]>,  // Identity of each column's field, which survives other fields changing
+    backfill: Box<[Option<Box<[u8]>>]>,  // What rows written before each field was added read back; None for null
+}
+
+impl Version {
+    #[inline(always)]
+    fn position(&self, field: u32) -> Option<usize> {
+        self.fields.iter().position(|&id| id == field)
+    }
+}
+
+/// Every version of a table's fields, so they can change while producers
+/// and consumers keep running.
+///
+/// Writers lay a row out with the current version and tag its slot with the
+/// version's index before publishing, under the same sequence protocol as
+/// `InlineColumns`. Readers rebuild the row with the version it was tagged
+/// with, then upgrade it to the current one: fields dropped since are left
+/// out, and fields added since read back their default, or null. Because a
+/// writer saw its version published before it published the row, a reader
+/// that loads the current version after claiming the row never sees an
+/// older one.
+///
+/// Versions are never freed before the table, so neither are the byte
+/// columns of dropped fields.
+pub(crate) struct SchemaVersions {
+    versions: Box<[OnceLock<Box<Version>>]>,
+    current: AtomicUsize,
+    tags: Box<[AtomicU8]>,  // Version of the row in each slot
+    mask: usize,
+    next_field: Mutex<u32>,  // Identity for the next field added; held while changing fields
+}
+
+impl SchemaVersions {
+    pub(crate) fn new(config: &TableConfig, capacity: usize) -> Result<Self, TableError> {
+        let layout = RowLayout::try_new(config)?;
+        let inline = InlineColumns::new(&layout.columns, capacity);
+        let fields = (0..layout.columns.len() as u32).collect();
+        let backfill = vec![None; layout.columns.len()].into();
+        let first = Version { index: 0, schema: config.schema.clone(), layout, inline, fields, backfill };
+
+        let versions: Box<[OnceLock<Box<Version>>]> = (0..MAX_VERSIONS).map(|_| OnceLock::new()).collect();
+        let _ = versions[0].set(Box::new(first));
+        Ok(Self {
+            next_field: Mutex::new(config.schema.len() as u32),
+            versions,
+            current: AtomicUsize::new(0),
+            tags: (0..capacity).map(|_| AtomicU8::new(0)).collect(),
+            mask: capacity - 1,
+        })
+    }
+
+    /// The version new rows are written with.
+    #[inline(always)]
+    pub(crate) fn current(&self) -> &Version {
+        self.get(self.current.load(Ordering::Acquire))
+    }
+
+    #[inline(always)]
+    fn get(&self, index: usize) -> &Version {
+        self.versions[index].get().expect("a published version")
+    }
+
+    /// Publish a version with `config` added as `field_name`, backfilled as
+    /// `Table::add_field` describes.
+    pub(crate) fn add_field(
+        &self,
+        field_name: Arc<str>,
+        config: FieldConfig,
+        default: Option<Box<[u8]>>,
+    ) -> Result<(), TableError> {
+        if let Some(width) = config.data_type.fixed_width() {
+            if config.field_size_bytes != width {
+                return Err(TableError::InvalidSchema(format!(
+                    "field `{}` is {} bytes wide but declares field_size_bytes {}",
+                    field_name, width, config.field_size_bytes
+                )));
+            }
+        }
+        if config.ring_capacity <= self.mask {
+            return Err(TableError::InvalidSchema(format!(
+                "field `{}` holds {} rows, the table holds {}",
+                field_name, config.ring_capacity, self.mask + 1
+            )));
+        }
+        if let Some(default) = &config.default {
+            config.validate(&field_name, default)?;
+        }
+        match &default {
+            Some(default) => config.validate(&field_name, default)?,
+            None if config.nullable => {}
+            None => {
+                return Err(TableError::InvalidSchema(format!(
+                    "field `{}` needs a default or must be nullable, for the rows written before it",
+                    field_name
+                )));
+            }
+        }
+
+        let mut next_field = self.lock_changes();
+        let current = self.current();
+        let schema = current.schema.columns().iter()
+            .fold(Schema::builder(), |builder, column| builder.field(Arc::clone(&column.name), column.config.clone()))
+            .field(field_name, config)
+            .build()?;
+        self.publish(&mut next_field, current, schema, default)
+    }
+
+    /// Publish a version without `field_name`. The time columns cannot be
+    /// dropped, nor can the last field.
+    pub(crate) fn drop_field(&self, field_name: &str) -> Result<(), TableError> {
+        let mut next_field = self.lock_changes();
+        let current = self.current();
+        if current.schema.field_id(field_name).is_none() {
+            return Err(TableError::UnknownField(field_name.to_owned()));
+        }
+        let layout = &current.layout;
+        if [layout.time_column_name(), layout.ingest_time_column_name()].contains(&Some(field_name)) {
+            return Err(TableError::InvalidSchema(format!("`{}` is a time column of the table", field_name)));
+        }
+
+        let schema = current.schema.columns().iter()
+            .filter(|column| &*column.name != field_name)
+            .fold(Schema::builder(), |builder, column| builder.field(Arc::clone(&column.name), column.config.clone()))
+            .build()?;
+        self.publish(&mut next_field, current, schema, None)
+    }
+
+    // Lay out the version after `current` and make it current. Fields are
+    // matched by name, and a name `current` lacks is a new field, whose
+    // older rows read back `added`.
+    fn publish(
+        &self,
+        next_field: &mut u32,
+        current: &Version,
+        schema: Schema,
+        added: Option<Box<[u8]>>,
+    ) -> Result<(), TableError> {
+        let index = current.index + 1;
+        if index == MAX_VERSIONS {
+            return Err(TableError::InvalidSchema(format!(
+                "a table's fields can change at most {} times",
+                MAX_VERSIONS - 1
+            )));
+        }
+        let schema = schema.with_version(current.schema.version() + 1);
+        let kept: Vec<Option<usize>> = schema.columns().iter()
+            .map(|column| current.schema.field_id(&column.name).map(|id| id.index()))
+            .collect();
+
+        let fields = kept.iter()
+            .map(|kept| match kept {
+                Some(old) => current.fields[*old],
+                None => {
+                    *next_field += 1;
+                    *next_field - 1
+                }
+            })
+            .collect();
+        let backfill = kept.iter()
+            .map(|kept| match kept {
+                Some(old) => current.backfill[*old].clone(),
+                None => added.clone(),
+            })
+            .collect();
+
+        // Names, not positions, carry the time columns over; a table without
+        // a time column takes the first timestamp_ns field added
+        let layout = RowLayout::try_of(
+            &schema,
+            current.layout.time_column_name(),
+            current.layout.ingest_time_column_name(),
+            Arc::clone(&current.layout.clock),
+            current.layout.strict,
+        )?;
+        let version = Version {
+            index,
+            inline: InlineColumns::evolve(&current.inline, &layout.columns, &kept),
+            schema,
+            layout,
+            fields,
+            backfill,
+        };
+        let _ = self.versions[index].set(Box::new(version));
+        self.current.store(index, Ordering::Release);
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn lock_changes(&self) -> MutexGuard<'_, u32> {
+        self.next_field.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Tag slot `seq` with `version` and store the fixed-width values of a
+    /// row laid out with it, returning the variable-length rest for the ring.
+    ///
+    /// # Safety
+    /// As for `InlineColumns::put`.
+    #[inline(always)]
+    pub(crate) unsafe fn put(&self, version: usize, seq: usize, row: Row) -> Row {
+        // Published by the ring along with the values
+        self.tags[seq & self.mask].store(version as u8, Ordering::Relaxed);
+        unsafe { self.get(version).inline.put(seq, row) }
+    }
+
+    /// Tag slot `seq` with `version` and encode `record`, checked against it,
+    /// straight into the slot, logging it to `wal` too. Returns the
+    /// variable-length values for the ring, so a record of fixed-width
+    /// fields is stored without allocating.
+    ///
+    /// # Safety
+    /// As for `InlineColumns::put`.
+    #[inline(always)]
+    pub(crate) unsafe fn put_typed<T: TableRecord>(
+        &self,
+        version: usize,
+        seq: usize,
+        record: &T,
+        wal: Option<&mut Wal>,
+    ) -> Row {
+        let mut slot = unsafe { self.slot_writer(version, seq, wal) };
+        let layout = &slot.version.layout;
+        layout.store_typed(record, |idx, value| slot.put(idx, value));
+        slot.finish()
+    }
+
+    /// As `put_typed`, for borrowed values checked with
+    /// `RowLayout::check_values`.
+    ///
+    /// # Safety
+    /// As for `InlineColumns::put`.
+    #[inline(always)]
+    pub(crate) unsafe fn put_values(
+        &self,
+        version: usize,
+        seq: usize,
+        values: &[(FieldId, &[u8])],
+        wal: Option<&mut Wal>,
+    ) -> Row {
+        let mut slot = unsafe { self.slot_writer(version, seq, wal) };
+        let layout = &slot.version.layout;
+        layout.store_values(values, |idx, value| slot.put(idx, value));
+        slot.finish()
+    }
+
+    // Tag slot `seq` with `version` and start filling it
+    #[inline(always)]
+    unsafe fn slot_writer<'a>(&'a self, version: usize, seq: usize, mut wal: Option<&'a mut Wal>) -> SlotWriter<'a> {
+        self.tags[seq & self.mask].store(version as u8, Ordering::Relaxed);
+        let version = self.get(version);
+        unsafe { version.inline.put_valid(seq) };
+        if let Some(wal) = wal.as_mut() {
+            wal.begin_entry();
+        }
+        SlotWriter { version, seq, wal, boxed: Vec::new(), nulls: false }
+    }
+
+    /// Decode the row in slot `seq` as a `T` of the current version's
+    /// fields, borrowing its values where they are stored.
+    ///
+    /// # Safety
+    /// As for `InlineColumns::copy`.
+    #[inline(always)]
+    pub(crate) unsafe fn decode<T: TableRecord>(&self, seq: usize, boxed: &Row) -> Result<T, TableError> {
+        T::decode(&SlotFields { written: self.written(seq), to: self.current(), seq, boxed })
+    }
+
+    /// Take the row in slot `seq` as a record of the current version.
+    ///
+    /// # Safety
+    /// As for `InlineColumns::take`.
+    #[inline(always)]
+    pub(crate) unsafe fn take(&self, seq: usize, boxed: Row) -> Record {
+        let written = self.written(seq);
+        let row = unsafe { written.inline.take(seq, boxed) };
+        self.upgrade(written, row)
+    }
+
+    /// Copy out the row in slot `seq` as a record of the current version.
+    ///
+    /// # Safety
+    /// As for `InlineColumns::copy`.
+    #[inline(always)]
+    pub(crate) unsafe fn copy(&self, seq: usize, boxed: &Row) -> Record {
+        let (written, row) = unsafe { self.copy_raw(seq, boxed) };
+        self.upgrade(self.get(written), row)
+    }
+
+    /// Copy out the row in slot `seq` as written, with the index of its
+    /// version, to `upgrade` later.
+    ///
+    /// # Safety
+    /// As for `InlineColumns::copy`.
+    #[inline(always)]
+    pub(crate) unsafe fn copy_raw(&self, seq: usize, boxed: &Row) -> (usize, Row) {
+        let written = self.written(seq);
+        (written.index, unsafe { written.inline.copy(seq, boxed) })
+    }
+
+    /// Borrow the value `to`'s column `idx` has for the row in slot `seq`.
+    /// `to` must be no older than the row.
+    ///
+    /// # Safety
+    /// As for `InlineColumns::value`.
+    #[inline(always)]
+    pub(crate) unsafe fn value<'a>(&'a self, seq: usize, boxed: &'a Row, to: &'a Version, idx: usize) -> &'a [u8] {
+        let written = self.written(seq);
+        match written.position(to.fields[idx]) {
+            Some(written_idx) => unsafe { written.inline.value(seq, boxed, written_idx) },
+            None => to.backfill[idx].as_deref().unwrap_or_default(),
+        }
+    }
+
+    /// Bring a row written under version `written` to version `to`, which
+    /// must be no older.
+    #[inline(always)]
+    pub(crate) fn upgrade_raw(&self, written: usize, to: &Version, row: Row) -> Record {
+        self.upgrade_to(self.get(written), to, row)
+    }
+
+    #[inline(always)]
+    fn written(&self, seq: usize) -> &Version {
+        self.get(self.tags[seq & self.mask].load(Ordering::Relaxed) as usize)
+    }
+
+    #[inline(always)]
+    fn upgrade(&self, written: &Version, row: Row) -> Record {
+        self.upgrade_to(written, self.current(), row)
+    }
+
+    #[inline(always)]
+    fn upgrade_to(&self, written: &Version, to: &Version, row: Row) -> Record {
+        if to.index == written.index {
+            return Record::new(Arc::clone(&to.layout.columns), row);
+        }
+        let mut values: Vec<Option<Box<[u8]>>> = row.into_vec().into_iter().map(Some).collect();
+        let written_validity = values.get_mut(written.fields.len()).and_then(Option::take);
+        let mut validity = None;
+        let mut row: Vec<Box<[u8]>> = to.fields.iter().enumerate()
+            .map(|(idx, &field)| {
+                let value = match written.position(field) {
+                    Some(old) if written_validity.as_deref().is_some_and(|bits| !layout::is_valid(bits, old)) => None,
+                    Some(old) => values[old].take(),
+                    None => to.backfill[idx].clone(),
+                };
+                value.unwrap_or_else(|| {
+                    let columns = to.fields.len();
+                    layout::set_null(validity.get_or_insert_with(|| layout::all_valid(columns)), idx);
+                    Box::default()
+                })
+            })
+            .collect();
+        row.extend(validity);
+        Record::new(Arc::clone(&to.layout.columns), row.into_boxed_slice())
+    }
+}
+
+// Fills a claimed slot value by value, logging each as it goes
+struct SlotWriter<'a> {
+    version: &'a Version,
+    seq: usize,  // Claimed by the writer
+    wal: Option<&'a mut Wal>,
+    boxed: Vec<Box<[u8]>>,  // Variable-length values, for the ring
+    nulls: bool,
+}
+
+impl SlotWriter<'_> {
+    // Store column `idx`'s value, or a null for None; columns in order
+    #[inline(always)]
+    fn put(&mut self, idx: usize, value: Option<&[u8]>) {
+        // SAFETY: the writer has claimed the slot and not yet published it
+        let inline = match value {
+            Some(value) => unsafe { self.version.inline.put_value(self.seq, idx, value) },
+            None => {
+                self.nulls = true;
+                unsafe { self.version.inline.put_null(self.seq, idx) }
+            }
+        };
+        let value = value.unwrap_or_default();
+        if !inline {
+            self.boxed.push(Box::from(value));
+        }
+        if let Some(wal) = self.wal.as_mut() {
+            wal.push_value(value);
+        }
+    }
+
+    // Log the validity bitmap after the values if the row has nulls, and
+    // return the values for the ring
+    #[inline(always)]
+    fn finish(mut self) -> Row {
+        if let (Some(wal), true) = (self.wal.as_mut(), self.nulls) {
+            // SAFETY: as in `put`, and every value is stored
+            let bits = unsafe { self.version.inline.nulls(self.seq) };
+            wal.push_value(bits.expect("a row with nulls"));
+        }
+        self.boxed.into_boxed_slice()
+    }
+}
+
+// The row in one slot, read with a version no older than it was written with
+struct SlotFields<'a> {
+    written: &'a Version,
+    to: &'a Version,
+    seq: usize,  // Held by the reader
+    boxed: &'a Row,
+}
+
+impl FieldReader for SlotFields<'_> {
+    #[inline(always)]
+    fn columns(&self) -> &[Column] {
+        &self.to.layout.columns
+    }
+
+    #[inline(always)]
+    fn value(&self, idx: usize) -> Option<&[u8]> {
+        let written_idx = match self.written.index == self.to.index {
+            true => Some(idx),
+            false => self.written.position(self.to.fields[idx]),
+        };
+        match written_idx {
+            // SAFETY: the reader holds the slot
+            Some(written_idx) => unsafe { self.written.inline.get(self.seq, self.boxed, written_idx) },
+            None => self.to.backfill[idx].as_deref(),
+        }
+    }
+}
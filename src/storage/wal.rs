@@ -0,0 +1,481 @@
+#[cfg(test)]
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::TableError;
+use crate::storage::checksum::crc32;
+use crate::storage::layout::RowLayout;
+use crate::storage::table::Row;
+
+const MAGIC: &[u8; 8] = b"ORTSWAL\0";
+const VERSION: u32 = 1;
+const SEGMENT_HEADER_LEN: usize = 12;  // Magic + version
+const ENTRY_HEADER_LEN: usize = 8;  // Payload length + CRC
+const DEFAULT_SEGMENT_BYTES: u64 = 64 * 1024 * 1024;
+
+/// When appended entries are forced to stable storage.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SyncPolicy {
+    /// fsync before every write returns
+    #[default]
+    EveryWrite,
+    /// fsync at most once per interval; a power loss can lose that window
+    GroupCommit(Duration),
+    /// Leave flushing to the OS; survives a process crash, not a power loss
+    Never,
+}
+
+/// Where and how a table logs its writes.
+#[derive(Clone, Debug)]
+pub struct WalConfig {
+    /// Directory holding this table's segments; one directory per table
+    pub dir: PathBuf,
+    /// A segment is closed and a new one started once it reaches this size
+    pub segment_bytes: u64,
+    pub sync: SyncPolicy,
+}
+
+impl WalConfig {
+    #[inline(always)]
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            segment_bytes: DEFAULT_SEGMENT_BYTES,
+            sync: SyncPolicy::EveryWrite,
+        }
+    }
+
+    #[inline(always)]
+    pub fn with_segment_bytes(mut self, segment_bytes: u64) -> Self {
+        self.segment_bytes = segment_bytes;
+        self
+    }
+
+    #[inline(always)]
+    pub fn with_sync(mut self, sync: SyncPolicy) -> Self {
+        self.sync = sync;
+        self
+    }
+}
+
+/// Rows recovered from the log, oldest first, and the sequence number of the
+/// first one.
+pub(crate) struct Replay {
+    pub(crate) start: usize,
+    pub(crate) rows: VecDeque<Row>,
+}
+
+/// Append-only, segmented log of accepted writes.
+///
+/// Each segment starts with a magic and version, followed by entries of
+/// `[payload len: u32][crc32: u32][seq: u64][head: u64]` and, per column,
+/// `[len: u32][bytes]`, then the validity bitmap the same way if the row has
+/// nulls. `head` is the ring's consumed watermark right after the write, so
+/// replay knows which rows had already been read. Sealed segments whose rows
+/// have all been read are deleted, so the log only holds what replay needs.
+///
+/// A failed append is cut off the segment, so no entry is ever written
+/// after a torn one, and its entries are kept to be written again ahead of
+/// the next commit: the rows are already in the ring, and the log must not
+/// skip their sequence numbers.
+pub(crate) struct Wal {
+    config: WalConfig,
+    segment: u64,
+    sealed: VecDeque<(u64, usize)>,  // Sealed segments, each with the sequence after its last entry
+    file: Option<File>,  // None once closed
+    segment_len: u64,
+    last_sync: Instant,
+    dirty: bool,
+    pending: Vec<u8>,  // Encoded entries awaiting `commit`
+    pending_starts: Vec<usize>,
+    unwritten: Vec<u8>,  // Committed entries a failed append left out of the segment
+    unwritten_seq: usize,  // Sequence of the first of them
+    torn: bool,  // A failed append may have left part of an entry past `segment_len`
+}
+
+impl Wal {
+    /// Open the log in `config.dir`, replaying every segment. A torn entry at
+    /// the end of the last segment is a write that never completed and is cut
+    /// off; damage anywhere else is reported as `Corrupt`.
+    pub(crate) fn open(config: &WalConfig, layout: &RowLayout, capacity: usize) -> Result<(Self, Replay), TableError> {
+        fs::create_dir_all(&config.dir)?;
+        let segments = list_segments(&config.dir)?;
+
+        let mut head = 0;
+        let mut next_seq = None;
+        let mut rows: VecDeque<(usize, Row)> = VecDeque::with_capacity(capacity);
+        let mut sealed = VecDeque::new();
+        let mut last = None;
+        for (pos, &segment) in segments.iter().enumerate() {
+            let path = segment_path(&config.dir, segment);
+            let bytes = fs::read(&path)?;
+            let is_last = pos + 1 == segments.len();
+
+            let mut offset = SEGMENT_HEADER_LEN;
+            let header_ok = bytes.len() >= SEGMENT_HEADER_LEN
+                && &bytes[..8] == MAGIC
+                && bytes[8..12] == VERSION.to_le_bytes();
+            if !header_ok {
+                // A segment created just before a crash may not have its header yet
+                if !is_last || bytes.len() >= SEGMENT_HEADER_LEN {
+                    return Err(corrupt(&path, 0, "bad segment header"));
+                }
+                offset = 0;
+            }
+
+            while header_ok && offset < bytes.len() {
+                let Some(payload) = read_entry(&bytes[offset..]) else {
+                    if is_last {
+                        break;
+                    }
+                    return Err(corrupt(&path, offset, "torn or damaged entry"));
+                };
+                let (seq, entry_head, row) = decode_payload(payload, layout)
+                    .map_err(|reason| corrupt(&path, offset, &reason))?;
+                if next_seq.is_some_and(|next| next != seq) {
+                    return Err(corrupt(&path, offset, "sequence gap"));
+                }
+
+                next_seq = Some(seq + 1);
+                head = entry_head;
+                if rows.len() == capacity {
+                    rows.pop_front();
+                }
+                rows.push_back((seq, row));
+                offset += ENTRY_HEADER_LEN + payload.len();
+            }
+            if !is_last {
+                sealed.push_back((segment, next_seq.unwrap_or(0)));
+            }
+            last = Some((segment, offset));
+        }
+
+        let (segment, valid_len) = last.unwrap_or((0, 0));
+        let (file, segment_len) = open_segment(&config.dir, segment, valid_len)?;
+
+        while rows.front().is_some_and(|&(seq, _)| seq < head) {
+            rows.pop_front();
+        }
+        let start = rows.front().map_or(next_seq.unwrap_or(0).max(head), |&(seq, _)| seq);
+        let replay = Replay {
+            start,
+            rows: rows.into_iter().map(|(_, row)| row).collect(),
+        };
+
+        let mut wal = Self {
+            config: config.clone(),
+            segment,
+            sealed,
+            file: Some(file),
+            segment_len,
+            last_sync: Instant::now(),
+            dirty: false,
+            pending: Vec::new(),
+            pending_starts: Vec::new(),
+            unwritten: Vec::new(),
+            unwritten_seq: 0,
+            torn: false,
+        };
+        wal.remove_consumed(head)?;
+        Ok((wal, replay))
+    }
+
+    /// Serialize `row` ahead of `commit`, while the row can still be borrowed.
+    #[inline(always)]
+    pub(crate) fn encode(&mut self, row: &Row) {
+        self.begin_entry();
+        for value in row.iter() {
+            self.push_value(value);
+        }
+    }
+
+    /// Start serializing a row ahead of `commit`, for its values to follow
+    /// one by one through `push_value`.
+    #[inline(always)]
+    pub(crate) fn begin_entry(&mut self) {
+        self.pending_starts.push(self.pending.len());
+        // Header, seq and head are filled in by `commit`
+        self.pending.resize(self.pending.len() + ENTRY_HEADER_LEN + 16, 0);
+    }
+
+    #[inline(always)]
+    pub(crate) fn push_value(&mut self, value: &[u8]) {
+        self.pending.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        self.pending.extend_from_slice(value);
+    }
+
+    /// Append the rows passed to `encode` since the last commit, stored at
+    /// consecutive sequence numbers from `first_seq`.
+    pub(crate) fn commit(&mut self, first_seq: usize, head: usize) -> Result<(), TableError> {
+        if self.pending_starts.is_empty() {
+            return Ok(());
+        }
+        for (offset, &start) in self.pending_starts.iter().enumerate() {
+            let end = self.pending_starts.get(offset + 1).copied().unwrap_or(self.pending.len());
+            let entry = &mut self.pending[start..end];
+            let seq = first_seq.wrapping_add(offset) as u64;
+            entry[ENTRY_HEADER_LEN..ENTRY_HEADER_LEN + 8].copy_from_slice(&seq.to_le_bytes());
+            entry[ENTRY_HEADER_LEN + 8..ENTRY_HEADER_LEN + 16].copy_from_slice(&(head as u64).to_le_bytes());
+            let payload_len = entry.len() - ENTRY_HEADER_LEN;
+            let crc = crc32(&entry[ENTRY_HEADER_LEN..]);
+            entry[..4].copy_from_slice(&(payload_len as u32).to_le_bytes());
+            entry[4..8].copy_from_slice(&crc.to_le_bytes());
+        }
+
+        if self.unwritten.is_empty() {
+            self.unwritten_seq = first_seq;
+        }
+        self.unwritten.append(&mut self.pending);
+        self.pending_starts.clear();
+        self.append_unwritten()?;
+        match self.config.sync {
+            SyncPolicy::EveryWrite => self.sync()?,
+            SyncPolicy::GroupCommit(interval) if self.last_sync.elapsed() >= interval => self.sync()?,
+            SyncPolicy::GroupCommit(_) | SyncPolicy::Never => {}
+        }
+        self.remove_consumed(head)
+    }
+
+    /// Sync if a `GroupCommit` window has unsynced or unwritten writes and
+    /// has run its interval, so writes that stop are not left unsynced.
+    pub(crate) fn flush_idle(&mut self) -> Result<(), TableError> {
+        match self.config.sync {
+            SyncPolicy::GroupCommit(interval)
+                if (self.dirty || !self.unwritten.is_empty()) && self.last_sync.elapsed() >= interval => self.sync(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Force everything committed so far to stable storage.
+    pub(crate) fn sync(&mut self) -> Result<(), TableError> {
+        self.append_unwritten()?;
+        self.sync_file()
+    }
+
+    // Sync what the segment file holds, without retrying a failed append
+    fn sync_file(&mut self) -> Result<(), TableError> {
+        if self.dirty {
+            self.file()?.sync_data()?;
+            self.dirty = false;
+        }
+        self.last_sync = Instant::now();
+        Ok(())
+    }
+
+    fn append_unwritten(&mut self) -> Result<(), TableError> {
+        if self.unwritten.is_empty() {
+            return Ok(());
+        }
+        // A closed log must not start a segment either
+        let file = self.file.as_mut().ok_or(TableError::Closed)?;
+        if self.torn {
+            file.set_len(self.segment_len)?;
+            self.torn = false;
+        }
+        let batch_len = self.unwritten.len() as u64;
+        if self.segment_len > SEGMENT_HEADER_LEN as u64
+            && self.segment_len + batch_len > self.config.segment_bytes
+        {
+            self.rotate(self.unwritten_seq)?;
+        }
+
+        let file = self.file.as_mut().ok_or(TableError::Closed)?;
+        if let Err(err) = write_entries(file, &self.unwritten) {
+            // The file is in append mode, so cutting it back is enough for the
+            // next append to land at `segment_len`
+            self.torn = file.set_len(self.segment_len).is_err();
+            return Err(err.into());
+        }
+        self.segment_len += batch_len;
+        self.unwritten.clear();
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Sync and close the segment file, so another log can open the
+    /// directory. Commits fail with `Closed` until `reopen`.
+    pub(crate) fn close(&mut self) -> Result<(), TableError> {
+        self.sync()?;
+        self.file = None;
+        Ok(())
+    }
+
+    /// Append to the current segment again after `close`.
+    pub(crate) fn reopen(&mut self) -> Result<(), TableError> {
+        if self.file.is_none() {
+            let (file, _) = open_segment(&self.config.dir, self.segment, self.segment_len as usize)?;
+            self.file = Some(file);
+            self.torn = false;
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn file(&mut self) -> Result<&mut File, TableError> {
+        self.file.as_mut().ok_or(TableError::Closed)
+    }
+
+    // Seal the current segment, whose entries end before `next_seq`
+    fn rotate(&mut self, next_seq: usize) -> Result<(), TableError> {
+        if self.config.sync != SyncPolicy::Never {
+            self.sync_file()?;
+        }
+        let (file, segment_len) = open_segment(&self.config.dir, self.segment + 1, 0)?;
+        self.sealed.push_back((self.segment, next_seq));
+        self.segment += 1;
+        self.file = Some(file);
+        self.segment_len = segment_len;
+        Ok(())
+    }
+
+    // Delete the sealed segments holding only rows before the consumed
+    // watermark `head`, oldest first so replay never sees a gap
+    fn remove_consumed(&mut self, head: usize) -> Result<(), TableError> {
+        while let Some(&(segment, end)) = self.sealed.front() {
+            if end > head {
+                break;
+            }
+            match fs::remove_file(segment_path(&self.config.dir, segment)) {
+                Ok(()) => {}
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err.into()),
+            }
+            self.sealed.pop_front();
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Wal {
+    fn drop(&mut self) {
+        if self.config.sync != SyncPolicy::Never {
+            let _ = self.sync();
+        }
+    }
+}
+
+/// Under `GroupCommit`, sync `wal` from a background thread once a window
+/// with unsynced writes has run its interval, as a later commit would have.
+/// The thread ends within an interval of the log being dropped.
+pub(crate) fn spawn_flusher(wal: &Arc<Mutex<Wal>>, config: &WalConfig) -> Result<(), TableError> {
+    let SyncPolicy::GroupCommit(interval) = config.sync else {
+        return Ok(());
+    };
+    let wal = Arc::downgrade(wal);
+    thread::Builder::new()
+        .name("ortsdb-wal-flush".to_owned())
+        .spawn(move || loop {
+            thread::sleep(interval);
+            let Some(wal) = wal.upgrade() else {
+                return;
+            };
+            // A failed sync leaves the window dirty, for the next try
+            let _ = wal.lock().unwrap_or_else(PoisonError::into_inner).flush_idle();
+        })?;
+    Ok(())
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Bytes the next append on this thread writes before failing as if the
+    /// disk were full, to test recovery from a torn append.
+    pub(crate) static SHORT_WRITE: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+#[inline(always)]
+fn write_entries(file: &mut File, bytes: &[u8]) -> io::Result<()> {
+    #[cfg(test)]
+    if let Some(len) = SHORT_WRITE.take() {
+        file.write_all(&bytes[..len.min(bytes.len())])?;
+        return Err(io::Error::new(io::ErrorKind::WriteZero, "short write"));
+    }
+    file.write_all(bytes)
+}
+
+#[inline(always)]
+fn segment_path(dir: &Path, segment: u64) -> PathBuf {
+    dir.join(format!("{:020}.wal", segment))
+}
+
+fn list_segments(dir: &Path) -> Result<Vec<u64>, TableError> {
+    let mut segments = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let name = entry?.file_name();
+        let segment = name.to_str()
+            .and_then(|name| name.strip_suffix(".wal"))
+            .and_then(|stem| stem.parse::<u64>().ok());
+        segments.extend(segment);
+    }
+    segments.sort_unstable();
+    Ok(segments)
+}
+
+/// Open a segment for appending, cutting it back to `valid_len` bytes and
+/// writing the header if it has none.
+fn open_segment(dir: &Path, segment: u64, valid_len: usize) -> Result<(File, u64), TableError> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(segment_path(dir, segment))?;
+    file.set_len(valid_len as u64)?;
+    if valid_len < SEGMENT_HEADER_LEN {
+        file.set_len(0)?;
+        file.write_all(MAGIC)?;
+        file.write_all(&VERSION.to_le_bytes())?;
+        file.sync_data()?;
+        return Ok((file, SEGMENT_HEADER_LEN as u64));
+    }
+    Ok((file, valid_len as u64))
+}
+
+/// The payload of the entry at the start of `bytes`, if it is complete and
+/// its checksum matches.
+#[inline(always)]
+fn read_entry(bytes: &[u8]) -> Option<&[u8]> {
+    let header = bytes.get(..ENTRY_HEADER_LEN)?;
+    let len = u32::from_le_bytes(header[..4].try_into().ok()?) as usize;
+    let crc = u32::from_le_bytes(header[4..].try_into().ok()?);
+    let payload = bytes.get(ENTRY_HEADER_LEN..ENTRY_HEADER_LEN + len)?;
+    (crc32(payload) == crc).then_some(payload)
+}
+
+// A row with nulls has its validity bitmap logged after the columns
+fn decode_payload(mut payload: &[u8], layout: &RowLayout) -> Result<(usize, usize, Row), String> {
+    let seq = u64::from_le_bytes(take(&mut payload, 8)?.try_into().unwrap()) as usize;
+    let head = u64::from_le_bytes(take(&mut payload, 8)?.try_into().unwrap()) as usize;
+    let columns = layout.columns.len();
+    let mut row = Vec::with_capacity(columns + 1);
+    while row.len() < columns || (layout.nullable && row.len() == columns && !payload.is_empty()) {
+        let len = u32::from_le_bytes(take(&mut payload, 4)?.try_into().unwrap()) as usize;
+        row.push(Box::from(take(&mut payload, len)?));
+    }
+    if !payload.is_empty() {
+        return Err(SCHEMA_MISMATCH.to_owned());
+    }
+    let row = row.into_boxed_slice();
+    layout.check_row(&row).map_err(|err| err.to_string())?;
+    Ok((seq, head, row))
+}
+
+const SCHEMA_MISMATCH: &str = "entry does not match the table's columns";
+
+#[inline(always)]
+fn take<'a>(bytes: &mut &'a [u8], n: usize) -> Result<&'a [u8], String> {
+    if bytes.len() < n {
+        return Err(SCHEMA_MISMATCH.to_owned());
+    }
+    let (taken, rest) = bytes.split_at(n);
+    *bytes = rest;
+    Ok(taken)
+}
+
+#[inline(always)]
+fn corrupt(path: &Path, offset: usize, reason: &str) -> TableError {
+    TableError::Corrupt(format!("{} at byte {}: {}", path.display(), offset, reason))
+}
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::future::{Future, poll_fn};
+use std::pin::{Pin, pin};
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+use std::time::Duration;
+
+use futures_core::Stream;
+use futures_sink::Sink;
+
+use crate::error::TableError;
+use crate::memory::low_latency_mpmc_ring::LowLatencyMpmcRing;
+use crate::storage::schema::{DataType, Endianness, FieldValue};
+use crate::storage::table::{Table, TableConfig, FieldConfig};
+
+// A minimal executor, so the tests show no runtime is needed
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        thread::park();
+    }
+}
+
+async fn next<S: Stream + Unpin>(stream: &mut S) -> Option<S::Item> {
+    poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx)).await
+}
+
+async fn send<S: Sink<T> + Unpin, T>(sink: &mut S, item: T) -> Result<(), S::Error> {
+    poll_fn(|cx| Pin::new(&mut *sink).poll_ready(cx)).await?;
+    Pin::new(&mut *sink).start_send(item)?;
+    poll_fn(|cx| Pin::new(&mut *sink).poll_flush(cx)).await
+}
+
+fn price_table(capacity: usize) -> Arc<Table> {
+    let mut fields = HashMap::new();
+    fields.insert("price", FieldConfig::new(DataType::F64, capacity));
+    Arc::new(Table::new("prices", TableConfig::new(fields)))
+}
+
+fn price(value: f64) -> HashMap<&'static str, Box<[u8]>> {
+    HashMap::from([("price", value.encode(Endianness::Little))])
+}
+
+#[test]
+fn test_ring_wakes_a_polling_consumer() {
+    let ring = Arc::new(LowLatencyMpmcRing::new(2));
+    let producer = {
+        let ring = Arc::clone(&ring);
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            ring.enqueue(7u64).unwrap();
+            thread::sleep(Duration::from_millis(10));
+            ring.close();
+        })
+    };
+
+    assert_eq!(block_on(poll_fn(|cx| ring.poll_dequeue_with(cx, |_, item| item))), Ok(7));
+    assert_eq!(block_on(poll_fn(|cx| ring.poll_dequeue_with(cx, |_, item| item))), Err(TableError::Closed));
+    producer.join().unwrap();
+}
+
+#[test]
+fn test_ring_pollers_on_both_ends_never_miss_a_wake() {
+    const ITEMS: u64 = 20_000;
+    let ring = Arc::new(LowLatencyMpmcRing::new(2));
+    let consumer = {
+        let ring = Arc::clone(&ring);
+        thread::spawn(move || {
+            block_on(async {
+                let mut total = 0;
+                while let Ok(item) = poll_fn(|cx| ring.poll_dequeue_with(cx, |_, item| item)).await {
+                    total += item;
+                }
+                total
+            })
+        })
+    };
+
+    // Every enqueue and dequeue races the other side's registration
+    block_on(async {
+        for item in 1..=ITEMS {
+            while ring.try_enqueue(item).is_err() {
+                poll_fn(|cx| ring.poll_enqueue_ready(cx)).await.unwrap();
+            }
+        }
+    });
+    ring.close();
+    assert_eq!(consumer.join().unwrap(), ITEMS * (ITEMS + 1) / 2);
+}
+
+#[test]
+fn test_stream_yields_rows_as_they_arrive_and_ends_at_shutdown() {
+    let table = price_table(4);
+    let mut stream = table.subscribe_stream("gateway").unwrap();
+    let writer = {
+        let table = Arc::clone(&table);
+        thread::spawn(move || {
+            for value in [1.5, 2.5, 3.5] {
+                thread::sleep(Duration::from_millis(5));
+                table.write_record(price(value)).unwrap();
+            }
+            thread::sleep(Duration::from_millis(5));
+            table.shutdown().unwrap();
+        })
+    };
+
+    let prices = block_on(async {
+        let mut prices = Vec::new();
+        while let Some(record) = next(&mut stream).await {
+            prices.push(record.get_as::<f64>("price").unwrap());
+        }
+        prices
+    });
+    writer.join().unwrap();
+    assert_eq!(prices, vec![1.5, 2.5, 3.5]);
+    assert_eq!(stream.subscription().lag(), 0);
+}
+
+#[test]
+fn test_async_writes_wait_for_capacity() {
+    let table = price_table(2);
+    for value in [1.0, 2.0] {
+        table.write_record(price(value)).unwrap();
+    }
+    assert_eq!(table.write_record(price(3.0)), Err(TableError::Full));
+
+    let writer = {
+        let table = Arc::clone(&table);
+        thread::spawn(move || block_on(table.write_record_async(price(3.0))))
+    };
+    thread::sleep(Duration::from_millis(10));
+    assert_eq!(table.read_one_record().unwrap().unwrap().get_as::<f64>("price"), Ok(1.0));
+    assert_eq!(writer.join().unwrap(), Ok(()));
+
+    let rest: Vec<f64> = table.drain().map(|record| record.get_as::<f64>("price").unwrap()).collect();
+    assert_eq!(rest, vec![2.0, 3.0]);
+    assert_eq!(table.record_count.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn test_sink_delivers_every_record_in_order_and_fails_after_shutdown() {
+    const RECORDS: usize = 200;
+    let table = price_table(4);
+    let reader = {
+        let table = Arc::clone(&table);
+        thread::spawn(move || {
+            let mut prices = Vec::with_capacity(RECORDS);
+            while prices.len() < RECORDS {
+                match table.read_one_record().unwrap() {
+                    Some(record) => prices.push(record.get_as::<f64>("price").unwrap()),
+                    None => thread::yield_now(),
+                }
+            }
+            prices
+        })
+    };
+
+    block_on(async {
+        let mut sink = table.sink();
+        for i in 0..RECORDS {
+            send(&mut sink, price(i as f64)).await.unwrap();
+        }
+    });
+    let prices = reader.join().unwrap();
+    assert_eq!(prices, (0..RECORDS).map(|i| i as f64).collect::<Vec<_>>());
+
+    table.shutdown().unwrap();
+    assert_eq!(block_on(send(&mut table.sink(), price(0.0))), Err(TableError::Closed));
+    assert_eq!(block_on(table.write_record_async(price(0.0))), Err(TableError::Closed));
+}
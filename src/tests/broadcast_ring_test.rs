@@ -0,0 +1,157 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::error::TableError;
+use crate::memory::broadcast_ring::{BroadcastReader, BroadcastRing};
+use crate::memory::low_latency_mpmc_ring::OverflowPolicy;
+use crate::memory::wait_strategy::Blocking;
+
+fn read_available(reader: &mut BroadcastReader<'_, u64>) -> Vec<u64> {
+    std::iter::from_fn(|| reader.try_read().unwrap()).collect()
+}
+
+#[test]
+fn test_every_reader_sees_every_item_in_order() {
+    let ring = BroadcastRing::new(8);
+    let mut early = ring.subscribe().unwrap();
+    ring.publish(1u64).unwrap();
+    let mut late = ring.subscribe().unwrap();
+    for item in 2..=4 {
+        assert_eq!(ring.publish(item), Ok(item as usize - 1));
+    }
+
+    assert_eq!(read_available(&mut early), vec![1, 2, 3, 4]);
+    // Readers only see what is published after they subscribe
+    assert_eq!(read_available(&mut late), vec![2, 3, 4]);
+    assert_eq!((early.lag(), late.lag()), (0, 0));
+}
+
+#[test]
+fn test_slowest_reader_gates_producers_under_reject() {
+    let ring = BroadcastRing::new(4);
+    let mut fast = ring.subscribe().unwrap();
+    let mut slow = ring.subscribe().unwrap();
+    for item in 0..4u64 {
+        ring.publish(item).unwrap();
+    }
+    assert_eq!(read_available(&mut fast), vec![0, 1, 2, 3]);
+    assert_eq!(ring.publish(4), Err(TableError::Full));
+
+    assert_eq!(slow.try_read(), Ok(Some(0)));
+    ring.publish(4).unwrap();
+    assert_eq!(ring.publish(5), Err(TableError::Full));
+
+    // A dropped reader stops gating
+    drop(slow);
+    ring.publish(5).unwrap();
+    assert_eq!(read_available(&mut fast), vec![4, 5]);
+}
+
+#[test]
+fn test_lapped_reader_reports_what_it_missed_under_overwrite() {
+    let ring = BroadcastRing::with_policy(4, OverflowPolicy::Overwrite);
+    let mut reader = ring.subscribe().unwrap();
+    for item in 0..10u64 {
+        ring.publish(item).unwrap();
+    }
+    assert_eq!(reader.lag(), 10);
+
+    assert_eq!(reader.try_read(), Err(TableError::Lagged { missed: 6 }));
+    assert_eq!(read_available(&mut reader), vec![6, 7, 8, 9]);
+    assert_eq!(reader.missed(), 6);
+}
+
+#[test]
+fn test_concurrent_producers_reach_every_reader() {
+    const PER_PRODUCER: u64 = 1_000;
+    let ring = BroadcastRing::with_policy(16, OverflowPolicy::Block { timeout: None })
+        .with_wait_strategy(Arc::new(Blocking::new()));
+
+    thread::scope(|scope| {
+        let readers: Vec<_> = (0..3).map(|_| {
+            let mut reader = ring.subscribe().unwrap();
+            scope.spawn(move || {
+                let mut next = [0, PER_PRODUCER];
+                loop {
+                    match reader.read_timeout(Duration::from_secs(10)) {
+                        Ok(Some(item)) => {
+                            // Each producer's items arrive in the order it published them
+                            let producer = (item / PER_PRODUCER) as usize;
+                            assert_eq!(item, next[producer]);
+                            next[producer] += 1;
+                        }
+                        Ok(None) => panic!("timed out"),
+                        Err(err) => return (err, next),
+                    }
+                }
+            })
+        }).collect();
+
+        let producers: Vec<_> = (0..2).map(|p| {
+            let ring = &ring;
+            scope.spawn(move || {
+                for i in 0..PER_PRODUCER {
+                    ring.publish(p * PER_PRODUCER + i).unwrap();
+                }
+            })
+        }).collect();
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        ring.close();
+
+        for reader in readers {
+            assert_eq!(reader.join().unwrap(), (TableError::Closed, [PER_PRODUCER, 2 * PER_PRODUCER]));
+        }
+    });
+}
+
+#[test]
+fn test_lapped_readers_never_see_a_torn_item() {
+    const ITEMS: u64 = 5_000;
+    let ring: BroadcastRing<String> = BroadcastRing::with_policy(4, OverflowPolicy::Overwrite);
+
+    thread::scope(|scope| {
+        let readers: Vec<_> = (0..2).map(|_| {
+            let mut reader = ring.subscribe().unwrap();
+            scope.spawn(move || {
+                let (mut read, mut last) = (0, None);
+                while reader.position() < ITEMS as usize {
+                    match reader.try_read() {
+                        // Heap items would be freed under us if a producer replaced one mid-copy
+                        Ok(Some(item)) => {
+                            let value: u64 = item.parse().unwrap();
+                            assert!(last.is_none_or(|last| value > last));
+                            last = Some(value);
+                            read += 1;
+                        }
+                        Ok(None) | Err(TableError::Lagged { .. }) => thread::yield_now(),
+                        Err(err) => panic!("{}", err),
+                    }
+                }
+                assert_eq!(read + reader.missed(), ITEMS as usize);
+            })
+        }).collect();
+
+        for item in 0..ITEMS {
+            ring.publish(item.to_string()).unwrap();
+        }
+        for reader in readers {
+            reader.join().unwrap();
+        }
+    });
+}
+
+#[test]
+fn test_overwritten_and_remaining_items_are_dropped() {
+    let item = Arc::new(());
+    {
+        let ring = BroadcastRing::with_policy(4, OverflowPolicy::Overwrite);
+        for _ in 0..6 {
+            ring.publish(Arc::clone(&item)).unwrap();
+        }
+        assert_eq!(Arc::strong_count(&item), 5);
+    }
+    assert_eq!(Arc::strong_count(&item), 1);
+}
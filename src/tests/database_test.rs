@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::{drain_ids, temp_dir, u64_fields, u64_record};
+use crate::clock::ManualClock;
+use crate::error::TableError;
+use crate::memory::low_latency_mpmc_ring::OverflowPolicy;
+use crate::storage::database::{Database, DatabaseConfig};
+use crate::storage::schema::DataType;
+use crate::storage::table::{FieldConfig, Table, TableConfig};
+use crate::storage::wal::{SyncPolicy, WalConfig};
+
+const BLOCK: OverflowPolicy = OverflowPolicy::Block { timeout: Some(Duration::from_millis(3)) };
+
+fn orders(capacity: usize) -> TableConfig {
+    let mut fields = u64_fields("order_id", capacity);
+    fields.insert("at", FieldConfig::new(DataType::TimestampNs, capacity));
+    TableConfig::new(fields)
+}
+
+fn write_orders(table: &Table, ids: std::ops::Range<u64>) {
+    for order_id in ids {
+        table.write_record(u64_record("order_id", order_id)).unwrap();
+    }
+}
+
+#[test]
+fn test_create_list_look_up_and_drop_tables() {
+    let clock = Arc::new(ManualClock::new(42));
+    let db = Database::new(DatabaseConfig::new().with_clock(clock));
+    let fills = db.create_table("fills", orders(16)).unwrap();
+    db.create_table(String::from("orders"), orders(16)).unwrap();
+    assert!(matches!(db.create_table("fills", orders(16)), Err(TableError::TableExists(_))));
+    for name in ["", "..", "a/b"] {
+        assert!(matches!(db.create_table(name, orders(16)), Err(TableError::InvalidName(_))));
+    }
+    assert_eq!(db.table_names(), [Arc::from("fills"), Arc::from("orders")]);
+
+    // Lookups share the table, which stamps from the database's clock
+    write_orders(&fills, 0..1);
+    let record = db.table("fills").unwrap().read_one_record().unwrap().unwrap();
+    assert_eq!(record.get_as::<u64>("at"), Ok(42));
+
+    write_orders(&fills, 1..3);
+    let dropped = db.drop_table("fills").unwrap();
+    assert!(dropped.is_closed());
+    assert_eq!(drain_ids(|| dropped.read_one_record(), "order_id"), [1, 2]);
+    assert!(matches!(db.table("fills"), Err(TableError::NoSuchTable(_))));
+    assert!(matches!(db.drop_table("fills"), Err(TableError::NoSuchTable(_))));
+    assert_eq!(db.table_names(), [Arc::from("orders")]);
+}
+
+#[test]
+fn test_memory_budget_limits_tables_and_is_released_on_drop() {
+    let per_table = orders(64).reserved_bytes();
+    let db = Database::new(DatabaseConfig::new().with_memory_budget(per_table * 2));
+    db.create_table("a", orders(64)).unwrap();
+    db.create_table("b", orders(64)).unwrap();
+    assert_eq!(db.reserved_bytes(), per_table * 2);
+    assert_eq!(
+        db.create_table("c", orders(64)).err(),
+        Some(TableError::OutOfMemory { requested: per_table, available: 0 })
+    );
+
+    db.drop_table("a").unwrap();
+    db.create_table("c", orders(64)).unwrap();
+    // Growing a table counts only what it adds
+    assert!(matches!(
+        db.alter_table("c", |_| orders(128)),
+        Err(TableError::OutOfMemory { .. })
+    ));
+}
+
+#[test]
+fn test_alter_table_moves_unread_rows_to_the_new_table() {
+    let db = Database::new(DatabaseConfig::new());
+    let old = db.create_table("orders", orders(8)).unwrap();
+    write_orders(&old, 0..6);
+    old.read_one_record().unwrap();
+
+    let grown = db.alter_table("orders", |config| {
+        orders(32).with_overflow_policy(OverflowPolicy::DropOldest).with_clock(Arc::clone(&config.clock))
+    }).unwrap();
+    assert!(old.is_closed());
+    assert_eq!(grown.stats().record_count, 5);
+    assert_eq!(old.write_record(u64_record("order_id", 0)), Err(TableError::Closed));
+    assert_eq!((grown.capacity(), grown.overflow_policy()), (32, OverflowPolicy::DropOldest));
+    assert!(Arc::ptr_eq(&grown, &db.table("orders").unwrap()));
+
+    // Shrinking keeps the newest rows
+    write_orders(&grown, 6..10);
+    let shrunk = db.alter_table("orders", |_| orders(4)).unwrap();
+    assert_eq!(drain_ids(|| shrunk.read_one_record(), "order_id"), [6, 7, 8, 9]);
+
+    let mut fields = HashMap::new();
+    fields.insert("order_id", FieldConfig::new(DataType::U32, 4));
+    assert!(matches!(
+        db.alter_table("orders", |_| TableConfig::new(fields)),
+        Err(TableError::SchemaMismatch(_))
+    ));
+    assert!(matches!(db.alter_table("fills", |config| config), Err(TableError::NoSuchTable(_))));
+}
+
+#[test]
+fn test_reopened_database_restores_its_catalog_and_logged_rows() {
+    let dir = temp_dir("db-reopen");
+    let wal = WalConfig::new("ignored").with_sync(SyncPolicy::GroupCommit(Duration::from_millis(5)));
+    {
+        let db = Database::open(DatabaseConfig::new().with_dir(&dir)).unwrap();
+        let logged = db.create_table("orders", orders(16).with_wal(wal)).unwrap();
+        db.create_table("quotes", orders(8).with_overflow_policy(BLOCK)).unwrap();
+        db.create_table("fills", orders(16)).unwrap();
+        db.drop_table("fills").unwrap();
+        write_orders(&logged, 0..5);
+        logged.read_one_record().unwrap();
+        // Rows read before the last write are known to the log
+        write_orders(&logged, 5..6);
+        logged.shutdown().unwrap();
+    }
+    assert!(dir.join("wal").join("orders").is_dir());
+
+    let db = Database::open(DatabaseConfig::new().with_dir(&dir)).unwrap();
+    assert_eq!(db.table_names(), [Arc::from("orders"), Arc::from("quotes")]);
+    let logged = db.table("orders").unwrap();
+    assert_eq!(logged.time_column(), Some("at"));
+    // A table with a WAL is rebuilt from its log
+    let logged = db.alter_table("orders", |config| config.with_overflow_policy(OverflowPolicy::DropOldest)).unwrap();
+    assert_eq!(logged.overflow_policy(), OverflowPolicy::DropOldest);
+    assert_eq!(drain_ids(|| logged.read_one_record(), "order_id"), [1, 2, 3, 4, 5]);
+    let quotes = db.table("quotes").unwrap();
+    assert_eq!(quotes.capacity(), 8);
+    assert_eq!(quotes.overflow_policy(), BLOCK);
+
+    db.drop_table("orders").unwrap();
+    assert!(!dir.join("wal").join("orders").exists());
+    let db = Database::open(DatabaseConfig::new().with_dir(&dir)).unwrap();
+    assert_eq!(db.table_names(), [Arc::from("quotes")]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_failed_alter_leaves_the_old_table_in_place() {
+    let dir = temp_dir("db-failed-alter");
+    let db = Database::open(DatabaseConfig::new().with_dir(&dir)).unwrap();
+    let wal = WalConfig::new("ignored").with_segment_bytes(128);
+    let old = db.create_table("orders", orders(64).with_wal(wal)).unwrap();
+    write_orders(&old, 0..20);
+
+    // Damage a sealed segment, so replaying the log fails
+    let wal_dir = dir.join("wal").join("orders");
+    let mut segments: Vec<PathBuf> = fs::read_dir(&wal_dir).unwrap().map(|entry| entry.unwrap().path()).collect();
+    segments.sort();
+    assert!(segments.len() > 1);
+    let mut bytes = fs::read(&segments[0]).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xFF;
+    fs::write(&segments[0], bytes).unwrap();
+
+    assert!(matches!(db.alter_table("orders", |config| config), Err(TableError::Corrupt(_))));
+    assert!(!old.is_closed());
+    assert!(Arc::ptr_eq(&old, &db.table("orders").unwrap()));
+    write_orders(&old, 20..21);
+    assert_eq!(drain_ids(|| old.read_one_record(), "order_id"), (0..21).collect::<Vec<_>>());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_changes_the_catalog_cannot_record_are_undone() {
+    let dir = temp_dir("db-unrecorded");
+    let db = Database::open(DatabaseConfig::new().with_dir(&dir)).unwrap();
+    let orders_table = db.create_table("orders", orders(16)).unwrap();
+    let logged = db.create_table("logged", orders(16).with_wal(WalConfig::new("ignored"))).unwrap();
+    write_orders(&orders_table, 0..3);
+    write_orders(&logged, 0..3);
+
+    // A directory where the catalog goes cannot be replaced by a file
+    fs::remove_file(dir.join("catalog")).unwrap();
+    fs::create_dir_all(dir.join("catalog").join("blocker")).unwrap();
+    let fee = FieldConfig::new(DataType::F64, 16).with_nullable(true);
+    assert!(matches!(db.add_field("orders", "fee", fee, None), Err(TableError::Io(_))));
+    assert!(orders_table.field_id("fee").is_err());
+    for name in ["orders", "logged"] {
+        let old = db.table(name).unwrap();
+        let altered = db.alter_table(name, |config| config.with_overflow_policy(OverflowPolicy::DropOldest));
+        assert!(matches!(altered, Err(TableError::Io(_))));
+        assert!(Arc::ptr_eq(&old, &db.table(name).unwrap()));
+        assert_eq!(old.overflow_policy(), OverflowPolicy::Reject);
+        write_orders(&old, 3..4);
+        assert_eq!(drain_ids(|| old.read_one_record(), "order_id"), [0, 1, 2, 3]);
+    }
+    assert!(matches!(db.drop_table("orders"), Err(TableError::Io(_))));
+    assert!(!orders_table.is_closed());
+
+    // Dropping a table shuts down every handle to it
+    fs::remove_dir_all(dir.join("catalog")).unwrap();
+    db.drop_table("logged").unwrap();
+    assert_eq!(logged.write_record(u64_record("order_id", 4)), Err(TableError::Closed));
+    assert!(!dir.join("wal").join("logged").exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_corrupt_catalog_is_reported() {
+    let dir = temp_dir("db-corrupt");
+    {
+        let db = Database::open(DatabaseConfig::new().with_dir(&dir)).unwrap();
+        db.create_table("orders", orders(16)).unwrap();
+    }
+    let path = dir.join("catalog");
+    let mut bytes = fs::read(&path).unwrap();
+    let last = bytes.len() - 6;
+    bytes[last] ^= 0xFF;
+    fs::write(&path, bytes).unwrap();
+    assert!(matches!(Database::open(DatabaseConfig::new().with_dir(&dir)), Err(TableError::Corrupt(_))));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_configs_that_cannot_build_a_table_are_reported() {
+    let db = Database::new(DatabaseConfig::new());
+    assert!(matches!(
+        db.create_table("orders", orders(16).with_wal(WalConfig::new("ignored"))),
+        Err(TableError::InvalidSchema(_))
+    ));
+    assert!(matches!(
+        db.create_table("orders", orders(16).with_time_column("order_id")),
+        Err(TableError::InvalidSchema(_))
+    ));
+    let mut fields = HashMap::new();
+    fields.insert("order_id", FieldConfig::new(DataType::U64, 16).with_max_len(4));
+    assert!(matches!(db.create_table("orders", TableConfig::new(fields)), Err(TableError::InvalidSchema(_))));
+    // Rings hold a nonzero power of two rows
+    for capacity in [0, 1000] {
+        assert!(matches!(db.create_table("orders", orders(capacity)), Err(TableError::InvalidSchema(_))));
+        assert!(matches!(Table::open("orders", orders(capacity)), Err(TableError::InvalidSchema(_))));
+    }
+    assert!(db.table_names().is_empty());
+
+    // The catalog stays usable, and a failed alter keeps the old table
+    let old = db.create_table("orders", orders(16)).unwrap();
+    assert!(matches!(
+        db.alter_table("orders", |config| config.with_ingest_time_column("at")),
+        Err(TableError::InvalidSchema(_))
+    ));
+    assert!(!old.is_closed());
+    assert!(Arc::ptr_eq(&old, &db.table("orders").unwrap()));
+    assert!(matches!(Database::open(DatabaseConfig::new()), Err(TableError::InvalidSchema(_))));
+}
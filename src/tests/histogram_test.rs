@@ -0,0 +1,148 @@
+use std::thread;
+
+use super::{u64_fields, u64_record};
+use crate::histogram::{Histogram, HistogramSnapshot};
+use crate::storage::table::{Table, TableConfig};
+
+fn table(config: impl FnOnce(TableConfig) -> TableConfig) -> Table {
+    Table::new("orders", config(TableConfig::new(u64_fields("order_id", 64))))
+}
+
+fn assert_close(actual: u64, expected: u64) {
+    let error = actual.abs_diff(expected) as f64 / expected as f64;
+    assert!(error <= 1.0 / 32.0, "{} is not within 1/32 of {}", actual, expected);
+}
+
+#[test]
+fn test_percentiles_are_within_bucket_precision() {
+    let histogram = Histogram::new();
+    for value in 1..=10_000 {
+        histogram.record(value);
+    }
+
+    let snapshot = histogram.snapshot();
+    assert_eq!(snapshot.count(), 10_000);
+    assert_eq!((snapshot.min(), snapshot.max()), (1, 10_000));
+    assert_eq!(snapshot.mean(), 5_000.5);
+    assert_close(snapshot.p50(), 5_000);
+    assert_close(snapshot.p99(), 9_900);
+    assert_close(snapshot.p999(), 9_990);
+    assert_eq!(snapshot.p9999(), 10_000);
+    assert_eq!(snapshot.value_at_percentile(100.0), 10_000);
+}
+
+#[test]
+fn test_small_values_are_exact() {
+    let histogram = Histogram::new();
+    for value in 0..64 {
+        histogram.record(value);
+    }
+
+    let snapshot = histogram.snapshot();
+    for value in 0..64 {
+        let percentile = (value + 1) as f64 * 100.0 / 64.0;
+        assert_eq!(snapshot.value_at_percentile(percentile), value);
+    }
+}
+
+#[test]
+fn test_empty_and_out_of_range_values() {
+    let empty = Histogram::new().snapshot();
+    assert!(empty.is_empty());
+    assert_eq!((empty.min(), empty.max(), empty.p99(), empty.mean()), (0, 0, 0, 0.0));
+
+    // Values past the top bucket keep their exact max
+    let histogram = Histogram::new();
+    histogram.record(u64::MAX);
+    let snapshot = histogram.snapshot();
+    assert_eq!((snapshot.count(), snapshot.max(), snapshot.p50()), (1, u64::MAX, u64::MAX));
+}
+
+#[test]
+fn test_merged_snapshots_match_one_histogram() {
+    let (low, high, both) = (Histogram::new(), Histogram::new(), Histogram::new());
+    for value in 0..1_000 {
+        low.record(value);
+        both.record(value);
+    }
+    for value in 1_000_000..1_001_000 {
+        high.record(value);
+        both.record(value);
+    }
+
+    let mut merged = HistogramSnapshot::default();
+    merged.merge(&low.snapshot());
+    merged.merge(&high.snapshot());
+    assert_eq!(merged, both.snapshot());
+    assert_close(merged.p50(), 1_000);
+    assert_close(merged.p99(), 1_000_980);
+}
+
+#[test]
+fn test_concurrent_records_are_all_counted() {
+    const PER_THREAD: u64 = 10_000;
+    let histogram = Histogram::new();
+    thread::scope(|scope| {
+        for t in 0..4 {
+            let histogram = &histogram;
+            scope.spawn(move || {
+                for i in 0..PER_THREAD {
+                    histogram.record(t * PER_THREAD + i);
+                }
+            });
+        }
+    });
+
+    let snapshot = histogram.snapshot();
+    assert_eq!(snapshot.count(), 4 * PER_THREAD);
+    assert_eq!((snapshot.min(), snapshot.max()), (0, 4 * PER_THREAD - 1));
+    assert_eq!(snapshot.mean(), (4 * PER_THREAD - 1) as f64 / 2.0);
+}
+
+#[test]
+fn test_table_samples_write_and_read_latency() {
+    let table = table(|config| config.with_latency_sampling(1));
+    for order_id in 0..10 {
+        table.write_record(u64_record("order_id", order_id)).unwrap();
+    }
+    for _ in 0..4 {
+        table.read_one_record().unwrap().unwrap();
+    }
+    // Reads of an empty table are not timed
+    table.drain().count();
+    assert!(table.read_one_record().unwrap().is_none());
+
+    let (write, read) = (table.write_latency().unwrap(), table.read_latency().unwrap());
+    assert_eq!((write.count(), read.count()), (10, 10));
+    assert!(write.min() <= write.p50() && write.p50() <= write.max());
+}
+
+#[test]
+fn test_table_samples_one_call_in_n() {
+    let sampled = table(|config| config.with_latency_sampling(4));
+    for order_id in 0..40 {
+        sampled.write_record(u64_record("order_id", order_id)).unwrap();
+    }
+    assert_eq!(sampled.write_latency().unwrap().count(), 10);
+
+    // Calls to one table do not shift which calls another samples
+    let (first, second) = (table(|config| config.with_latency_sampling(2)), table(|config| config.with_latency_sampling(2)));
+    for order_id in 0..40 {
+        first.write_record(u64_record("order_id", order_id)).unwrap();
+        second.write_record(u64_record("order_id", order_id)).unwrap();
+    }
+    assert_eq!((first.write_latency().unwrap().count(), second.write_latency().unwrap().count()), (20, 20));
+
+    // Nor do reads shift which writes are sampled, or writes which reads
+    let alternating = table(|config| config.with_latency_sampling(2));
+    for order_id in 0..100 {
+        alternating.write_record(u64_record("order_id", order_id)).unwrap();
+        alternating.read_one_record().unwrap().unwrap();
+    }
+    assert_eq!((alternating.write_latency().unwrap().count(), alternating.read_latency().unwrap().count()), (50, 50));
+
+    let unsampled = table(|config| config);
+    unsampled.write_record(u64_record("order_id", 1)).unwrap();
+    assert!(unsampled.write_latency().is_none());
+    assert!(unsampled.read_latency().is_none());
+}
@@ -3,18 +3,15 @@ use std::sync::Arc;
 use std::thread;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::atomic::AtomicU64;
-use std::sync::Mutex;
-use std::borrow::Cow;
-use std::hint::black_box;
 use std::sync::atomic::AtomicBool;
 
+use crate::histogram::{Histogram, HistogramSnapshot};
+use crate::storage::schema::DataType;
 use crate::storage::table::{Table, TableConfig, FieldConfig};
 
 // Constants for performance tuning
 const RING_BUFFER_SIZE: usize = 16384;  // 16K entries per field
 const BATCH_SIZE: usize = 256;          // Optimal cache line usage
-const CACHE_LINE_SIZE: usize = 64;      // Common CPU cache line size
 const MAX_RETRIES: usize = 1000;
 
 // Align data to cache line boundaries to prevent false sharing
@@ -53,70 +50,26 @@ impl PreAllocatedRecord {
     }
 }
 
-// Cache-aligned performance stats
-#[repr(align(64))]
+// Latencies go into the library's histograms, so the test reports the numbers production would
 struct PerformanceStats {
-    write_latencies: Mutex<Vec<u64>>,
-    read_latencies: Mutex<Vec<u64>>,
+    write_latencies: Histogram,
+    read_latencies: Histogram,
     dropped_messages: AtomicUsize,
     total_messages: AtomicUsize,
-    max_latency: AtomicU64,
-    _padding: [u8; CACHE_LINE_SIZE - 40], // Pad to cache line
 }
 
 impl PerformanceStats {
-    fn new(capacity: usize) -> Self {
+    fn new() -> Self {
         Self {
-            write_latencies: Mutex::new(Vec::with_capacity(capacity)),
-            read_latencies: Mutex::new(Vec::with_capacity(capacity)),
+            write_latencies: Histogram::new(),
+            read_latencies: Histogram::new(),
             dropped_messages: AtomicUsize::new(0),
             total_messages: AtomicUsize::new(0),
-            max_latency: AtomicU64::new(0),
-            _padding: [0; CACHE_LINE_SIZE - 40],
-        }
-    }
-
-    #[inline(always)]
-    fn update_max_latency(&self, latency: u64) {
-        let mut current = self.max_latency.load(Ordering::Relaxed);
-        while latency > current {
-            match self.max_latency.compare_exchange_weak(
-                current,
-                latency,
-                Ordering::Relaxed,
-                Ordering::Relaxed,
-            ) {
-                Ok(_) => break,
-                Err(actual) => current = actual,
-            }
-        }
-    }
-
-    #[inline(always)]
-    fn add_read_latency(&self, latency: u64) {
-        if let Ok(mut latencies) = self.read_latencies.lock() {
-            latencies.push(latency);
         }
     }
 
-    #[inline(always)]
-    fn add_write_latency(&self, latency: u64) {
-        if let Ok(mut latencies) = self.write_latencies.lock() {
-            latencies.push(latency);
-        }
-    }
-
-    fn get_stats(&self) -> (Option<f64>, Option<f64>, u64) {
-        let avg_write = self.write_latencies.lock().ok().map(|latencies| {
-            latencies.iter().sum::<u64>() as f64 / latencies.len() as f64
-        });
-        
-        let avg_read = self.read_latencies.lock().ok().map(|latencies| {
-            latencies.iter().sum::<u64>() as f64 / latencies.len() as f64
-        });
-
-        let max = self.max_latency.load(Ordering::Relaxed);
-        (avg_write, avg_read, max)
+    fn get_stats(&self) -> (HistogramSnapshot, HistogramSnapshot) {
+        (self.write_latencies.snapshot(), self.read_latencies.snapshot())
     }
 }
 
@@ -126,23 +79,20 @@ fn test_full_market_data_system() {
     // Setup with static field names for zero allocation
     let mut fields = HashMap::new();
     let field_configs = [
-        ("symbol_id", 4),
-        ("price", 8),
-        ("quantity", 4),
-        ("timestamp", 8),
-        ("exchange_id", 1),
+        ("symbol_id", DataType::U32),
+        ("price", DataType::F64),
+        ("quantity", DataType::U32),
+        ("timestamp", DataType::TimestampNs),
+        ("exchange_id", DataType::U8),
     ];
 
-    for &(name, size) in field_configs.iter() {
-        fields.insert(name.into(), FieldConfig {
-            field_size_bytes: size,
-            ring_capacity: RING_BUFFER_SIZE,
-        });
+    for &(name, data_type) in field_configs.iter() {
+        fields.insert(name, FieldConfig::new(data_type, RING_BUFFER_SIZE));
     }
 
-    let table_config = TableConfig { fields };
-    let table = Arc::new(Table::new("market_data".into(), table_config));
-    let stats = Arc::new(PerformanceStats::new(RING_BUFFER_SIZE));
+    let table_config = TableConfig::new(fields);
+    let table = Arc::new(Table::new("market_data", table_config));
+    let stats = Arc::new(PerformanceStats::new());
     let start_time = Instant::now();
 
     const PRODUCER_COUNT: usize = 4;
@@ -162,7 +112,7 @@ fn test_full_market_data_system() {
             for i in 0..MESSAGES_PER_PRODUCER {
                 // Direct memory writes without intermediate allocations
                 record.symbol_id.copy_from_slice(&((100 + p_id) as u32).to_le_bytes());
-                record.price.copy_from_slice(&((1000.0 + (i as f64) * 0.01) as f64).to_le_bytes());
+                record.price.copy_from_slice(&(1000.0 + (i as f64) * 0.01).to_le_bytes());
                 record.quantity.copy_from_slice(&(100 + (i % 100) as u32).to_le_bytes());
                 record.exchange_id[0] = p_id as u8;
                 
@@ -176,10 +126,8 @@ fn test_full_market_data_system() {
                 let mut success = false;
                 
                 while !success && retry_count < MAX_RETRIES {
-                    if table.write_record(record.to_hashmap()) {
-                        let latency = write_start.elapsed().as_nanos() as u64;
-                        stats.update_max_latency(latency);
-                        stats.add_write_latency(latency);
+                    if table.write_record(record.to_hashmap()).is_ok() {
+                        stats.write_latencies.record_duration(write_start.elapsed());
                         stats.total_messages.fetch_add(1, Ordering::Relaxed);
                         success = true;
                         retry_count = 0;
@@ -208,64 +156,53 @@ fn test_full_market_data_system() {
     // Consumer threads
     const CONSUMER_COUNT: usize = 3;
     let mut consumers = Vec::with_capacity(CONSUMER_COUNT);
+    let producers_done = Arc::new(AtomicBool::new(false));
     
     for c_id in 0..CONSUMER_COUNT {
         let table = Arc::clone(&table);
         let stats = Arc::clone(&stats);
+        let producers_done = Arc::clone(&producers_done);
         
         let handle = thread::spawn(move || {
             let mut processed_count = 0;
             let mut batch_buffer = Vec::with_capacity(BATCH_SIZE);
             let target_messages = MESSAGES_PER_PRODUCER * PRODUCER_COUNT / CONSUMER_COUNT;
             
-            while processed_count < target_messages {
+            // Consumers compete for records, so stop once producers are done and the table is drained
+            loop {
+                let drained = producers_done.load(Ordering::Acquire);
                 batch_buffer.clear();
                 let read_start = Instant::now();
                 
                 // Batch reading for better cache utilization
-                for _ in 0..BATCH_SIZE {
-                    if let Some(record) = table.read_one_record() {
-                        batch_buffer.push(record);
-                    } else {
-                        break;
-                    }
-                }
+                let _ = table.read_batch(&mut batch_buffer, BATCH_SIZE);
 
                 if !batch_buffer.is_empty() {
-                    let latency = read_start.elapsed().as_nanos() as u64;
-                    stats.update_max_latency(latency);
-                    stats.add_read_latency(latency);
+                    stats.read_latencies.record_duration(read_start.elapsed());
                     
                     for record in &batch_buffer {
                         match c_id {
                             0 => {
                                 // Zero-copy price tracking
-                                if let (Some(price_bytes), Some(qty_bytes)) = (
-                                    record.get("price"),
-                                    record.get("quantity")
+                                if let (Ok(price), Ok(qty)) = (
+                                    record.get_as::<f64>("price"),
+                                    record.get_as::<u32>("quantity")
                                 ) {
-                                    if price_bytes.len() >= 8 && qty_bytes.len() >= 4 {
-                                        let price = f64::from_le_bytes(price_bytes[..8].try_into().unwrap());
-                                        let qty = u32::from_le_bytes(qty_bytes[..4].try_into().unwrap());
-                                        if processed_count % 1000 == 0 {
-                                            println!("Consumer {}: VWAP update - Price: {}, Qty: {}", 
-                                                   c_id, price, qty);
-                                        }
+                                    if processed_count % 1000 == 0 {
+                                        println!("Consumer {}: VWAP update - Price: {}, Qty: {}", 
+                                               c_id, price, qty);
                                     }
                                 }
                             },
                             1 => {
                                 // Zero-copy latency analysis
-                                if let Some(ts_bytes) = record.get("timestamp") {
-                                    if ts_bytes.len() >= 8 {
-                                        let msg_ts = u64::from_le_bytes(ts_bytes[..8].try_into().unwrap());
-                                        let current = SystemTime::now()
-                                            .duration_since(UNIX_EPOCH)
-                                            .unwrap()
-                                            .as_nanos() as u64;
-                                        if current > msg_ts {
-                                            stats.add_read_latency(current - msg_ts);
-                                        }
+                                if let Ok(msg_ts) = record.get_as::<u64>("timestamp") {
+                                    let current = SystemTime::now()
+                                        .duration_since(UNIX_EPOCH)
+                                        .unwrap()
+                                        .as_nanos() as u64;
+                                    if current > msg_ts {
+                                        stats.read_latencies.record(current - msg_ts);
                                     }
                                 }
                             },
@@ -278,12 +215,12 @@ fn test_full_market_data_system() {
                         }
                         processed_count += 1;
                     }
+                } else if drained {
+                    break;
+                } else if processed_count < target_messages / 2 {
+                    thread::yield_now();
                 } else {
-                    if processed_count < target_messages / 2 {
-                        thread::yield_now();
-                    } else {
-                        thread::sleep(Duration::from_micros(10));
-                    }
+                    thread::sleep(Duration::from_micros(10));
                 }
             }
             
@@ -296,16 +233,18 @@ fn test_full_market_data_system() {
     for p in producers {
         p.join().unwrap();
     }
+    producers_done.store(true, Ordering::Release);
     for c in consumers {
         c.join().unwrap();
     }
+    assert_eq!(table.record_count.load(Ordering::SeqCst), 0);
 
     // Performance analysis
     let total_time = start_time.elapsed();
     let total_messages = stats.total_messages.load(Ordering::Relaxed);
     let dropped_messages = stats.dropped_messages.load(Ordering::Relaxed);
     let messages_per_second = total_messages as f64 / total_time.as_secs_f64();
-    let (avg_write_latency, avg_read_latency, max_latency) = stats.get_stats();
+    let (write_latency, read_latency) = stats.get_stats();
 
     println!("\nSystem Performance Summary:");
     println!("-------------------------");
@@ -313,9 +252,11 @@ fn test_full_market_data_system() {
     println!("Total Messages: {}", total_messages);
     println!("Dropped Messages: {}", dropped_messages);
     println!("Messages/second: {:.2}", messages_per_second);
-    println!("Average Write Latency: {:.2}ns", avg_write_latency.unwrap_or(0.0));
-    println!("Average Read Latency: {:.2}ns", avg_read_latency.unwrap_or(0.0));
-    println!("Max Latency: {}ns", max_latency);
+    println!("Average Write Latency: {:.2}ns", write_latency.mean());
+    println!("Average Read Latency: {:.2}ns", read_latency.mean());
+    println!("P99 Write Latency: {}ns", write_latency.p99());
+    println!("P99 Read Latency: {}ns", read_latency.p99());
+    println!("Max Latency: {}ns", write_latency.max().max(read_latency.max()));
     println!("Current table size: {}", table.record_count.load(Ordering::Relaxed));
 }
 
@@ -331,81 +272,9 @@ mod latency_tests {
     const TEST_ITERATIONS: usize = 100_000;
     const PERCENTILES: &[f64] = &[50.0, 90.0, 99.0, 99.9, 99.99];
 
-    #[derive(Default)]
-    struct LatencyMetrics {
-        min_ns: u64,
-        max_ns: u64,
-        total_ns: u64,
-        samples: Vec<u64>,
-    }
-
-    impl LatencyMetrics {
-        fn new() -> Self {
-            Self {
-                min_ns: u64::MAX,
-                max_ns: 0,
-                total_ns: 0,
-                samples: Vec::with_capacity(TEST_ITERATIONS),
-            }
-        }
-
-        fn update(&mut self, latency: u64) {
-            self.min_ns = self.min_ns.min(latency);
-            self.max_ns = self.max_ns.max(latency);
-            self.total_ns += latency;
-            self.samples.push(latency);
-        }
-
-        fn percentile(&self, p: f64) -> u64 {
-            let mut sorted = self.samples.clone();
-            sorted.sort_unstable();
-            let index = ((p / 100.0) * (sorted.len() - 1) as f64).round() as usize;
-            sorted[index]
-        }
-
-        fn mean(&self) -> f64 {
-            self.total_ns as f64 / self.samples.len() as f64
-        }
-    }
-
-    // Thread-safe metrics wrapper
-    struct ThreadMetrics {
-        metrics: Mutex<LatencyMetrics>,
-    }
-
-    impl ThreadMetrics {
-        fn new() -> Self {
-            Self {
-                metrics: Mutex::new(LatencyMetrics::new()),
-            }
-        }
-
-        fn update(&self, latency: u64) {
-            if let Ok(mut metrics) = self.metrics.lock() {
-                metrics.update(latency);
-            }
-        }
-
-        fn get_metrics(&self) -> Option<LatencyMetrics> {
-            self.metrics.lock().ok().map(|metrics| metrics.clone())
-        }
-    }
-
-    // Make LatencyMetrics cloneable for thread safety
-    impl Clone for LatencyMetrics {
-        fn clone(&self) -> Self {
-            Self {
-                min_ns: self.min_ns,
-                max_ns: self.max_ns,
-                total_ns: self.total_ns,
-                samples: self.samples.clone(),
-            }
-        }
-    }
-
     // Producer function
     fn producer_thread(
-        metrics: Arc<ThreadMetrics>,
+        metrics: Arc<Histogram>,
         table: Arc<Table>,
         running: Arc<AtomicBool>,
     ) {
@@ -415,9 +284,8 @@ mod latency_tests {
             record.insert("data", Vec::from(42u64.to_le_bytes()).into_boxed_slice());
             
             let start = Instant::now();
-            if table.write_record(record.clone()) {
-                let latency = start.elapsed().as_nanos() as u64;
-                metrics.update(latency);
+            if table.write_record(record.clone()).is_ok() {
+                metrics.record_duration(start.elapsed());
             }
             thread::yield_now();
         }
@@ -425,15 +293,14 @@ mod latency_tests {
 
     // Consumer function
     fn consumer_thread(
-        metrics: Arc<ThreadMetrics>,
+        metrics: Arc<Histogram>,
         table: Arc<Table>,
         running: Arc<AtomicBool>,
     ) {
         while running.load(Ordering::Relaxed) {
             let start = Instant::now();
-            if let Some(_) = table.read_one_record() {
-                let latency = start.elapsed().as_nanos() as u64;
-                metrics.update(latency);
+            if let Ok(Some(_)) = table.read_one_record() {
+                metrics.record_duration(start.elapsed());
             }
             thread::yield_now();
         }
@@ -451,17 +318,14 @@ mod latency_tests {
 
         // Setup minimal table for latency testing
         let mut fields = HashMap::with_capacity(1);
-        fields.insert("data", FieldConfig {
-            field_size_bytes: 8,
-            ring_capacity: RING_BUFFER_SIZE,
-        });
+        fields.insert("data", FieldConfig::new(DataType::U64, RING_BUFFER_SIZE));
 
-        let table_config = TableConfig { fields };
+        // The table samples every call too, to compare its numbers with the test's
+        let table_config = TableConfig::new(fields).with_latency_sampling(1);
         let table = Arc::new(Table::new("latency_test", table_config));
         
-        // Pre-allocate buffers for all metrics
-        let mut write_metrics = LatencyMetrics::new();
-        let mut read_metrics = LatencyMetrics::new();
+        let write_metrics = Histogram::new();
+        let read_metrics = Histogram::new();
 
         // Warmup phase with zero allocations
         println!("Warming up...");
@@ -470,8 +334,8 @@ mod latency_tests {
             for _ in 0..WARMUP_ITERATIONS {
                 record.clear();
                 record.insert("data", Vec::from(42u64.to_le_bytes()).into_boxed_slice());
-                black_box(table.write_record(record.clone()));
-                black_box(table.read_one_record());
+                let _ = black_box(table.write_record(record.clone()));
+                let _ = black_box(table.read_one_record());
             }
         }
 
@@ -485,16 +349,14 @@ mod latency_tests {
                 record.insert("data", Vec::from(data.to_le_bytes()).into_boxed_slice());
                 
                 let start = Instant::now();
-                table.write_record(record.clone());
-                let latency = start.elapsed().as_nanos() as u64;
-                write_metrics.update(latency);
+                let _ = table.write_record(record.clone());
+                write_metrics.record_duration(start.elapsed());
 
                 // Read latency
                 let start = Instant::now();
                 let result = table.read_one_record();
-                let latency = start.elapsed().as_nanos() as u64;
-                read_metrics.update(latency);
-                black_box(result);
+                read_metrics.record_duration(start.elapsed());
+                let _ = black_box(result);
             }
         }
 
@@ -503,8 +365,8 @@ mod latency_tests {
         let running = Arc::new(AtomicBool::new(true));
         
         // Create thread metrics
-        let producer_metrics = Arc::new(ThreadMetrics::new());
-        let consumer_metrics = Arc::new(ThreadMetrics::new());
+        let producer_metrics = Arc::new(Histogram::new());
+        let consumer_metrics = Arc::new(Histogram::new());
 
         // Spawn threads
         let producer = {
@@ -534,31 +396,47 @@ mod latency_tests {
         consumer.join().unwrap();
 
         // Get results
-        let producer_results = producer_metrics.get_metrics().unwrap();
-        let consumer_results = consumer_metrics.get_metrics().unwrap();
+        let producer_results = producer_metrics.snapshot();
+        let consumer_results = consumer_metrics.snapshot();
 
         // Print results
         println!("\nSingle-threaded Write Latencies:");
-        print_metrics(&write_metrics);
+        print_metrics(&write_metrics.snapshot());
 
         println!("\nSingle-threaded Read Latencies:");
-        print_metrics(&read_metrics);
+        print_metrics(&read_metrics.snapshot());
 
         println!("\nMulti-threaded Write Latencies:");
         print_metrics(&producer_results);
 
         println!("\nMulti-threaded Read Latencies:");
         print_metrics(&consumer_results);
+
+        // Every timed call above, plus the warmup, as the table itself saw it
+        let mut all_writes = write_metrics.snapshot();
+        all_writes.merge(&producer_results);
+        let table_writes = table.write_latency().unwrap();
+        assert!(table_writes.count() >= all_writes.count());
+
+        println!("\nAll Write Latencies, Measured by the Test:");
+        print_metrics(&all_writes);
+
+        println!("\nAll Write Latencies, Sampled by the Table:");
+        print_metrics(&table_writes);
+
+        println!("\nAll Read Latencies, Sampled by the Table:");
+        print_metrics(&table.read_latency().unwrap());
     }
 
-    fn print_metrics(metrics: &LatencyMetrics) {
-        println!("  Min latency: {} ns", metrics.min_ns);
-        println!("  Max latency: {} ns", metrics.max_ns);
+    fn print_metrics(metrics: &HistogramSnapshot) {
+        println!("  Samples: {}", metrics.count());
+        println!("  Min latency: {} ns", metrics.min());
+        println!("  Max latency: {} ns", metrics.max());
         println!("  Mean latency: {:.2} ns", metrics.mean());
         
         println!("  Percentiles:");
         for &p in PERCENTILES {
-            println!("    P{:.2}: {} ns", p, metrics.percentile(p));
+            println!("    P{:.2}: {} ns", p, metrics.value_at_percentile(p));
         }
     }
 
@@ -569,12 +447,9 @@ mod latency_tests {
 
         // Setup minimal table for latency testing
         let mut fields = HashMap::with_capacity(1);
-        fields.insert("data", FieldConfig {
-            field_size_bytes: 8,
-            ring_capacity: RING_BUFFER_SIZE,
-        });
+        fields.insert("data", FieldConfig::new(DataType::U64, RING_BUFFER_SIZE));
 
-        let table_config = TableConfig { fields };
+        let table_config = TableConfig::new(fields);
         let table = Arc::new(Table::new("instruction_latency_test", table_config));
         
         // Pre-allocate test data
@@ -585,8 +460,8 @@ mod latency_tests {
         for _ in 0..1000 {
             record.clear();
             record.insert("data", test_data.clone());
-            black_box(table.write_record(record.clone()));
-            black_box(table.read_one_record());
+            let _ = black_box(table.write_record(record.clone()));
+            let _ = black_box(table.read_one_record());
         }
 
         // Measure individual instruction latencies
@@ -635,7 +510,7 @@ mod latency_tests {
                 record.clear();
                 record.insert("data", test_data.clone());
                 let start = Instant::now();
-                black_box(table.write_record(record.clone()));
+                let _ = black_box(table.write_record(record.clone()));
                 total_ns += start.elapsed().as_nanos() as u64;
             }
             latencies.insert("Write Record (No Contention)", total_ns / iterations);
@@ -646,7 +521,7 @@ mod latency_tests {
             let mut total_ns = 0;
             for _ in 0..iterations {
                 let start = Instant::now();
-                black_box(table.read_one_record());
+                let _ = black_box(table.read_one_record());
                 total_ns += start.elapsed().as_nanos() as u64;
             }
             latencies.insert("Read Record (No Contention)", total_ns / iterations);
@@ -663,7 +538,7 @@ mod latency_tests {
                 while running_clone.load(Ordering::Relaxed) {
                     let mut record = HashMap::with_capacity(1);
                     record.insert("data", Vec::from(42u64.to_le_bytes()).into_boxed_slice());
-                    black_box(table_clone.write_record(record));
+                    let _ = black_box(table_clone.write_record(record));
                     thread::yield_now();
                 }
             });
@@ -673,7 +548,7 @@ mod latency_tests {
                 record.clear();
                 record.insert("data", test_data.clone());
                 let start = Instant::now();
-                black_box(table.write_record(record.clone()));
+                let _ = black_box(table.write_record(record.clone()));
                 total_ns += start.elapsed().as_nanos() as u64;
             }
             latencies.insert("Write Record (With Contention)", total_ns / iterations);
@@ -691,7 +566,7 @@ mod latency_tests {
             // Create contention with a background thread
             let background = thread::spawn(move || {
                 while running_clone.load(Ordering::Relaxed) {
-                    black_box(table_clone.read_one_record());
+                    let _ = black_box(table_clone.read_one_record());
                     thread::yield_now();
                 }
             });
@@ -699,7 +574,7 @@ mod latency_tests {
             let mut total_ns = 0;
             for _ in 0..iterations {
                 let start = Instant::now();
-                black_box(table.read_one_record());
+                let _ = black_box(table.read_one_record());
                 total_ns += start.elapsed().as_nanos() as u64;
             }
             latencies.insert("Read Record (With Contention)", total_ns / iterations);
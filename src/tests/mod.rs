@@ -1,2 +1,84 @@
+#[cfg(all(test, feature = "async"))]
+mod async_test;
 #[cfg(test)]
-mod integration_test; 
\ No newline at end of file
+mod broadcast_ring_test;
+#[cfg(test)]
+mod database_test;
+#[cfg(test)]
+mod histogram_test;
+#[cfg(test)]
+mod integration_test;
+#[cfg(test)]
+mod nullable_test;
+#[cfg(test)]
+mod ring_test;
+#[cfg(test)]
+mod schema_evolution_test;
+#[cfg(test)]
+mod schema_test;
+#[cfg(test)]
+mod scan_test;
+#[cfg(test)]
+mod shared_table_test;
+#[cfg(test)]
+mod slot_ring_test;
+#[cfg(test)]
+mod snapshot_test;
+#[cfg(test)]
+mod stats_test;
+#[cfg(test)]
+mod subscription_test;
+#[cfg(test)]
+mod table_record_test;
+#[cfg(test)]
+mod table_test;
+#[cfg(test)]
+mod time_column_test;
+#[cfg(test)]
+mod wal_test;
+#[cfg(test)]
+mod wait_strategy_test;
+#[cfg(test)]
+use std::collections::HashMap;
+#[cfg(test)]
+use std::path::PathBuf;
+
+#[cfg(test)]
+use crate::error::TableError;
+#[cfg(test)]
+use crate::storage::record::Record;
+#[cfg(test)]
+use crate::storage::schema::{DataType, Endianness, FieldValue};
+#[cfg(test)]
+use crate::storage::table::FieldConfig;
+
+// A path in the temp dir for `test` alone, with nothing left at it
+#[cfg(test)]
+fn temp_dir(test: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("ortsdb-{}-{}", std::process::id(), test));
+    let _ = std::fs::remove_dir_all(&path);
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+// Fields of a table keyed by u64 field `field`, for a test to add its own to
+#[cfg(test)]
+fn u64_fields(field: &'static str, capacity: usize) -> HashMap<&'static str, FieldConfig> {
+    HashMap::from([(field, FieldConfig::new(DataType::U64, capacity))])
+}
+
+// A record holding `value` in u64 field `field`, for a test to add its own to
+#[cfg(test)]
+fn u64_record(field: &'static str, value: u64) -> HashMap<&'static str, Box<[u8]>> {
+    HashMap::from([(field, value.encode(Endianness::Little))])
+}
+
+// Read rows until there are none left, collecting u64 field `field`
+#[cfg(test)]
+fn drain_ids(mut read: impl FnMut() -> Result<Option<Record>, TableError>, field: &str) -> Vec<u64> {
+    let mut ids = Vec::new();
+    while let Some(record) = read().unwrap() {
+        ids.push(record.get_as::<u64>(field).unwrap());
+    }
+    ids
+}
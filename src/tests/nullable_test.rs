@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Arc;
+
+use super::{temp_dir, u64_fields, u64_record};
+use crate::clock::ManualClock;
+use crate::error::TableError;
+use crate::storage::database::{Database, DatabaseConfig};
+use crate::storage::record::Record;
+use crate::storage::schema::{DataType, Endianness, FieldValue};
+use crate::storage::table::{FieldConfig, Table, TableConfig};
+use crate::storage::wal::WalConfig;
+
+fn quotes(capacity: usize) -> TableConfig {
+    let mut fields = u64_fields("order_id", capacity);
+    fields.insert("qty", FieldConfig::new(DataType::U32, capacity).with_default(1u32.encode(Endianness::Little)));
+    fields.insert("price", FieldConfig::new(DataType::F64, capacity).with_nullable(true));
+    fields.insert("venue", FieldConfig::new(DataType::Utf8, capacity).with_nullable(true));
+    fields.insert("at", FieldConfig::new(DataType::TimestampNs, capacity));
+    TableConfig::new(fields).with_clock(Arc::new(ManualClock::new(100)))
+}
+
+fn quote(order_id: u64, price: Option<f64>, venue: Option<&str>) -> HashMap<&'static str, Box<[u8]>> {
+    let mut record = u64_record("order_id", order_id);
+    if let Some(price) = price {
+        record.insert("price", price.encode(Endianness::Little));
+    }
+    if let Some(venue) = venue {
+        record.insert("venue", Box::from(venue.as_bytes()));
+    }
+    record
+}
+
+fn fields(record: &Record) -> (Option<u64>, Option<f64>, Option<&str>) {
+    (
+        record.get_opt::<u64>("order_id").unwrap(),
+        record.get_opt::<f64>("price").unwrap(),
+        record.get_str_opt("venue").unwrap(),
+    )
+}
+
+#[test]
+fn test_missing_fields_read_back_their_default_or_null() {
+    let table = Table::new("quotes", quotes(16));
+    table.write_record(quote(1, None, None)).unwrap();
+    table.write_record(quote(2, Some(9.5), Some(""))).unwrap();
+    assert_eq!(
+        table.write_record(HashMap::from([("qty", 2u32.encode(Endianness::Little))])),
+        Err(TableError::MissingField("order_id".to_owned()))
+    );
+
+    let record = table.read_one_record().unwrap().unwrap();
+    assert_eq!(fields(&record), (Some(1), None, None));
+    assert_eq!((record.get_as::<u32>("qty"), record.get_as::<u64>("at")), (Ok(1), Ok(100)));
+    assert_eq!(record.get_as::<f64>("price"), Err(TableError::Null("price".to_owned())));
+    assert_eq!(record.get_str("venue"), Err(TableError::Null("venue".to_owned())));
+    assert_eq!((record.get("venue"), record.is_null("venue")), (None, Ok(true)));
+    assert_eq!(record.len(), 5);
+    assert_eq!(record.iter().map(|(name, _)| name).collect::<Vec<_>>(), ["at", "order_id", "qty"]);
+
+    // An empty string is a value, not a null
+    let record = table.read_one_record().unwrap().unwrap();
+    assert_eq!(fields(&record), (Some(2), Some(9.5), Some("")));
+    assert_eq!(record.is_null("venue"), Ok(false));
+    assert_eq!(record.iter().count(), 5);
+    assert!(matches!(record.get_opt::<u64>("venue"), Err(TableError::TypeMismatch { .. })));
+    assert_eq!(record.is_null("fee"), Err(TableError::UnknownField("fee".to_owned())));
+}
+
+#[test]
+fn test_strict_tables_reject_records_missing_any_field() {
+    let table = Table::new("quotes", quotes(16).with_strict(true));
+    assert_eq!(
+        table.write_record(quote(1, Some(9.5), None)),
+        Err(TableError::MissingField("qty".to_owned()))
+    );
+
+    // Only the stamped time column may be left out, even by fields with defaults
+    let mut record = quote(1, Some(9.5), Some("XNAS"));
+    record.insert("qty", 3u32.encode(Endianness::Little));
+    table.write_record(record.clone()).unwrap();
+    record.remove("venue");
+    assert_eq!(table.write_batch(&mut vec![record]), Err(TableError::MissingField("venue".to_owned())));
+    let values = [
+        (table.field_id("order_id").unwrap(), 2u64.encode(Endianness::Little)),
+        (table.field_id("qty").unwrap(), 3u32.encode(Endianness::Little)),
+    ];
+    assert_eq!(table.write_fields(values), Err(TableError::MissingField("price".to_owned())));
+
+    let record = table.read_one_record().unwrap().unwrap();
+    assert_eq!((fields(&record), record.get_as::<u32>("qty")), ((Some(1), Some(9.5), Some("XNAS")), Ok(3)));
+    assert!(table.read_one_record().unwrap().is_none());
+}
+
+#[test]
+fn test_batches_and_field_ids_write_nulls() {
+    let table = Table::new("quotes", quotes(16));
+    let mut batch = vec![quote(1, Some(9.5), None), quote(2, None, Some("XLON")), quote(3, None, None)];
+    table.write_batch(&mut batch).unwrap();
+    let order_id = table.field_id("order_id").unwrap();
+    let venue = table.field_id("venue").unwrap();
+    table.write_fields([(order_id, 4u64.encode(Endianness::Little)), (venue, Box::from(*b"XNAS"))]).unwrap();
+
+    let records: Vec<_> = table.drain().collect();
+    let read: Vec<_> = records.iter().map(fields).collect();
+    assert_eq!(read, [
+        (Some(1), Some(9.5), None),
+        (Some(2), None, Some("XLON")),
+        (Some(3), None, None),
+        (Some(4), None, Some("XNAS")),
+    ]);
+    assert!(records.iter().all(|record| record.get_as::<u32>("qty") == Ok(1)));
+}
+
+#[test]
+fn test_borrowed_values_are_stored_and_logged_as_owned_ones() {
+    let dir = temp_dir("nullable-values");
+    {
+        let table = Table::open("quotes", quotes(16).with_wal(WalConfig::new(&dir))).unwrap();
+        let order_id = table.field_id("order_id").unwrap();
+        let price = table.field_id("price").unwrap();
+        let venue = table.field_id("venue").unwrap();
+        table.write_values(&[(order_id, &1u64.to_le_bytes()), (venue, b"XNAS")]).unwrap();
+        table.write_values(&[(order_id, &2u64.to_le_bytes()), (price, &9.5f64.to_le_bytes())]).unwrap();
+        table.write_values(&[(order_id, &3u64.to_le_bytes()), (venue, b"")]).unwrap();
+        assert_eq!(table.write_values(&[(price, &9.5f64.to_le_bytes())]), Err(TableError::MissingField("order_id".to_owned())));
+        assert!(matches!(table.write_values(&[(order_id, &[1, 2])]), Err(TableError::SizeMismatch { .. })));
+
+        let records: Vec<_> = table.drain().collect();
+        let read: Vec<_> = records.iter().map(fields).collect();
+        assert_eq!(read, [(Some(1), None, Some("XNAS")), (Some(2), Some(9.5), None), (Some(3), None, Some(""))]);
+        assert!(records.iter().all(|record| record.get_as::<u32>("qty") == Ok(1) && record.get_as::<u64>("at") == Ok(100)));
+    }
+
+    let table = Table::open("quotes", quotes(16).with_wal(WalConfig::new(&dir))).unwrap();
+    let read: Vec<_> = table.drain().map(|record| fields(&record).1).collect();
+    assert_eq!(read, [None, Some(9.5), None]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_nullable_field_added_later_is_null_in_older_rows() {
+    let table = Table::new("quotes", quotes(16));
+    table.write_record(quote(1, None, Some("XNAS"))).unwrap();
+    table.add_field("fee", FieldConfig::new(DataType::F64, 16).with_nullable(true), None).unwrap();
+    table.write_record(quote(2, Some(9.5), None)).unwrap();
+    let mut record = quote(3, None, None);
+    record.insert("fee", 0.25f64.encode(Endianness::Little));
+    table.write_record(record).unwrap();
+    // Nulls keep to their field as the columns around them move
+    table.drop_field("order_id").unwrap();
+
+    let read: Vec<_> = table.drain()
+        .map(|record| (
+            record.get_opt::<f64>("price").unwrap(),
+            record.get_str_opt("venue").unwrap().map(str::to_owned),
+            record.get_opt::<f64>("fee").unwrap(),
+        ))
+        .collect();
+    assert_eq!(read, [
+        (None, Some("XNAS".to_owned()), None),
+        (Some(9.5), None, None),
+        (None, None, Some(0.25)),
+    ]);
+}
+
+#[test]
+fn test_nulls_survive_snapshots_and_the_wal() {
+    let path = temp_dir("nullable-snapshot");
+    let table = Table::new("quotes", quotes(16));
+    table.write_record(quote(1, None, Some("XNAS"))).unwrap();
+    table.write_record(quote(2, Some(9.5), Some("XLON"))).unwrap();
+    table.snapshot(&path).unwrap();
+    let restored = Table::restore("quotes", quotes(16), &path).unwrap();
+    fs::remove_file(&path).unwrap();
+    let read: Vec<_> = restored.drain().map(|record| fields(&record).1).collect();
+    assert_eq!(read, [None, Some(9.5)]);
+
+    // The columns must agree on which fields are nullable too
+    table.snapshot(&path).unwrap();
+    let mut required = u64_fields("order_id", 16);
+    required.insert("qty", FieldConfig::new(DataType::U32, 16));
+    required.insert("price", FieldConfig::new(DataType::F64, 16));
+    required.insert("venue", FieldConfig::new(DataType::Utf8, 16));
+    required.insert("at", FieldConfig::new(DataType::TimestampNs, 16));
+    assert!(matches!(
+        Table::restore("quotes", TableConfig::new(required), &path),
+        Err(TableError::SchemaMismatch(_))
+    ));
+    fs::remove_file(&path).unwrap();
+
+    let dir = temp_dir("nullable-wal");
+    {
+        let logged = Table::open("quotes", quotes(16).with_wal(WalConfig::new(&dir))).unwrap();
+        let mut batch = vec![quote(1, None, None), quote(2, Some(9.5), None)];
+        logged.write_batch(&mut batch).unwrap();
+        logged.write_record(quote(3, None, Some("XNAS"))).unwrap();
+    }
+    let logged = Table::open("quotes", quotes(16).with_wal(WalConfig::new(&dir))).unwrap();
+    let read: Vec<_> = logged.drain()
+        .map(|record| (fields(&record).1, record.get_str_opt("venue").unwrap().map(str::to_owned)))
+        .collect();
+    assert_eq!(read, [(None, None), (Some(9.5), None), (None, Some("XNAS".to_owned()))]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_catalog_keeps_nullable_fields_defaults_and_strict_mode() {
+    let dir = temp_dir("nullable-catalog");
+    {
+        let db = Database::open(DatabaseConfig::new().with_dir(&dir)).unwrap();
+        db.create_table("quotes", quotes(16)).unwrap();
+        db.create_table("fills", quotes(16).with_strict(true)).unwrap();
+        db.add_field("quotes", "fee", FieldConfig::new(DataType::F64, 16).with_nullable(true), None).unwrap();
+    }
+
+    let db = Database::open(DatabaseConfig::new().with_dir(&dir)).unwrap();
+    let quotes = db.table("quotes").unwrap();
+    quotes.write_record(quote(1, None, None)).unwrap();
+    let record = quotes.read_one_record().unwrap().unwrap();
+    assert_eq!((fields(&record), record.get_as::<u32>("qty")), ((Some(1), None, None), Ok(1)));
+    assert_eq!(record.get_opt::<f64>("fee"), Ok(None));
+
+    let fills = db.table("fills").unwrap();
+    assert_eq!(fills.write_record(quote(1, None, None)), Err(TableError::MissingField("price".to_owned())));
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,350 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::TableError;
+use crate::memory::low_latency_mpmc_ring::{LowLatencyMpmcRing, OverflowPolicy, Topology};
+
+fn drain(ring: &LowLatencyMpmcRing<u64>) -> Vec<u64> {
+    let mut out = Vec::new();
+    while let Some(item) = ring.try_dequeue().unwrap() {
+        out.push(item);
+    }
+    out
+}
+
+#[test]
+fn test_reject_policy_leaves_ring_untouched() {
+    let ring = LowLatencyMpmcRing::with_policy(4, OverflowPolicy::Reject);
+    for i in 0..4 {
+        assert_eq!(ring.enqueue(i), Ok(0));
+    }
+    assert_eq!(ring.enqueue(4), Err(TableError::Full));
+    assert_eq!(ring.evicted(), 0);
+    assert_eq!(drain(&ring), vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn test_drop_oldest_and_overwrite_keep_the_freshest_items() {
+    for policy in [OverflowPolicy::DropOldest, OverflowPolicy::Overwrite] {
+        let ring = LowLatencyMpmcRing::with_policy(4, policy);
+        let evicted: usize = (0..10).map(|i| ring.enqueue(i).unwrap()).sum();
+
+        assert_eq!(evicted, 6, "{:?}", policy);
+        assert_eq!(ring.evicted(), 6, "{:?}", policy);
+        assert_eq!(drain(&ring), vec![6, 7, 8, 9], "{:?}", policy);
+
+        // The ring keeps working normally after a lap of evictions
+        assert_eq!(ring.enqueue(10), Ok(0));
+        assert_eq!(drain(&ring), vec![10]);
+    }
+}
+
+#[test]
+fn test_block_policy_times_out_when_nobody_consumes() {
+    let timeout = Duration::from_millis(20);
+    let ring = LowLatencyMpmcRing::with_policy(2, OverflowPolicy::Block { timeout: Some(timeout) });
+    ring.enqueue(1u64).unwrap();
+    ring.enqueue(2u64).unwrap();
+
+    let start = Instant::now();
+    assert_eq!(ring.enqueue(3), Err(TableError::Full));
+    assert!(start.elapsed() >= timeout);
+    assert_eq!(drain(&ring), vec![1, 2]);
+}
+
+#[test]
+fn test_block_policy_waits_for_a_consumer() {
+    let ring = Arc::new(LowLatencyMpmcRing::with_policy(8, OverflowPolicy::Block { timeout: None }));
+    const MESSAGES: u64 = 10_000;
+
+    let producer = {
+        let ring = Arc::clone(&ring);
+        thread::spawn(move || {
+            for i in 0..MESSAGES {
+                assert_eq!(ring.enqueue(i), Ok(0));
+            }
+        })
+    };
+
+    let mut expected = 0;
+    while expected < MESSAGES {
+        match ring.try_dequeue().unwrap() {
+            Some(item) => {
+                assert_eq!(item, expected, "blocking must not lose or reorder items");
+                expected += 1;
+            }
+            None => thread::yield_now(),
+        }
+    }
+    producer.join().unwrap();
+    assert_eq!(ring.evicted(), 0);
+}
+
+#[test]
+fn test_overwrite_accounts_for_every_item_under_contention() {
+    const PRODUCER_COUNT: usize = 3;
+    const MESSAGES_PER_PRODUCER: u64 = 20_000;
+
+    for policy in [OverflowPolicy::DropOldest, OverflowPolicy::Overwrite] {
+        let ring = Arc::new(LowLatencyMpmcRing::with_policy(16, policy));
+        let done = Arc::new(AtomicUsize::new(0));
+
+        let producers: Vec<_> = (0..PRODUCER_COUNT).map(|_| {
+            let ring = Arc::clone(&ring);
+            let done = Arc::clone(&done);
+            thread::spawn(move || {
+                for i in 0..MESSAGES_PER_PRODUCER {
+                    ring.enqueue(i).unwrap();
+                }
+                done.fetch_add(1, Ordering::Release);
+            })
+        }).collect();
+
+        let mut consumed = 0;
+        loop {
+            let finished = done.load(Ordering::Acquire) == PRODUCER_COUNT;
+            match ring.try_dequeue().unwrap() {
+                Some(_) => consumed += 1,
+                None if finished => break,
+                None => thread::yield_now(),
+            }
+        }
+        for p in producers {
+            p.join().unwrap();
+        }
+
+        let total = PRODUCER_COUNT * MESSAGES_PER_PRODUCER as usize;
+        assert_eq!(consumed + ring.evicted(), total, "{:?}", policy);
+    }
+}
+
+#[test]
+fn test_batches_claim_what_fits_and_leave_the_rest() {
+    let ring = LowLatencyMpmcRing::new(8);
+    let mut items = 0..12u32;
+    assert_eq!(ring.try_enqueue_batch(&mut items), 8);
+    assert_eq!(items.next(), Some(8), "refused items stay in the iterator");
+    assert_eq!(ring.try_enqueue_batch(&mut (100..101u32)), 0);
+
+    let mut out = Vec::new();
+    assert_eq!(ring.try_dequeue_batch(&mut out, 3), 3);
+    assert_eq!(ring.try_dequeue_batch(&mut out, 16), 5);
+    assert_eq!(out, (0..8).collect::<Vec<_>>());
+    assert_eq!(ring.try_dequeue_batch(&mut out, 16), 0);
+}
+
+#[test]
+fn test_batch_policies_match_single_enqueue() {
+    let ring = LowLatencyMpmcRing::with_policy(4, OverflowPolicy::Reject);
+    assert_eq!(ring.enqueue_batch(&mut (0..6u32)), Err(TableError::PartialWrite { written: 4 }));
+    assert_eq!(ring.enqueue_batch(&mut (0..6u32)), Err(TableError::Full));
+
+    for policy in [OverflowPolicy::DropOldest, OverflowPolicy::Overwrite] {
+        let ring = LowLatencyMpmcRing::with_policy(4, policy);
+        ring.enqueue(0u32).unwrap();
+        assert_eq!(ring.enqueue_batch(&mut (1..10u32)), Ok(6), "{:?}", policy);
+        let mut out = Vec::new();
+        ring.try_dequeue_batch(&mut out, 8);
+        assert_eq!(out, vec![6, 7, 8, 9], "{:?}", policy);
+    }
+}
+
+#[test]
+fn test_concurrent_batches_lose_and_duplicate_nothing() {
+    const PRODUCERS: u32 = 3;
+    const PER_PRODUCER: u32 = 20_000;
+    let ring = Arc::new(LowLatencyMpmcRing::with_policy(64, OverflowPolicy::Block { timeout: None }));
+    let consumed = Arc::new(AtomicUsize::new(0));
+
+    let producers: Vec<_> = (0..PRODUCERS).map(|p| {
+        let ring = Arc::clone(&ring);
+        thread::spawn(move || {
+            let start = p * PER_PRODUCER;
+            for chunk in (start..start + PER_PRODUCER).step_by(37) {
+                let mut items = chunk..(chunk + 37).min(start + PER_PRODUCER);
+                ring.enqueue_batch(&mut items).unwrap();
+            }
+        })
+    }).collect();
+    let consumers: Vec<_> = (0..2).map(|_| {
+        let ring = Arc::clone(&ring);
+        let consumed = Arc::clone(&consumed);
+        thread::spawn(move || {
+            let mut seen = Vec::new();
+            while consumed.load(Ordering::Relaxed) < (PRODUCERS * PER_PRODUCER) as usize {
+                let got = ring.try_dequeue_batch(&mut seen, 16);
+                consumed.fetch_add(got, Ordering::Relaxed);
+                if got == 0 {
+                    thread::yield_now();
+                }
+            }
+            seen
+        })
+    }).collect();
+
+    for producer in producers {
+        producer.join().unwrap();
+    }
+    let mut all: Vec<u32> = consumers.into_iter().flat_map(|c| c.join().unwrap()).collect();
+    all.sort_unstable();
+    assert_eq!(all, (0..PRODUCERS * PER_PRODUCER).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_with_variants_see_each_item_at_its_sequence_number() {
+    for policy in [OverflowPolicy::Reject, OverflowPolicy::Overwrite] {
+        let ring = LowLatencyMpmcRing::with_policy(4, policy);
+        ring.enqueue(0usize).unwrap();
+        ring.enqueue_with(|seq| seq).unwrap();
+        let mut items = 0..2u32;
+        ring.enqueue_batch_with(&mut items, |seq, _| seq).unwrap();
+
+        assert_eq!(ring.try_dequeue_with(|seq, item| (seq, item)).unwrap(), Some((0, 0)), "{:?}", policy);
+        let mut seen = Vec::new();
+        ring.dequeue_batch_with(8, |seq, item| seen.push((seq, item)));
+        assert_eq!(seen, vec![(1, 1), (2, 2), (3, 3)], "{:?}", policy);
+    }
+}
+
+#[test]
+fn test_dropping_the_ring_drops_the_items_it_still_holds() {
+    let item = Arc::new(());
+    {
+        let ring = LowLatencyMpmcRing::new(4);
+        // Wrap around once so the held items straddle the end of the buffer
+        for _ in 0..3 {
+            ring.enqueue(Arc::clone(&item)).unwrap();
+        }
+        assert_eq!(ring.drain().take(2).count(), 2);
+        for _ in 0..3 {
+            ring.enqueue(Arc::clone(&item)).unwrap();
+        }
+        assert_eq!(Arc::strong_count(&item), 5);
+    }
+    assert_eq!(Arc::strong_count(&item), 1);
+}
+
+#[test]
+fn test_closed_ring_refuses_enqueues_but_drains() {
+    let ring = LowLatencyMpmcRing::with_policy(4, OverflowPolicy::DropOldest);
+    ring.enqueue(1u32).unwrap();
+    ring.enqueue(2).unwrap();
+    ring.close();
+
+    assert!(ring.is_closed());
+    assert_eq!(ring.enqueue(3), Err(TableError::Closed));
+    assert_eq!(ring.try_enqueue(3), Err(TableError::Closed));
+    let mut items = 3..5u32;
+    assert_eq!(ring.enqueue_batch(&mut items), Err(TableError::Closed));
+    assert_eq!(items.len(), 2);
+    assert_eq!(ring.drain().collect::<Vec<_>>(), vec![1, 2]);
+    assert!(ring.is_empty());
+}
+
+#[test]
+fn test_close_wakes_blocked_producers() {
+    let ring = Arc::new(LowLatencyMpmcRing::with_policy(2, OverflowPolicy::Block { timeout: None }));
+    ring.enqueue(0u64).unwrap();
+    ring.enqueue(1).unwrap();
+
+    let producer = {
+        let ring = Arc::clone(&ring);
+        thread::spawn(move || ring.enqueue(2))
+    };
+    thread::sleep(Duration::from_millis(20));
+    ring.close();
+    assert_eq!(producer.join().unwrap(), Err(TableError::Closed));
+    assert_eq!(drain(&ring), vec![0, 1]);
+}
+
+#[test]
+fn test_every_topology_delivers_each_item_once_in_producer_order() {
+    const MESSAGES_PER_PRODUCER: u64 = 5_000;
+
+    for topology in [Topology::Mpmc, Topology::Mpsc, Topology::Spmc, Topology::Spsc] {
+        let producer_count = if topology.single_producer() { 1 } else { 2 };
+        let consumer_count = if topology.single_consumer() { 1 } else { 2 };
+        // SAFETY: the threads below match the declared topology
+        let ring = unsafe { LowLatencyMpmcRing::new(16).with_topology(topology) };
+        let consumed = AtomicUsize::new(0);
+        let total = producer_count * MESSAGES_PER_PRODUCER as usize;
+
+        let seen: Vec<Vec<u64>> = thread::scope(|scope| {
+            for p in 0..producer_count as u64 {
+                let ring = &ring;
+                scope.spawn(move || {
+                    // Alternate single and batch enqueues to cover both claim paths
+                    let (base, end) = (p * MESSAGES_PER_PRODUCER, MESSAGES_PER_PRODUCER as usize);
+                    let mut next = 0;
+                    while next < end {
+                        let stored = if next % 2 == 0 {
+                            ring.try_enqueue(base + next as u64).is_ok() as usize
+                        } else {
+                            let mut chunk = (next..end.min(next + 3)).map(|i| base + i as u64);
+                            ring.try_enqueue_batch(&mut chunk)
+                        };
+                        if stored == 0 {
+                            thread::yield_now();
+                        }
+                        next += stored;
+                    }
+                });
+            }
+            let consumers: Vec<_> = (0..consumer_count).map(|c| {
+                let (ring, consumed) = (&ring, &consumed);
+                scope.spawn(move || {
+                    let mut seen = Vec::new();
+                    while consumed.load(Ordering::Acquire) < total {
+                        let before = seen.len();
+                        if c % 2 == 0 {
+                            seen.extend(ring.try_dequeue().unwrap());
+                        } else {
+                            ring.try_dequeue_batch(&mut seen, 4);
+                        }
+                        match seen.len() - before {
+                            0 => thread::yield_now(),
+                            read => {
+                                consumed.fetch_add(read, Ordering::AcqRel);
+                            }
+                        }
+                    }
+                    seen
+                })
+            }).collect();
+            consumers.into_iter().map(|consumer| consumer.join().unwrap()).collect()
+        });
+
+        let mut all: Vec<u64> = seen.iter().flatten().copied().collect();
+        for items in &seen {
+            for producer in 0..producer_count as u64 {
+                let own: Vec<_> = items.iter().filter(|&&item| item / MESSAGES_PER_PRODUCER == producer).collect();
+                assert!(own.windows(2).all(|pair| pair[0] < pair[1]), "{:?} reordered items", topology);
+            }
+        }
+        all.sort_unstable();
+        assert_eq!(all, (0..total as u64).collect::<Vec<_>>(), "{:?}", topology);
+        assert!(ring.is_empty());
+    }
+}
+
+#[test]
+fn test_single_consumer_ring_has_no_cursors() {
+    // SAFETY: only this thread uses the ring
+    let ring = unsafe { LowLatencyMpmcRing::with_policy(4, OverflowPolicy::Block { timeout: None }).with_topology(Topology::Mpsc) };
+    assert_eq!(ring.add_cursor(), None);
+    for i in 0..4u64 {
+        ring.try_enqueue(i).unwrap();
+    }
+    assert_eq!(ring.try_enqueue(4), Err(TableError::Full));
+    assert_eq!(drain(&ring), vec![0, 1, 2, 3]);
+}
+
+#[test]
+#[should_panic(expected = "needs the Reject or Block policy")]
+fn test_single_consumer_ring_refuses_evicting_policies() {
+    let ring = LowLatencyMpmcRing::<u64>::with_policy(4, OverflowPolicy::DropOldest);
+    // SAFETY: the ring is never used
+    let _ = unsafe { ring.with_topology(Topology::Spsc) };
+}
@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use super::{temp_dir, u64_fields, u64_record};
+use crate::clock::ManualClock;
+use crate::error::TableError;
+use crate::storage::database::{Database, DatabaseConfig};
+use crate::storage::schema::{DataType, Endianness, FieldValue};
+use crate::storage::table::{FieldConfig, Table, TableConfig};
+use crate::storage::wal::WalConfig;
+
+fn orders(capacity: usize) -> TableConfig {
+    let mut fields = u64_fields("order_id", capacity);
+    fields.insert("qty", FieldConfig::new(DataType::U32, capacity));
+    fields.insert("at", FieldConfig::new(DataType::TimestampNs, capacity));
+    TableConfig::new(fields).with_clock(Arc::new(ManualClock::new(100)))
+}
+
+fn order(order_id: u64) -> HashMap<&'static str, Box<[u8]>> {
+    let mut record = u64_record("order_id", order_id);
+    record.insert("qty", 5u32.encode(Endianness::Little));
+    record
+}
+
+#[test]
+fn test_added_field_reads_back_its_default_for_older_rows() {
+    let table = Table::new("orders", orders(16));
+    let qty = table.field_id("qty").unwrap();
+    table.write_record(order(1)).unwrap();
+
+    table.add_field("venue", FieldConfig::new(DataType::Utf8, 16), Some(Box::from(*b"XNAS"))).unwrap();
+    table.add_field("fee", FieldConfig::new(DataType::F64, 16), Some(0.5f64.encode(Endianness::Little))).unwrap();
+    assert_eq!(table.schema().version(), 2);
+    assert_eq!(table.write_record(order(2)), Err(TableError::MissingField("fee".to_owned())));
+    let mut newer = order(2);
+    newer.insert("venue", Box::from(*b"XLON"));
+    newer.insert("fee", 1.25f64.encode(Endianness::Little));
+    table.write_record(newer).unwrap();
+
+    let older = table.read_one_record().unwrap().unwrap();
+    assert_eq!((older.get_as::<u64>("order_id"), older.get_str("venue")), (Ok(1), Ok("XNAS")));
+    assert_eq!((older.get_as::<f64>("fee"), older.get_as::<u64>("at")), (Ok(0.5), Ok(100)));
+    let newer = table.read_one_record().unwrap().unwrap();
+    assert_eq!((newer.get_str("venue"), newer.get_as::<f64>("fee")), (Ok("XLON"), Ok(1.25)));
+    assert_eq!(newer.len(), 5);
+
+    // Ids resolved before the change would now address other columns
+    assert!(matches!(
+        table.write_fields([(qty, 1u32.encode(Endianness::Little))]),
+        Err(TableError::SchemaMismatch(_))
+    ));
+}
+
+#[test]
+fn test_older_rows_and_records_leaving_a_field_out_have_separate_defaults() {
+    let table = Table::new("orders", orders(16));
+    table.write_record(order(1)).unwrap();
+    let fee = FieldConfig::new(DataType::F64, 16).with_default(1.0f64.encode(Endianness::Little));
+    table.add_field("fee", fee, Some(0.5f64.encode(Endianness::Little))).unwrap();
+    table.write_record(order(2)).unwrap();
+
+    let fees: Vec<_> = table.drain().map(|record| record.get_as::<f64>("fee").unwrap()).collect();
+    assert_eq!(fees, [0.5, 1.0]);
+}
+
+#[test]
+fn test_dropped_field_is_left_out_of_every_record() {
+    let table = Table::new("orders", orders(16));
+    table.write_record(order(1)).unwrap();
+    table.drop_field("qty").unwrap();
+    assert_eq!(table.write_record(order(2)), Err(TableError::UnknownField("qty".to_owned())));
+    table.write_record(u64_record("order_id", 2)).unwrap();
+
+    // Re-added under the same name it is a new field: older rows read its default
+    table.add_field("qty", FieldConfig::new(DataType::U16, 32), Some(7u16.encode(Endianness::Little))).unwrap();
+    let records: Vec<_> = table.drain().collect();
+    assert_eq!(records.len(), 2);
+    for record in &records {
+        assert_eq!(record.get_as::<u16>("qty"), Ok(7));
+        assert_eq!(record.iter().map(|(name, _)| name).collect::<Vec<_>>(), ["at", "order_id", "qty"]);
+    }
+
+    assert_eq!(table.drop_field("venue"), Err(TableError::UnknownField("venue".to_owned())));
+    assert!(matches!(table.drop_field("at"), Err(TableError::InvalidSchema(_))));
+    table.drop_field("qty").unwrap();
+    table.drop_field("order_id").unwrap();
+    assert!(matches!(table.drop_field("at"), Err(TableError::InvalidSchema(_))));
+}
+
+#[test]
+fn test_invalid_additions_leave_the_fields_unchanged() {
+    let table = Table::new("orders", orders(16));
+    let invalid = |result: Result<(), TableError>| matches!(result, Err(TableError::InvalidSchema(_)));
+    let zeros = |len: usize| Some(vec![0u8; len].into());
+    assert!(invalid(table.add_field("qty", FieldConfig::new(DataType::U32, 16), zeros(4))));
+    assert!(invalid(table.add_field("", FieldConfig::new(DataType::U32, 16), zeros(4))));
+    assert!(invalid(table.add_field("fee", FieldConfig::new(DataType::F64, 8), zeros(8))));
+    // Older rows need something to read back
+    assert!(invalid(table.add_field("fee", FieldConfig::new(DataType::F64, 16), None)));
+    assert!(matches!(
+        table.add_field("fee", FieldConfig::new(DataType::F64, 16), zeros(4)),
+        Err(TableError::SizeMismatch { .. })
+    ));
+    assert!(matches!(
+        table.add_field("venue", FieldConfig::new(DataType::Utf8, 16), Some(Box::from([0xFFu8]))),
+        Err(TableError::TypeMismatch { .. })
+    ));
+    assert_eq!(table.schema().version(), 0);
+
+    table.shutdown().unwrap();
+    assert_eq!(table.drop_field("qty"), Err(TableError::Closed));
+}
+
+#[test]
+fn test_fields_change_while_producers_keep_writing() {
+    const PER_THREAD: u64 = 2_000;
+    let table = Table::new("orders", orders(1024));
+    let written = thread::scope(|scope| {
+        let producers: Vec<_> = (0..2u64).map(|t| {
+            let table = &table;
+            scope.spawn(move || {
+                for i in 0..PER_THREAD {
+                    let mut record = order(t * PER_THREAD + i);
+                    loop {
+                        match table.write_record(record.clone()) {
+                            Ok(()) => break,
+                            Err(TableError::Full) => thread::yield_now(),
+                            // Writers learn about the new field from the error
+                            Err(TableError::MissingField(_)) => {
+                                record.insert("venue", Box::from(*b"XLON"));
+                            }
+                            Err(err) => panic!("{}", err),
+                        }
+                    }
+                }
+            })
+        }).collect();
+        let consumer = scope.spawn(|| {
+            let mut read = Vec::new();
+            while read.len() < 2 * PER_THREAD as usize {
+                if let Some(record) = table.read_one_record().unwrap() {
+                    read.push(record);
+                }
+            }
+            read
+        });
+
+        table.add_field("venue", FieldConfig::new(DataType::Utf8, 1024), Some(Box::from(*b"XNAS"))).unwrap();
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        consumer.join().unwrap()
+    });
+
+    let mut ids: Vec<u64> = written.iter().map(|record| record.get_as::<u64>("order_id").unwrap()).collect();
+    ids.sort_unstable();
+    assert_eq!(ids, (0..2 * PER_THREAD).collect::<Vec<_>>());
+    // Records read before the change have no venue at all
+    for venue in written.iter().filter_map(|record| record.get("venue")) {
+        assert!(venue == b"XNAS" || venue == b"XLON");
+    }
+    assert!(written.last().unwrap().get_str("venue").is_ok());
+}
+
+#[test]
+fn test_scan_and_snapshot_see_the_current_fields() {
+    let table = Table::new("orders", orders(16));
+    table.write_record(order(1)).unwrap();
+    table.add_field("fee", FieldConfig::new(DataType::F64, 16), Some(0.5f64.encode(Endianness::Little))).unwrap();
+    table.drop_field("qty").unwrap();
+
+    let scanned: Vec<_> = table.scan(0, u64::MAX).unwrap().collect();
+    assert_eq!(scanned[0].get_as::<f64>("fee"), Ok(0.5));
+    assert_eq!(scanned[0].get("qty"), None);
+
+    let path = temp_dir("evolved-snapshot");
+    table.snapshot(&path).unwrap();
+    let config = TableConfig::from_schema(table.schema().clone());
+    let restored = Table::restore("orders", config, &path).unwrap();
+    fs::remove_file(&path).unwrap();
+    let record = restored.read_one_record().unwrap().unwrap();
+    assert_eq!((record.get_as::<u64>("order_id"), record.get_as::<f64>("fee")), (Ok(1), Ok(0.5)));
+}
+
+#[test]
+fn test_snapshots_taken_during_field_changes_restore_whole() {
+    let table = Table::new("orders", orders(64));
+    for order_id in 0..64 {
+        table.write_record(order(order_id)).unwrap();
+    }
+    let fee = || FieldConfig::new(DataType::F64, 64).with_nullable(true);
+    let with_fee = || {
+        let mut fields = u64_fields("order_id", 64);
+        fields.insert("qty", FieldConfig::new(DataType::U32, 64));
+        fields.insert("at", FieldConfig::new(DataType::TimestampNs, 64));
+        fields.insert("fee", fee());
+        TableConfig::new(fields)
+    };
+    let path = temp_dir("evolving-snapshot");
+    let done = AtomicBool::new(false);
+
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            for _ in 0..120 {
+                table.add_field("fee", fee(), None).unwrap();
+                thread::sleep(Duration::from_micros(50));
+                table.drop_field("fee").unwrap();
+                thread::sleep(Duration::from_micros(50));
+            }
+            done.store(true, Ordering::Release);
+        });
+        loop {
+            let changing = !done.load(Ordering::Acquire);
+            table.snapshot(&path).unwrap();
+            // Each image holds rows of one version, whichever it was
+            let restored = [Table::restore("orders", orders(64), &path), Table::restore("orders", with_fee(), &path)];
+            assert!(restored.iter().all(|table| !matches!(table, Err(TableError::Corrupt(_)))));
+            let restored = restored.into_iter().find_map(Result::ok).unwrap();
+            assert_eq!(restored.record_count.load(Ordering::SeqCst), 64);
+            if !changing {
+                break;
+            }
+        }
+    });
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_database_records_field_changes_in_its_catalog() {
+    let dir = temp_dir("evolved-database");
+    let budget = orders(16).reserved_bytes() + 16 * 8;
+    {
+        let db = Database::open(DatabaseConfig::new().with_dir(&dir).with_memory_budget(budget)).unwrap();
+        let table = db.create_table("orders", orders(16)).unwrap();
+        table.write_record(order(1)).unwrap();
+        db.add_field("orders", "fee", FieldConfig::new(DataType::F64, 16), Some(0.5f64.encode(Endianness::Little))).unwrap();
+        assert_eq!(db.reserved_bytes(), budget);
+        assert!(matches!(
+            db.add_field("orders", "tax", FieldConfig::new(DataType::F64, 16), Some(Box::from([0u8; 8]))),
+            Err(TableError::OutOfMemory { .. })
+        ));
+        db.drop_field("orders", "qty").unwrap();
+
+        // Rows move to an altered table with the fields they were read with
+        let grown = db.alter_table("orders", |config| config).unwrap();
+        let record = grown.read_one_record().unwrap().unwrap();
+        assert_eq!((record.get_as::<f64>("fee"), record.get("qty")), (Ok(0.5), None));
+
+        let logged = Table::open("fills", orders(16).with_wal(WalConfig::new(dir.join("fills")))).unwrap();
+        assert!(matches!(
+            logged.add_field("fee", FieldConfig::new(DataType::F64, 16), Some(Box::from([0u8; 8]))),
+            Err(TableError::SchemaMismatch(_))
+        ));
+    }
+
+    let db = Database::open(DatabaseConfig::new().with_dir(&dir)).unwrap();
+    let names: Vec<String> = db.table("orders").unwrap().columns().iter().map(|column| column.name.to_string()).collect();
+    assert_eq!(names, ["at", "fee", "order_id"]);
+    fs::remove_dir_all(&dir).unwrap();
+}
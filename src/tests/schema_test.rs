@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::clock::ManualClock;
+use crate::error::TableError;
+use crate::storage::schema::{DataType, Endianness, FieldId, FieldValue, Schema};
+use crate::storage::table::{FieldConfig, Table, TableConfig};
+
+// Field names known only at runtime, as from a config file
+fn runtime_schema(spec: &str) -> Result<Schema, TableError> {
+    spec.split(',')
+        .map(|field| field.split_once(':').unwrap())
+        .fold(Schema::builder(), |builder, (name, data_type)| {
+            let data_type = match data_type {
+                "u32" => DataType::U32,
+                "f64" => DataType::F64,
+                "ts" => DataType::TimestampNs,
+                _ => DataType::Utf8,
+            };
+            builder.field(name.to_owned(), FieldConfig::new(data_type, 16))
+        })
+        .build()
+}
+
+#[test]
+fn test_builder_rejects_empty_and_duplicate_fields() {
+    let invalid = |result: Result<Schema, TableError>| matches!(result, Err(TableError::InvalidSchema(_)));
+    assert!(invalid(Schema::builder().build()));
+    assert!(invalid(runtime_schema("qty:u32,price:f64,qty:f64")));
+    assert!(invalid(runtime_schema(":u32")));
+    assert!(runtime_schema("qty:u32,price:f64").is_ok());
+}
+
+#[test]
+fn test_schema_resolves_fields_in_column_order() {
+    let schema = runtime_schema("venue:utf8,qty:u32,price:f64").unwrap();
+    let names: Vec<&str> = schema.columns().iter().map(|column| &*column.name).collect();
+    assert_eq!(names, ["price", "qty", "venue"]);
+    assert_eq!(schema.field_id("qty").map(FieldId::index), Some(1));
+    assert_eq!(schema.field("venue").unwrap().data_type, DataType::Utf8);
+    assert!(schema.field_id("missing").is_none());
+}
+
+#[test]
+fn test_table_from_runtime_schema_takes_owned_names() {
+    let name = String::from("orders");
+    let config = TableConfig::from_schema(runtime_schema("qty:u32,price:f64,at:ts").unwrap())
+        .with_time_column(String::from("at"))
+        .with_clock(Arc::new(ManualClock::new(7)));
+    let table = Table::new(name, config);
+    assert_eq!(&*table.name, "orders");
+    assert_eq!(table.time_column(), Some("at"));
+
+    let record: HashMap<String, Box<[u8]>> = HashMap::from([
+        ("qty".to_owned(), 3u32.encode(Endianness::Little)),
+        ("price".to_owned(), 9.5f64.encode(Endianness::Little)),
+    ]);
+    table.write_record(record).unwrap();
+    let record = table.read_one_record().unwrap().unwrap();
+    assert_eq!(record.get_as::<u32>("qty"), Ok(3));
+    assert_eq!(record.get_as::<u64>("at"), Ok(7));
+}
+
+#[test]
+fn test_write_fields_addresses_fields_by_id() {
+    let table = Table::new("orders", TableConfig::from_schema(runtime_schema("qty:u32,price:f64,at:ts").unwrap()));
+    let qty = table.field_id("qty").unwrap();
+    let price = table.field_id("price").unwrap();
+    assert_eq!(table.field_id("venue"), Err(TableError::UnknownField("venue".to_owned())));
+
+    // The time column is stamped when left out
+    table.write_fields([(price, 2.5f64.encode(Endianness::Little)), (qty, 4u32.encode(Endianness::Little))]).unwrap();
+    let record = table.read_one_record().unwrap().unwrap();
+    assert_eq!((record.get_as::<u32>("qty"), record.get_as::<f64>("price")), (Ok(4), Ok(2.5)));
+    assert!(record.get_as::<u64>("at").unwrap() > 0);
+
+    assert_eq!(
+        table.write_fields([(qty, 4u32.encode(Endianness::Little))]),
+        Err(TableError::MissingField("price".to_owned()))
+    );
+    assert!(matches!(
+        table.write_fields([(FieldId { index: 9, version: 0 }, Box::from([0u8; 4]))]),
+        Err(TableError::UnknownField(_))
+    ));
+    assert!(matches!(
+        table.write_fields([(qty, Box::from([0u8; 2])), (price, 1f64.encode(Endianness::Little))]),
+        Err(TableError::SizeMismatch { .. })
+    ));
+    assert_eq!(table.stats().rows_written, 1);
+}
+
+#[test]
+#[should_panic(expected = "at least one field")]
+fn test_config_without_fields_panics() {
+    TableConfig::new(HashMap::<&str, FieldConfig>::new());
+}
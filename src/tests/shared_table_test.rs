@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use std::env;
+use std::process::{self, Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::TableError;
+use crate::memory::low_latency_mpmc_ring::OverflowPolicy;
+use crate::memory::slot_ring::SlotRing;
+use crate::storage::schema::{DataType, Endianness, FieldValue};
+use crate::storage::shared_table::SharedTable;
+use crate::storage::table::{Table, TableConfig, FieldConfig};
+use crate::storage::wal::WalConfig;
+
+// Set in a child process to the name of the shared table it should attach to
+const CHILD_ENV: &str = "ORTS_SHARED_TABLE_CHILD";
+const ROWS: u64 = 1_000;
+// Bounds the waits on the other process, so a crashed peer fails the test
+const PEER_TIMEOUT: Duration = Duration::from_secs(30);
+
+fn fields() -> HashMap<&'static str, FieldConfig> {
+    let mut fields = HashMap::new();
+    fields.insert("seq", FieldConfig::new(DataType::U64, 64));
+    fields.insert("price", FieldConfig::new(DataType::F64, 64));
+    fields.insert("ts", FieldConfig::new(DataType::TimestampNs, 64));
+    fields
+}
+
+fn config() -> TableConfig {
+    TableConfig::new(fields())
+}
+
+fn unique_name(test: &str) -> String {
+    format!("{}-{}", test, process::id())
+}
+
+// Run `test` again in a child process that attaches to `name`
+fn spawn_child(test: &str, name: &str) -> Child {
+    Command::new(env::current_exe().unwrap())
+        .args([&format!("tests::shared_table_test::{}", test), "--exact", "--test-threads=1"])
+        .env(CHILD_ENV, name)
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap()
+}
+
+fn write_with_retry(table: &SharedTable, seq: u64) {
+    let record = HashMap::from([
+        ("seq", seq.encode(Endianness::Little)),
+        ("price", (seq as f64 / 4.0).encode(Endianness::Little)),
+    ]);
+    let deadline = Instant::now() + PEER_TIMEOUT;
+    loop {
+        match table.write_record(record.clone()) {
+            Err(TableError::Full) if Instant::now() < deadline => thread::yield_now(),
+            result => return result.unwrap(),
+        }
+    }
+}
+
+// Read until the table is shut down and empty, checking every row
+fn read_all(table: &SharedTable) -> u64 {
+    let mut next = 0;
+    let deadline = Instant::now() + PEER_TIMEOUT;
+    loop {
+        match table.read_one_record().unwrap() {
+            Some(record) => {
+                assert_eq!(record.get_as::<u64>("seq"), Ok(next));
+                assert_eq!(record.get_as::<f64>("price"), Ok(next as f64 / 4.0));
+                assert!(record.get_as::<u64>("ts").unwrap() > 0);
+                next += 1;
+            }
+            None if table.is_closed() && table.is_empty() => return next,
+            None => {
+                assert!(Instant::now() < deadline, "timed out after {} rows", next);
+                thread::yield_now();
+            }
+        }
+    }
+}
+
+#[test]
+fn test_attached_ring_shares_slots_and_checks_its_layout() {
+    let name = &unique_name("ring");
+    let ring = SlotRing::create_shared(name, 4, 8, 7).unwrap();
+    assert!(matches!(SlotRing::create_shared(name, 4, 8, 7), Err(TableError::Io(_))));
+    assert!(matches!(SlotRing::attach_shared(name, 8), Err(TableError::SchemaMismatch(_))));
+
+    // A second mapping of the same region sees the first one's writes
+    let attached = SlotRing::attach_shared(name, 7).unwrap();
+    assert_eq!((attached.capacity(), attached.slot_size(), attached.layout()), (4, 8, 7));
+    ring.claim().unwrap().copy_from_slice(&42u64.to_le_bytes());
+    assert_eq!(*attached.read().unwrap().unwrap(), 42u64.to_le_bytes());
+    assert!(ring.is_empty());
+
+    attached.close();
+    assert!(matches!(ring.claim(), Err(TableError::Closed)));
+
+    // The creator releases the name; existing mappings stay usable
+    drop(ring);
+    assert!(matches!(SlotRing::attach_shared(name, 7), Err(TableError::Io(_))));
+    assert!(attached.read().unwrap().is_none());
+}
+
+#[test]
+fn test_shared_tables_need_fixed_width_fields_and_a_matching_schema() {
+    let name = unique_name("schema");
+    let name = name.as_str();
+    let mut with_text = fields();
+    with_text.insert("venue", FieldConfig::new(DataType::Utf8, 64));
+    let with_text = TableConfig::new(with_text);
+    assert!(matches!(Table::create_shared(name, with_text), Err(TableError::SchemaMismatch(_))));
+    let mut with_nulls = fields();
+    with_nulls.insert("qty", FieldConfig::new(DataType::U32, 64).with_nullable(true));
+    let with_nulls = TableConfig::new(with_nulls);
+    assert!(matches!(Table::create_shared(name, with_nulls), Err(TableError::SchemaMismatch(_))));
+    let evicting = config().with_overflow_policy(OverflowPolicy::DropOldest);
+    assert!(matches!(Table::create_shared(name, evicting), Err(TableError::SchemaMismatch(_))));
+    let logged = config().with_wal(WalConfig::new(std::env::temp_dir()));
+    assert!(matches!(Table::create_shared(name, logged), Err(TableError::SchemaMismatch(_))));
+    assert!(matches!(Table::create_shared("a/b", config()), Err(TableError::InvalidName(_))));
+    let mut too_wide = fields();
+    too_wide.insert("qty", FieldConfig::new(DataType::U32, 64).with_max_len(8));
+    let too_wide = TableConfig::new(too_wide);
+    assert!(matches!(Table::create_shared(name, too_wide), Err(TableError::InvalidSchema(_))));
+    let mut bad_default = fields();
+    bad_default.insert("qty", FieldConfig::new(DataType::U32, 64).with_default(Box::from([1u8])));
+    let bad_default = TableConfig::new(bad_default);
+    assert!(matches!(Table::create_shared(name, bad_default), Err(TableError::InvalidSchema(_))));
+    let untimed = config().with_time_column("price");
+    assert!(matches!(Table::create_shared(name, untimed), Err(TableError::InvalidSchema(_))));
+    let mut uneven = fields();
+    uneven.insert("qty", FieldConfig::new(DataType::U32, 48));
+    assert!(matches!(Table::create_shared(name, TableConfig::new(uneven)), Err(TableError::InvalidSchema(_))));
+    let twice = config().with_time_column("ts").with_ingest_time_column("ts");
+    assert!(matches!(Table::attach_shared(name, twice), Err(TableError::InvalidSchema(_))));
+
+    let _table = Table::create_shared(name, config()).unwrap();
+    let mut other = fields();
+    other.insert("qty", FieldConfig::new(DataType::U32, 64));
+    let other = TableConfig::new(other);
+    assert!(matches!(Table::attach_shared(name, other), Err(TableError::SchemaMismatch(_))));
+    assert!(Table::attach_shared(name, config()).is_ok());
+}
+
+#[test]
+fn test_child_process_consumes_what_the_parent_writes() {
+    if let Ok(name) = env::var(CHILD_ENV) {
+        let table = Table::attach_shared(name, config()).unwrap();
+        assert_eq!(read_all(&table), ROWS);
+        return;
+    }
+
+    let name = unique_name("parent-writes");
+    let table = Table::create_shared(name.as_str(), config()).unwrap();
+    let mut child = spawn_child("test_child_process_consumes_what_the_parent_writes", &name);
+    for seq in 0..ROWS {
+        write_with_retry(&table, seq);
+    }
+    table.shutdown();
+    assert!(child.wait().unwrap().success());
+    assert!(table.is_empty());
+}
+
+#[test]
+fn test_parent_consumes_what_a_child_process_writes() {
+    if let Ok(name) = env::var(CHILD_ENV) {
+        let table = Table::attach_shared(name, config()).unwrap();
+        for seq in 0..ROWS {
+            write_with_retry(&table, seq);
+        }
+        table.shutdown();
+        return;
+    }
+
+    let name = unique_name("child-writes");
+    let table = Table::create_shared(name.as_str(), config()).unwrap();
+    let mut child = spawn_child("test_parent_consumes_what_a_child_process_writes", &name);
+    assert_eq!(read_all(&table), ROWS);
+    assert!(child.wait().unwrap().success());
+}
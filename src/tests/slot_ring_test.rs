@@ -0,0 +1,92 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::thread;
+
+use crate::error::TableError;
+use crate::memory::slot_ring::SlotRing;
+
+#[test]
+fn test_claimed_bytes_are_published_on_drop() {
+    let ring = SlotRing::new(4, 8);
+    let mut slot = ring.claim().unwrap();
+    assert_eq!(slot.len(), 8);
+    slot.copy_from_slice(&42u64.to_le_bytes());
+
+    // Nothing is visible until the producer lets go of the slot
+    assert!(ring.read().unwrap().is_none());
+    drop(slot);
+
+    let read = ring.read().unwrap().unwrap();
+    assert_eq!(read.seq(), 0);
+    assert_eq!(u64::from_le_bytes((*read).try_into().unwrap()), 42);
+}
+
+#[test]
+fn test_slot_is_reusable_only_after_the_reader_drops_it() {
+    let ring = SlotRing::new(2, 4);
+    for i in 0..2u32 {
+        ring.claim().unwrap().copy_from_slice(&i.to_le_bytes());
+    }
+    assert!(matches!(ring.claim(), Err(TableError::Full)));
+
+    let read = ring.read().unwrap().unwrap();
+    assert!(matches!(ring.claim(), Err(TableError::Full)));
+    drop(read);
+
+    let mut slot = ring.claim().unwrap();
+    assert_eq!(slot.seq(), 2);
+    // Storage is reused in place, so the claimed bytes still hold the old value
+    assert_eq!(*slot, 0u32.to_le_bytes());
+    slot.copy_from_slice(&2u32.to_le_bytes());
+    drop(slot);
+
+    let rest: Vec<u32> = std::iter::from_fn(|| ring.read().unwrap())
+        .map(|read| u32::from_le_bytes((*read).try_into().unwrap()))
+        .collect();
+    assert_eq!(rest, vec![1, 2]);
+    assert!(ring.is_empty());
+}
+
+#[test]
+fn test_concurrent_claims_and_reads_deliver_every_value_once() {
+    const PRODUCERS: u64 = 4;
+    const PER_PRODUCER: u64 = 10_000;
+    let ring = SlotRing::new(64, 16);
+    let received = AtomicUsize::new(0);
+    let sum = AtomicU64::new(0);
+
+    thread::scope(|scope| {
+        for producer in 0..PRODUCERS {
+            let ring = &ring;
+            scope.spawn(move || {
+                for i in 0..PER_PRODUCER {
+                    let value = producer * PER_PRODUCER + i;
+                    let mut slot = loop {
+                        match ring.claim() {
+                            Ok(slot) => break slot,
+                            Err(_) => thread::yield_now(),
+                        }
+                    };
+                    slot[..8].copy_from_slice(&value.to_le_bytes());
+                    slot[8..].copy_from_slice(&(!value).to_le_bytes());
+                }
+            });
+        }
+        for _ in 0..2 {
+            scope.spawn(|| {
+                while received.load(Ordering::Relaxed) < (PRODUCERS * PER_PRODUCER) as usize {
+                    let Some(read) = ring.read().unwrap() else {
+                        thread::yield_now();
+                        continue;
+                    };
+                    let value = u64::from_le_bytes(read[..8].try_into().unwrap());
+                    assert_eq!(u64::from_le_bytes(read[8..].try_into().unwrap()), !value);
+                    sum.fetch_add(value, Ordering::Relaxed);
+                    received.fetch_add(1, Ordering::Relaxed);
+                }
+            });
+        }
+    });
+
+    let total = PRODUCERS * PER_PRODUCER;
+    assert_eq!(sum.load(Ordering::Relaxed), total * (total - 1) / 2);
+}
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+use super::{drain_ids, temp_dir, u64_fields, u64_record};
+use crate::error::TableError;
+use crate::memory::low_latency_mpmc_ring::OverflowPolicy;
+use crate::storage::schema::DataType;
+use crate::storage::table::{Table, TableConfig, FieldConfig};
+
+fn fill_config(policy: OverflowPolicy) -> TableConfig {
+    let mut fields = u64_fields("fill_id", 64);
+    fields.insert("side", FieldConfig::new(DataType::Bytes(1), 64));
+    fields.insert("account", FieldConfig::new(DataType::Utf8, 64).with_max_len(16));
+    TableConfig::new(fields).with_overflow_policy(policy)
+}
+
+fn fill(fill_id: u64) -> HashMap<&'static str, Box<[u8]>> {
+    let mut record = u64_record("fill_id", fill_id);
+    record.insert("side", Box::from(if fill_id.is_multiple_of(2) { *b"B" } else { *b"S" }));
+    record.insert("account", Box::from(format!("acct-{}", fill_id % 5).as_bytes()));
+    record
+}
+
+fn fill_id(table: &Table) -> Option<u64> {
+    table.read_one_record().unwrap().map(|record| record.get_as::<u64>("fill_id").unwrap())
+}
+
+#[test]
+fn test_restore_brings_back_rows_and_group_cursors() {
+    let path = temp_dir("snapshot-round-trip");
+    let table = Table::new("fills", fill_config(OverflowPolicy::Reject));
+    let vwap = table.subscribe("vwap").unwrap();
+    // Holds every row in the table until it reads them
+    let audit = table.subscribe("audit").unwrap();
+    for id in 0..10 {
+        table.write_record(fill(id)).unwrap();
+    }
+    for _ in 0..4 {
+        vwap.read_one_record().unwrap().unwrap();
+    }
+    table.snapshot(&path).unwrap();
+    drop((vwap, audit));
+
+    let restored = Table::restore("fills", fill_config(OverflowPolicy::Reject), &path).unwrap();
+    assert_eq!(restored.record_count.load(Ordering::SeqCst), 10);
+    let vwap = restored.subscribe("vwap").unwrap();
+    assert_eq!(drain_ids(|| vwap.read_one_record(), "fill_id"), vec![4, 5, 6, 7, 8, 9]);
+    let audit = restored.subscribe("audit").unwrap();
+    assert_eq!(audit.lag(), 10);
+
+    let record = restored.read_one_record().unwrap().unwrap();
+    assert_eq!(record.get_as::<u64>("fill_id"), Ok(0));
+    assert_eq!(record.get_bytes("side").unwrap(), b"B");
+    assert_eq!(record.get_str("account").unwrap(), "acct-0");
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_restored_ring_keeps_its_sequence_numbers() {
+    let path = temp_dir("snapshot-sequence");
+    let table = Table::new("fills", fill_config(OverflowPolicy::Overwrite));
+    for id in 0..100 {
+        table.write_record(fill(id)).unwrap();
+    }
+    table.snapshot(&path).unwrap();
+
+    let restored = Table::restore("fills", fill_config(OverflowPolicy::Overwrite), &path).unwrap();
+    restored.write_record(fill(100)).unwrap();
+    assert_eq!(restored.evicted_count(), 1);
+    assert_eq!(fill_id(&restored), Some(37));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_damaged_snapshot_is_rejected() {
+    let path = temp_dir("snapshot-damaged");
+    let table = Table::new("fills", fill_config(OverflowPolicy::Reject));
+    for id in 0..5 {
+        table.write_record(fill(id)).unwrap();
+    }
+    table.snapshot(&path).unwrap();
+
+    let mut bytes = fs::read(&path).unwrap();
+    let middle = bytes.len() / 2;
+    bytes[middle] ^= 0x01;
+    fs::write(&path, bytes).unwrap();
+    assert!(matches!(
+        Table::restore("fills", fill_config(OverflowPolicy::Reject), &path),
+        Err(TableError::Corrupt(_))
+    ));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_snapshot_of_another_schema_is_rejected() {
+    let path = temp_dir("snapshot-schema");
+    Table::new("fills", fill_config(OverflowPolicy::Reject)).snapshot(&path).unwrap();
+
+    assert!(matches!(
+        Table::restore("fills", TableConfig::new(u64_fields("fill_id", 64)), &path),
+        Err(TableError::SchemaMismatch(_))
+    ));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_snapshots_under_load_are_contiguous() {
+    let path = temp_dir("snapshot-load");
+    let table = Table::new("fills", fill_config(OverflowPolicy::DropOldest));
+    let done = AtomicBool::new(false);
+
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            for id in 0..20_000 {
+                table.write_record(fill(id)).unwrap();
+            }
+            done.store(true, Ordering::Release);
+        });
+        scope.spawn(|| {
+            while !done.load(Ordering::Acquire) {
+                let _ = table.read_one_record();
+            }
+        });
+
+        loop {
+            table.snapshot(&path).unwrap();
+            let restored = Table::restore("fills", fill_config(OverflowPolicy::DropOldest), &path).unwrap();
+            let ids = drain_ids(|| restored.read_one_record(), "fill_id");
+            assert!(ids.windows(2).all(|pair| pair[1] == pair[0] + 1), "gap in snapshot: {:?}", ids);
+            if done.load(Ordering::Acquire) {
+                break;
+            }
+        }
+    });
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_concurrent_snapshots_to_one_path_leave_a_whole_file() {
+    let path = temp_dir("snapshot-concurrent");
+    let table = Table::new("fills", fill_config(OverflowPolicy::Reject));
+    for id in 0..32 {
+        table.write_record(fill(id)).unwrap();
+    }
+
+    thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                for _ in 0..25 {
+                    table.snapshot(&path).unwrap();
+                }
+            });
+        }
+    });
+
+    let restored = Table::restore("fills", fill_config(OverflowPolicy::Reject), &path).unwrap();
+    assert_eq!(drain_ids(|| restored.read_one_record(), "fill_id"), (0..32).collect::<Vec<_>>());
+    let name = path.file_name().unwrap().to_str().unwrap().to_owned();
+    let leftovers = fs::read_dir(path.parent().unwrap()).unwrap()
+        .filter_map(|entry| entry.unwrap().file_name().into_string().ok())
+        .filter(|file| file.starts_with(&name) && file != &name)
+        .count();
+    assert_eq!(leftovers, 0);
+    fs::remove_file(&path).unwrap();
+}
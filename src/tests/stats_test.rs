@@ -0,0 +1,103 @@
+use std::thread;
+
+use super::{u64_fields, u64_record};
+use crate::error::TableError;
+use crate::memory::low_latency_mpmc_ring::{LowLatencyMpmcRing, OverflowPolicy};
+use crate::storage::table::{Table, TableConfig};
+
+fn table(capacity: usize, policy: OverflowPolicy) -> Table {
+    Table::new("orders", TableConfig::new(u64_fields("order_id", capacity)).with_overflow_policy(policy))
+}
+
+#[test]
+fn test_ring_counts_traffic_rejections_and_peak_occupancy() {
+    let ring = LowLatencyMpmcRing::new(4);
+    for i in 0..3u32 {
+        ring.try_enqueue(i).unwrap();
+    }
+    ring.try_dequeue().unwrap();
+    assert_eq!(ring.try_enqueue_batch(&mut (3..6u32)), 2);
+    assert_eq!(ring.try_enqueue(6), Err(TableError::Full));
+    assert_eq!(ring.try_enqueue_batch(&mut (6..8u32)), 0);
+    let mut out = Vec::new();
+    ring.try_dequeue_batch(&mut out, 8);
+
+    let stats = ring.stats();
+    assert_eq!((stats.enqueued, stats.dequeued, stats.evicted), (5, 5, 0));
+    assert_eq!(stats.rejected_full, 2);
+    assert_eq!(stats.high_water_mark, 4);
+    // A single thread never loses a claim
+    assert_eq!(stats.cas_retries, 0);
+}
+
+#[test]
+fn test_evictions_are_not_counted_as_dequeues() {
+    for policy in [OverflowPolicy::DropOldest, OverflowPolicy::Overwrite] {
+        let ring = LowLatencyMpmcRing::with_policy(4, policy);
+        for i in 0..10u64 {
+            ring.enqueue(i).unwrap();
+        }
+        ring.try_dequeue().unwrap();
+
+        let stats = ring.stats();
+        assert_eq!((stats.enqueued, stats.dequeued, stats.evicted), (10, 1, 6), "{:?}", policy);
+        assert_eq!(stats.high_water_mark, 4, "{:?}", policy);
+    }
+}
+
+#[test]
+fn test_counts_from_many_threads_add_up() {
+    const PER_THREAD: u64 = 2_000;
+    let ring = LowLatencyMpmcRing::new(8);
+    thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                for i in 0..PER_THREAD {
+                    while ring.try_enqueue(i).is_err() {
+                        thread::yield_now();
+                    }
+                    while ring.try_dequeue().unwrap().is_none() {
+                        thread::yield_now();
+                    }
+                }
+            });
+        }
+    });
+
+    let stats = ring.stats();
+    assert_eq!((stats.enqueued, stats.dequeued), (4 * PER_THREAD, 4 * PER_THREAD));
+    assert!(stats.high_water_mark <= 8);
+}
+
+#[test]
+fn test_table_stats_cover_writes_reads_and_partial_batches() {
+    let table = table(4, OverflowPolicy::Reject);
+    let group = table.subscribe("audit").unwrap();
+    table.write_record(u64_record("order_id", 1)).unwrap();
+    let mut batch: Vec<_> = (2..6).map(|order_id| u64_record("order_id", order_id)).collect();
+    assert_eq!(table.write_batch(&mut batch), Err(TableError::PartialWrite { written: 3 }));
+
+    assert!(group.read_one_record().unwrap().is_some());
+    assert!(table.read_one_record().unwrap().is_some());
+    let mut out = Vec::new();
+    table.read_batch(&mut out, 2).unwrap();
+
+    let stats = table.stats();
+    assert_eq!((stats.rows_written, stats.rows_read, stats.partial_writes), (4, 4, 1));
+    assert_eq!((stats.record_count, stats.evicted), (0, 0));
+    // The group's read released the first row, since no other group needed it
+    assert_eq!((stats.ring.enqueued, stats.ring.dequeued), (4, 4));
+    assert_eq!(stats.ring.rejected_full, 1);
+    assert_eq!(stats.ring.high_water_mark, 4);
+}
+
+#[test]
+fn test_table_stats_count_evicted_rows() {
+    let table = table(4, OverflowPolicy::DropOldest);
+    for order_id in 0..6 {
+        table.write_record(u64_record("order_id", order_id)).unwrap();
+    }
+    let stats = table.stats();
+    assert_eq!((stats.rows_written, stats.evicted, stats.record_count), (6, 2, 4));
+    assert_eq!(stats.ring.evicted, 2);
+}
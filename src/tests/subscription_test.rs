@@ -0,0 +1,143 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+use super::{drain_ids, u64_fields, u64_record};
+use crate::error::TableError;
+use crate::memory::low_latency_mpmc_ring::{OverflowPolicy, MAX_CURSORS};
+use crate::storage::table::{Table, TableConfig};
+
+fn seq_table(capacity: usize, policy: OverflowPolicy) -> Table {
+    Table::new("seqs", TableConfig::new(u64_fields("seq", capacity)).with_overflow_policy(policy))
+}
+
+#[test]
+fn test_every_group_sees_every_row() {
+    let table = seq_table(16, OverflowPolicy::Reject);
+    let vwap = table.subscribe("vwap").unwrap();
+    let latency = table.subscribe("latency").unwrap();
+    for seq in 0..10 {
+        table.write_record(u64_record("seq", seq)).unwrap();
+    }
+
+    assert_eq!(vwap.lag(), 10);
+    assert_eq!(drain_ids(|| vwap.read_one_record(), "seq"), (0..10).collect::<Vec<_>>());
+    // Still held for the other group
+    assert_eq!(table.record_count.load(Ordering::SeqCst), 10);
+    assert_eq!(drain_ids(|| latency.read_one_record(), "seq"), (0..10).collect::<Vec<_>>());
+    assert_eq!(table.record_count.load(Ordering::SeqCst), 0);
+    assert_eq!(vwap.skipped(), 0);
+}
+
+#[test]
+fn test_members_of_a_group_share_the_rows() {
+    const ROWS: u64 = 10_000;
+    let table = seq_table(64, OverflowPolicy::Block { timeout: None });
+    let done = AtomicBool::new(false);
+    let audit = table.subscribe("audit").unwrap();
+    // Created up front so the group sees every row whenever members join
+    let _workers = table.subscribe("workers").unwrap();
+
+    let seen: Vec<Vec<u64>> = thread::scope(|scope| {
+        let members: Vec<_> = (0..3).map(|_| {
+            scope.spawn(|| {
+                let member = table.subscribe("workers").unwrap();
+                let mut seqs = Vec::new();
+                loop {
+                    match member.read_one_record().unwrap() {
+                        Some(record) => seqs.push(record.get_as::<u64>("seq").unwrap()),
+                        None if done.load(Ordering::Acquire) && member.lag() == 0 => break,
+                        None => thread::yield_now(),
+                    }
+                }
+                seqs
+            })
+        }).collect();
+        // Keep the audit group moving too, or it would gate the producer
+        let auditor = scope.spawn(|| {
+            let mut count = 0;
+            while count < ROWS {
+                if audit.read_one_record().unwrap().is_some() {
+                    count += 1;
+                } else {
+                    thread::yield_now();
+                }
+            }
+        });
+
+        for seq in 0..ROWS {
+            table.write_record(u64_record("seq", seq)).unwrap();
+        }
+        done.store(true, Ordering::Release);
+        auditor.join().unwrap();
+        members.into_iter().map(|member| member.join().unwrap()).collect()
+    });
+
+    let mut all: Vec<u64> = seen.into_iter().flatten().collect();
+    let unique: HashSet<u64> = all.iter().copied().collect();
+    assert_eq!(unique.len(), all.len(), "a row was delivered twice within a group");
+    all.sort_unstable();
+    assert_eq!(all, (0..ROWS).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_slowest_group_gates_rejecting_producers() {
+    let table = seq_table(4, OverflowPolicy::Reject);
+    let fast = table.subscribe("fast").unwrap();
+    let slow = table.subscribe("slow").unwrap();
+    for seq in 0..4 {
+        table.write_record(u64_record("seq", seq)).unwrap();
+    }
+    assert_eq!(drain_ids(|| fast.read_one_record(), "seq").len(), 4);
+    assert_eq!(table.write_record(u64_record("seq", 4)), Err(TableError::Full));
+
+    assert!(slow.read_one_record().unwrap().is_some());
+    table.write_record(u64_record("seq", 4)).unwrap();
+    assert_eq!(drain_ids(|| slow.read_one_record(), "seq"), vec![1, 2, 3, 4]);
+    assert_eq!(drain_ids(|| fast.read_one_record(), "seq"), vec![4]);
+}
+
+#[test]
+fn test_overwrite_runs_ahead_of_a_lagging_group() {
+    let table = seq_table(4, OverflowPolicy::Overwrite);
+    let lagging = table.subscribe("lagging").unwrap();
+    for seq in 0..10 {
+        table.write_record(u64_record("seq", seq)).unwrap();
+    }
+
+    assert_eq!(drain_ids(|| lagging.read_one_record(), "seq"), vec![6, 7, 8, 9]);
+    assert_eq!(lagging.skipped(), 6);
+    assert_eq!(table.record_count.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn test_dropping_the_last_member_releases_held_rows() {
+    let table = seq_table(8, OverflowPolicy::Reject);
+    let reader = table.subscribe("reader").unwrap();
+    let first = table.subscribe("idle").unwrap();
+    let second = table.subscribe("idle").unwrap();
+    for seq in 0..8 {
+        table.write_record(u64_record("seq", seq)).unwrap();
+    }
+    assert_eq!(drain_ids(|| reader.read_one_record(), "seq").len(), 8);
+
+    drop(first);
+    assert_eq!(table.record_count.load(Ordering::SeqCst), 8);
+    drop(second);
+    assert_eq!(table.record_count.load(Ordering::SeqCst), 0);
+    table.write_record(u64_record("seq", 8)).unwrap();
+    assert_eq!(drain_ids(|| reader.read_one_record(), "seq"), vec![8]);
+}
+
+#[test]
+fn test_group_slots_are_limited() {
+    let table = seq_table(8, OverflowPolicy::Reject);
+    let groups: Vec<_> = (0..MAX_CURSORS)
+        .map(|i| table.subscribe(&format!("group-{}", i)).unwrap())
+        .collect();
+    assert!(matches!(table.subscribe("one-too-many"), Err(TableError::TooManyGroups)));
+    // Joining an existing group needs no new slot
+    assert!(table.subscribe("group-0").is_ok());
+    drop(groups);
+    assert!(table.subscribe("one-too-many").is_ok());
+}
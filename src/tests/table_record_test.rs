@@ -0,0 +1,235 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::thread;
+
+use crate::error::TableError;
+use crate::storage::schema::{DataType, Endianness};
+use crate::storage::table::Table;
+use crate::storage::table_record::TableRecord;
+
+#[derive(TableRecord, Clone, Debug, PartialEq)]
+struct Trade {
+    symbol_id: u32,
+    price: f64,
+    quantity: u32,
+    #[table(timestamp)]
+    timestamp: u64,
+    #[table(rename = "exchange_id")]
+    exchange: u8,
+    #[table(big_endian)]
+    venue_seq_no: u64,
+    venue: String,
+    flags: [u8; 2],
+}
+
+#[derive(TableRecord, Debug, PartialEq)]
+struct Quote {
+    symbol_id: u32,
+    bid: f64,
+    ask: f64,
+}
+
+#[derive(TableRecord, Clone, Debug, PartialEq)]
+struct Fill {
+    order_id: u64,
+    price: Option<f64>,
+    venue: Option<String>,
+}
+
+#[derive(TableRecord, Debug, PartialEq)]
+struct PricedFill {
+    order_id: u64,
+    price: f64,
+    venue: String,
+}
+
+// Counts the allocations each thread makes, so tests can check a path makes none
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.set(ALLOCATIONS.get() + 1);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.set(ALLOCATIONS.get() + 1);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn trade(p_id: u32, i: u32) -> Trade {
+    Trade {
+        symbol_id: p_id,
+        price: 1000.0 + i as f64 * 0.01,
+        quantity: i,
+        timestamp: 1_700_000_000_000_000_000 + i as u64,
+        exchange: p_id as u8,
+        venue_seq_no: i as u64,
+        venue: format!("XNAS-{}", p_id),
+        flags: [p_id as u8, i as u8],
+    }
+}
+
+#[test]
+fn test_derived_schema() {
+    let config = Trade::table_config(64);
+    assert_eq!(config.schema.len(), 8);
+    assert_eq!(config.schema.field("timestamp").unwrap().data_type, DataType::TimestampNs);
+    assert_eq!(config.schema.field("exchange_id").unwrap().data_type, DataType::U8);
+    assert_eq!(config.schema.field("venue_seq_no").unwrap().endianness, Endianness::Big);
+    assert_eq!(config.schema.field("venue").unwrap().data_type, DataType::Utf8);
+    assert_eq!(config.schema.field("flags").unwrap().data_type, DataType::Bytes(2));
+    assert!(config.schema.columns().iter().all(|column| column.config.ring_capacity == 64));
+}
+
+#[test]
+fn test_typed_write_and_read_round_trip() {
+    let table = Table::new("trades", Trade::table_config(64));
+
+    let written = trade(7, 42);
+    table.write(&written).unwrap();
+
+    let record = table.read_one_record().unwrap().unwrap();
+    assert_eq!(record.get_as::<u8>("exchange_id"), Ok(7));
+    assert_eq!(record.get("venue_seq_no"), Some(&42u64.to_be_bytes()[..]));
+    assert_eq!(Trade::decode_record(&record), Ok(written.clone()));
+
+    table.write(&written).unwrap();
+    assert_eq!(table.read::<Trade>(), Ok(Some(written)));
+    assert_eq!(table.read::<Trade>(), Ok(None));
+}
+
+#[test]
+fn test_option_fields_round_trip_nulls() {
+    let config = Fill::table_config(16);
+    assert!(!config.schema.field("order_id").unwrap().nullable);
+    assert!(config.schema.field("price").unwrap().nullable);
+    assert!(config.schema.field("venue").unwrap().nullable);
+
+    let table = Table::new("fills", config);
+    let fills = [
+        Fill { order_id: 1, price: None, venue: Some("XNAS".to_owned()) },
+        Fill { order_id: 2, price: Some(9.5), venue: None },
+        Fill { order_id: 3, price: None, venue: None },
+    ];
+    for fill in &fills {
+        table.write(fill).unwrap();
+    }
+    let record = table.read_one_record().unwrap().unwrap();
+    assert_eq!((record.is_null("price"), record.is_null("venue")), (Ok(true), Ok(false)));
+    assert_eq!(Fill::decode_record(&record), Ok(fills[0].clone()));
+    assert_eq!(table.read::<Fill>(), Ok(Some(fills[1].clone())));
+    assert_eq!(table.read::<Fill>(), Ok(Some(fills[2].clone())));
+
+    // Fields that are not Options can neither read nor write a null
+    table.write(&fills[1]).unwrap();
+    assert_eq!(table.read::<PricedFill>(), Err(TableError::Null("venue".to_owned())));
+    let table = Table::new("fills", PricedFill::table_config(16));
+    assert_eq!(table.write(&fills[1]), Err(TableError::MissingField("venue".to_owned())));
+    assert_eq!(table.read::<Fill>(), Ok(None));
+}
+
+#[test]
+fn test_typed_write_rejects_foreign_schema() {
+    let table = Table::new("quotes", Quote::table_config(64));
+    assert_eq!(table.write(&trade(1, 1)), Err(TableError::UnknownField("exchange_id".into())));
+    assert_eq!(table.record_count.load(Ordering::SeqCst), 0);
+
+    table.write(&Quote { symbol_id: 1, bid: 99.5, ask: 100.5 }).unwrap();
+    assert_eq!(table.read::<Quote>(), Ok(Some(Quote { symbol_id: 1, bid: 99.5, ask: 100.5 })));
+
+    // The table is drained, so a typed read of a foreign schema reports nothing
+    assert_eq!(table.read::<Trade>(), Ok(None));
+}
+
+#[test]
+fn test_typed_records_across_threads() {
+    const PRODUCER_COUNT: u32 = 3;
+    const MESSAGES_PER_PRODUCER: u32 = 5_000;
+
+    let table = Arc::new(Table::new("trades", Trade::table_config(256)));
+    let producers: Vec<_> = (0..PRODUCER_COUNT).map(|p_id| {
+        let table = Arc::clone(&table);
+        thread::spawn(move || {
+            for i in 0..MESSAGES_PER_PRODUCER {
+                while table.write(&trade(p_id, i)).is_err() {
+                    thread::yield_now();
+                }
+            }
+        })
+    }).collect();
+
+    let mut next = [0; PRODUCER_COUNT as usize];
+    let mut received = 0;
+    while received < PRODUCER_COUNT * MESSAGES_PER_PRODUCER {
+        match table.read::<Trade>().unwrap() {
+            Some(read) => {
+                let p_id = read.symbol_id;
+                assert_eq!(read, trade(p_id, next[p_id as usize]));
+                next[p_id as usize] += 1;
+                received += 1;
+            }
+            None => thread::yield_now(),
+        }
+    }
+
+    for p in producers {
+        p.join().unwrap();
+    }
+}
+
+#[test]
+fn test_fixed_width_typed_writes_and_reads_do_not_allocate() {
+    let table = Table::new("quotes", Quote::table_config(64));
+    let quote = |symbol_id| Quote { symbol_id, bid: 99.5, ask: 100.5 };
+    // The first calls may set up per-thread state
+    table.write(&quote(0)).unwrap();
+    assert_eq!(table.read::<Quote>(), Ok(Some(quote(0))));
+
+    let before = ALLOCATIONS.get();
+    for symbol_id in 0..1_000 {
+        table.write(&quote(symbol_id)).unwrap();
+        assert_eq!(table.read::<Quote>(), Ok(Some(quote(symbol_id))));
+    }
+    assert_eq!(ALLOCATIONS.get() - before, 0);
+
+    let subscription = table.subscribe("audit").unwrap();
+    let before = ALLOCATIONS.get();
+    for symbol_id in 0..1_000 {
+        table.write(&quote(symbol_id)).unwrap();
+        assert_eq!(subscription.read::<Quote>(), Ok(Some(quote(symbol_id))));
+    }
+    assert_eq!(ALLOCATIONS.get() - before, 0);
+}
+
+#[test]
+fn test_fixed_width_value_writes_do_not_allocate() {
+    let table = Table::new("quotes", Quote::table_config(64));
+    let symbol_id = table.field_id("symbol_id").unwrap();
+    let bid = table.field_id("bid").unwrap();
+    let ask = table.field_id("ask").unwrap();
+    let write = |id: u32| table.write_values(&[(symbol_id, &id.to_le_bytes()), (bid, &99.5f64.to_le_bytes()), (ask, &100.5f64.to_le_bytes())]);
+    write(0).unwrap();
+    assert_eq!(table.read::<Quote>(), Ok(Some(Quote { symbol_id: 0, bid: 99.5, ask: 100.5 })));
+
+    let before = ALLOCATIONS.get();
+    for id in 0..1_000 {
+        write(id).unwrap();
+        assert_eq!(table.read::<Quote>(), Ok(Some(Quote { symbol_id: id, bid: 99.5, ask: 100.5 })));
+    }
+    assert_eq!(ALLOCATIONS.get() - before, 0);
+}
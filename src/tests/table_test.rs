@@ -0,0 +1,380 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::error::TableError;
+use crate::memory::low_latency_mpmc_ring::{OverflowPolicy, Topology};
+use crate::storage::record::Record;
+use crate::storage::schema::{DataType, Endianness, FieldValue};
+use crate::storage::table::{Table, TableConfig, FieldConfig};
+
+const RING_BUFFER_SIZE: usize = 1024;
+const PRODUCER_COUNT: usize = 4;
+const CONSUMER_COUNT: usize = 4;
+const MESSAGES_PER_PRODUCER: usize = 20_000;
+
+fn market_data_table() -> Table {
+    let mut fields = HashMap::new();
+    let field_types = [
+        ("symbol_id", DataType::U32),
+        ("price", DataType::F64),
+        ("quantity", DataType::U32),
+        ("exchange_id", DataType::U8),
+    ];
+    for &(name, data_type) in field_types.iter() {
+        fields.insert(name, FieldConfig::new(data_type, RING_BUFFER_SIZE));
+    }
+    Table::new("market_data", TableConfig::new(fields))
+}
+
+// Every field is derived from (producer, sequence) so a reader can tell
+// whether the fields it got back were all written by the same record.
+fn encode(p_id: usize, i: usize) -> HashMap<&'static str, Box<[u8]>> {
+    let mut record = HashMap::with_capacity(4);
+    record.insert("symbol_id", Box::from((p_id as u32).to_le_bytes()));
+    record.insert("price", Box::from(((p_id * MESSAGES_PER_PRODUCER + i) as f64).to_le_bytes()));
+    record.insert("quantity", Box::from((i as u32).to_le_bytes()));
+    record.insert("exchange_id", Box::from([p_id as u8]));
+    record
+}
+
+fn decode(record: &Record) -> (usize, usize) {
+    let p_id = record.get_as::<u32>("symbol_id").unwrap() as usize;
+    let i = record.get_as::<u32>("quantity").unwrap() as usize;
+    let price = record.get_as::<f64>("price").unwrap();
+
+    assert_eq!(record.get_as::<u8>("exchange_id").unwrap() as usize, p_id, "exchange_id from another record");
+    assert_eq!(price as usize, p_id * MESSAGES_PER_PRODUCER + i, "price from another record");
+    (p_id, i)
+}
+
+#[test]
+fn test_rows_are_never_interleaved() {
+    let table = Arc::new(market_data_table());
+    let consumed = Arc::new(AtomicUsize::new(0));
+    let total = PRODUCER_COUNT * MESSAGES_PER_PRODUCER;
+
+    let producers: Vec<_> = (0..PRODUCER_COUNT).map(|p_id| {
+        let table = Arc::clone(&table);
+        thread::spawn(move || {
+            for i in 0..MESSAGES_PER_PRODUCER {
+                while table.write_record(encode(p_id, i)).is_err() {
+                    thread::yield_now();
+                }
+            }
+        })
+    }).collect();
+
+    let consumers: Vec<_> = (0..CONSUMER_COUNT).map(|_| {
+        let table = Arc::clone(&table);
+        let consumed = Arc::clone(&consumed);
+        thread::spawn(move || {
+            let mut seen = Vec::new();
+            while consumed.load(Ordering::Relaxed) < total {
+                if let Some(record) = table.read_one_record().unwrap() {
+                    seen.push(decode(&record));
+                    consumed.fetch_add(1, Ordering::Relaxed);
+                } else {
+                    thread::yield_now();
+                }
+            }
+            seen
+        })
+    }).collect();
+
+    for p in producers {
+        p.join().unwrap();
+    }
+
+    let mut all = HashSet::with_capacity(total);
+    for c in consumers {
+        let seen = c.join().unwrap();
+
+        // Each consumer must see a given producer's records in write order
+        let mut last = [None; PRODUCER_COUNT];
+        for &(p_id, i) in &seen {
+            assert!(last[p_id] < Some(i), "producer {} reordered at {}", p_id, i);
+            last[p_id] = Some(i);
+            assert!(all.insert((p_id, i)), "record ({}, {}) read twice", p_id, i);
+        }
+    }
+
+    assert_eq!(all.len(), total);
+    assert_eq!(table.record_count.load(Ordering::SeqCst), 0);
+    assert_eq!(table.read_one_record().unwrap().map(|_| ()), None);
+}
+
+#[test]
+fn test_round_trip_preserves_every_field() {
+    let table = market_data_table();
+    assert_eq!(table.capacity(), RING_BUFFER_SIZE);
+
+    for i in 0..RING_BUFFER_SIZE {
+        table.write_record(encode(1, i)).unwrap();
+    }
+    assert_eq!(table.write_record(encode(1, RING_BUFFER_SIZE)), Err(TableError::Full));
+
+    for i in 0..RING_BUFFER_SIZE {
+        let record = table.read_one_record().unwrap().unwrap();
+        assert_eq!(record.len(), 4);
+        assert_eq!(decode(&record), (1, i));
+    }
+    assert!(table.read_one_record().unwrap().is_none());
+}
+
+#[test]
+fn test_writes_are_validated_against_field_types() {
+    let mut fields = HashMap::new();
+    fields.insert("timestamp", FieldConfig::new(DataType::TimestampNs, 16));
+    fields.insert("is_buy", FieldConfig::new(DataType::Bool, 16));
+    fields.insert("venue", FieldConfig::new(DataType::Utf8, 16).with_max_len(8));
+    let table = Table::new("typed", TableConfig::new(fields));
+
+    let record = |timestamp: Box<[u8]>, is_buy: Box<[u8]>, venue: &[u8]| {
+        let mut record = HashMap::new();
+        record.insert("timestamp", timestamp);
+        record.insert("is_buy", is_buy);
+        record.insert("venue", Box::from(venue));
+        record
+    };
+
+    // A u128 nanosecond timestamp does not fit an 8-byte timestamp_ns field
+    assert_eq!(
+        table.write_record(record(Box::from(1u128.to_le_bytes()), true.encode(Endianness::Little), b"XNAS")),
+        Err(TableError::SizeMismatch { field: "timestamp".into(), expected: 8, actual: 16 })
+    );
+    assert_eq!(
+        table.write_record(record(1u64.encode(Endianness::Little), Box::from([2u8]), b"XNAS")),
+        Err(TableError::TypeMismatch { field: "is_buy".into(), expected: DataType::Bool })
+    );
+    assert_eq!(
+        table.write_record(record(1u64.encode(Endianness::Little), true.encode(Endianness::Little), &[0xff, 0xfe])),
+        Err(TableError::TypeMismatch { field: "venue".into(), expected: DataType::Utf8 })
+    );
+    assert_eq!(
+        table.write_record(record(1u64.encode(Endianness::Little), true.encode(Endianness::Little), b"TOO_LONG_VENUE")),
+        Err(TableError::SizeMismatch { field: "venue".into(), expected: 8, actual: 14 })
+    );
+    assert_eq!(table.record_count.load(Ordering::SeqCst), 0);
+
+    table.write_record(record(42u64.encode(Endianness::Little), true.encode(Endianness::Little), b"XNAS")).unwrap();
+    let record = table.read_one_record().unwrap().unwrap();
+    assert_eq!(record.get_as::<u64>("timestamp"), Ok(42));
+    assert_eq!(record.get_as::<bool>("is_buy"), Ok(true));
+    assert_eq!(record.get_str("venue"), Ok("XNAS"));
+
+    // Typed getters refuse to reinterpret a field as the wrong type
+    assert_eq!(
+        record.get_as::<i64>("timestamp"),
+        Err(TableError::TypeMismatch { field: "timestamp".into(), expected: DataType::TimestampNs })
+    );
+    assert!(record.get_bytes("venue").is_err());
+    assert_eq!(record.get_as::<u64>("missing"), Err(TableError::UnknownField("missing".into())));
+}
+
+#[test]
+fn test_big_endian_fields_round_trip() {
+    let mut fields = HashMap::new();
+    fields.insert("seq_no", FieldConfig::new(DataType::U32, 16).with_endianness(Endianness::Big));
+    let table = Table::new("big_endian", TableConfig::new(fields));
+
+    let mut record = HashMap::new();
+    record.insert("seq_no", 0x0102_0304u32.encode(Endianness::Big));
+    table.write_record(record).unwrap();
+
+    let record = table.read_one_record().unwrap().unwrap();
+    assert_eq!(record.get("seq_no"), Some(&[1, 2, 3, 4][..]));
+    assert_eq!(record.get_as::<u32>("seq_no"), Ok(0x0102_0304));
+}
+
+#[test]
+fn test_schema_errors_are_distinct_from_backpressure() {
+    let table = market_data_table();
+
+    let mut record = encode(1, 1);
+    record.remove("price");
+    assert_eq!(table.write_record(record), Err(TableError::MissingField("price".into())));
+
+    let mut record = encode(1, 1);
+    record.insert("venue", Box::from(&b"XNAS"[..]));
+    assert_eq!(table.write_record(record), Err(TableError::UnknownField("venue".into())));
+
+    let mut record = encode(1, 1);
+    record.insert("quantity", Box::from(7u64.to_le_bytes()));
+    let err = table.write_record(record).unwrap_err();
+    assert!(!err.is_backpressure());
+    assert_eq!(err, TableError::SizeMismatch { field: "quantity".into(), expected: 4, actual: 8 });
+
+    // Nothing was partially written by the rejected records
+    assert_eq!(table.record_count.load(Ordering::SeqCst), 0);
+    assert!(table.read_one_record().unwrap().is_none());
+}
+
+#[test]
+fn test_drop_oldest_table_keeps_the_freshest_rows() {
+    let mut fields = HashMap::new();
+    fields.insert("seq_no", FieldConfig::new(DataType::U64, 8));
+    let config = TableConfig::new(fields).with_overflow_policy(OverflowPolicy::DropOldest);
+    let table = Table::new("ticks", config);
+
+    for i in 0..20u64 {
+        let mut record = HashMap::new();
+        record.insert("seq_no", i.encode(Endianness::Little));
+        table.write_record(record).unwrap();
+    }
+    assert_eq!(table.evicted_count(), 12);
+    assert_eq!(table.record_count.load(Ordering::SeqCst), 8);
+
+    for i in 12..20u64 {
+        let record = table.read_one_record().unwrap().unwrap();
+        assert_eq!(record.get_as::<u64>("seq_no"), Ok(i));
+    }
+    assert_eq!(table.record_count.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn test_batches_round_trip_and_keep_refused_records() {
+    let table = market_data_table();
+    let capacity = table.capacity();
+    let mut batch: Vec<_> = (0..capacity + 10).map(|i| encode(7, i)).collect();
+
+    assert_eq!(table.write_batch(&mut batch), Err(TableError::PartialWrite { written: capacity }));
+    assert_eq!(batch.len(), 10, "refused records are left for a retry");
+    assert_eq!(table.record_count.load(Ordering::SeqCst), capacity);
+
+    let mut records = Vec::new();
+    assert_eq!(table.read_batch(&mut records, 256), Ok(256));
+    table.write_batch(&mut batch).unwrap();
+    assert!(batch.is_empty());
+    while table.read_batch(&mut records, 256).unwrap() > 0 {}
+
+    let order: Vec<usize> = records.iter().map(|record| decode(record).1).collect();
+    assert_eq!(order, (0..capacity + 10).collect::<Vec<_>>());
+    assert_eq!(table.record_count.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn test_a_bad_record_rejects_the_whole_batch() {
+    let table = market_data_table();
+    let mut batch: Vec<_> = (0..5).map(|i| encode(1, i)).collect();
+    batch[3].insert("venue", Box::from(*b"X"));
+
+    assert_eq!(table.write_batch(&mut batch), Err(TableError::UnknownField("venue".to_owned())));
+    assert_eq!(batch.len(), 5);
+    assert_eq!(table.record_count.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn test_fixed_and_variable_width_fields_survive_slot_reuse() {
+    let mut fields = HashMap::new();
+    fields.insert("seq", FieldConfig::new(DataType::U64, 8));
+    fields.insert("timestamp", FieldConfig::new(DataType::TimestampNs, 8));
+    fields.insert("venue", FieldConfig::new(DataType::Utf8, 8));
+    fields.insert("flags", FieldConfig::new(DataType::Bytes(3), 8));
+    let config = TableConfig::new(fields).with_overflow_policy(OverflowPolicy::Overwrite);
+    let table = Table::new("quotes", config);
+
+    let quote = |seq: u64| HashMap::from([
+        ("seq", seq.encode(Endianness::Little)),
+        ("timestamp", (seq * 10).encode(Endianness::Little)),
+        ("venue", Box::from("x".repeat(seq as usize % 5).as_bytes())),
+        ("flags", Box::from([seq as u8, 0, !(seq as u8)])),
+    ]);
+    let check = |record: &Record| {
+        let seq = record.get_as::<u64>("seq").unwrap();
+        assert_eq!(record.get_as::<u64>("timestamp"), Ok(seq * 10));
+        assert_eq!(record.get_str("venue").unwrap(), "x".repeat(seq as usize % 5));
+        assert_eq!(record.get_bytes("flags").unwrap(), [seq as u8, 0, !(seq as u8)]);
+        seq
+    };
+
+    // Several laps around the ring, so every inline slot is rewritten
+    for seq in 0..29 {
+        table.write_record(quote(seq)).unwrap();
+    }
+    let scanned: Vec<u64> = table.scan(230, u64::MAX).unwrap().map(|record| check(&record)).collect();
+    assert_eq!(scanned, vec![23, 24, 25, 26, 27, 28]);
+
+    let mut batch = Vec::new();
+    table.read_batch(&mut batch, 3).unwrap();
+    assert_eq!(batch.iter().map(check).collect::<Vec<_>>(), vec![21, 22, 23]);
+    let rest: Vec<u64> = std::iter::from_fn(|| table.read_one_record().unwrap())
+        .map(|record| check(&record))
+        .collect();
+    assert_eq!(rest, vec![24, 25, 26, 27, 28]);
+}
+
+#[test]
+#[should_panic(expected = "declares field_size_bytes")]
+fn test_fixed_width_field_size_must_match_its_type() {
+    let mut fields = HashMap::new();
+    fields.insert("price", FieldConfig::new(DataType::F64, 8).with_max_len(4));
+    Table::new("prices", TableConfig::new(fields));
+}
+
+#[test]
+fn test_shutdown_refuses_writes_and_lets_readers_finish() {
+    let table = market_data_table();
+    for i in 0..3 {
+        table.write_record(encode(0, i)).unwrap();
+    }
+    table.shutdown().unwrap();
+
+    assert!(table.is_closed());
+    assert_eq!(table.write_record(encode(0, 3)), Err(TableError::Closed));
+    let mut batch = vec![encode(0, 4), encode(0, 5)];
+    assert_eq!(table.write_batch(&mut batch), Err(TableError::Closed));
+    assert_eq!(batch.len(), 2);
+    assert_eq!(table.record_count.load(Ordering::SeqCst), 3);
+
+    let drained: Vec<usize> = table.drain().map(|record| decode(&record).1).collect();
+    assert_eq!(drained, vec![0, 1, 2]);
+    assert_eq!(table.record_count.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn test_single_producer_single_consumer_table_keeps_rows_whole() {
+    const MESSAGES: usize = 5_000;
+    let table_config = || {
+        let mut fields = HashMap::new();
+        for (name, data_type) in [("symbol_id", DataType::U32), ("price", DataType::F64), ("quantity", DataType::U32), ("exchange_id", DataType::U8)] {
+            fields.insert(name, FieldConfig::new(data_type, 64));
+        }
+        TableConfig::new(fields)
+    };
+    // SAFETY: one thread writes and one reads
+    let config = unsafe { table_config().with_topology(Topology::Spsc) };
+    let table = Table::new("feed", config);
+    assert_eq!(table.topology(), Topology::Spsc);
+    // Consumer groups would be a second consumer
+    assert!(matches!(table.subscribe("audit"), Err(TableError::TooManyGroups)));
+    // Nor can a single consumer keep up with evictions
+    for policy in [OverflowPolicy::DropOldest, OverflowPolicy::Overwrite] {
+        // SAFETY: the table is never built
+        let config = unsafe { table_config().with_topology(Topology::Mpsc) }.with_overflow_policy(policy);
+        assert!(matches!(Table::open("feed", config), Err(TableError::InvalidSchema(_))));
+    }
+
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            for i in 0..MESSAGES {
+                while let Err(err) = table.write_record(encode(1, i)) {
+                    assert!(err.is_backpressure());
+                    thread::yield_now();
+                }
+            }
+        });
+        let mut expected = 0;
+        while expected < MESSAGES {
+            match table.read_one_record().unwrap() {
+                Some(record) => {
+                    assert_eq!(decode(&record), (1, expected));
+                    expected += 1;
+                }
+                None => thread::yield_now(),
+            }
+        }
+    });
+    assert_eq!(table.record_count.load(Ordering::SeqCst), 0);
+}
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::clock::ManualClock;
+use crate::error::TableError;
+use crate::storage::schema::{DataType, Endianness, FieldValue};
+use crate::storage::table::{Table, TableConfig, FieldConfig};
+
+fn event_table(clock: Arc<ManualClock>) -> Table {
+    let mut fields = HashMap::new();
+    fields.insert("event_time", FieldConfig::new(DataType::TimestampNs, 16));
+    fields.insert("ingest_time", FieldConfig::new(DataType::TimestampNs, 16).with_endianness(Endianness::Big));
+    fields.insert("value", FieldConfig::new(DataType::I64, 16));
+    let config = TableConfig::new(fields)
+        .with_time_column("event_time")
+        .with_ingest_time_column("ingest_time")
+        .with_clock(clock);
+    Table::new("events", config)
+}
+
+fn value(v: i64) -> HashMap<&'static str, Box<[u8]>> {
+    let mut record = HashMap::new();
+    record.insert("value", v.encode(Endianness::Little));
+    record
+}
+
+#[test]
+fn test_missing_time_column_is_stamped_from_the_clock() {
+    let clock = Arc::new(ManualClock::new(1_000));
+    let table = event_table(clock.clone());
+    assert_eq!(table.time_column(), Some("event_time"));
+    assert_eq!(table.ingest_time_column(), Some("ingest_time"));
+
+    table.write_record(value(1)).unwrap();
+    clock.advance(500);
+    table.write_record(value(2)).unwrap();
+
+    let first = table.read_one_record().unwrap().unwrap();
+    assert_eq!(first.get_as::<u64>("event_time"), Ok(1_000));
+    assert_eq!(first.get_as::<u64>("ingest_time"), Ok(1_000));
+    let second = table.read_one_record().unwrap().unwrap();
+    assert_eq!(second.get_as::<u64>("event_time"), Ok(1_500));
+    assert_eq!(second.get_as::<i64>("value"), Ok(2));
+}
+
+#[test]
+fn test_supplied_event_time_is_kept_and_ingest_time_is_overridden() {
+    let clock = Arc::new(ManualClock::new(9_000));
+    let table = event_table(clock);
+
+    let mut record = value(7);
+    record.insert("event_time", 42u64.encode(Endianness::Little));
+    record.insert("ingest_time", 1u64.encode(Endianness::Big));
+    table.write_record(record).unwrap();
+
+    let record = table.read_one_record().unwrap().unwrap();
+    assert_eq!(record.get_as::<u64>("event_time"), Ok(42));
+    assert_eq!(record.get_as::<u64>("ingest_time"), Ok(9_000));
+}
+
+#[test]
+fn test_batches_stamp_time_columns_as_single_writes_do() {
+    let clock = Arc::new(ManualClock::new(5_000));
+    let table = event_table(clock);
+    let record = || {
+        let mut record = value(3);
+        record.insert("event_time", Box::default());
+        record.insert("ingest_time", Box::from([1u8]));
+        record
+    };
+
+    table.write_record(record()).unwrap();
+    table.write_batch(&mut vec![record()]).unwrap();
+    for _ in 0..2 {
+        let record = table.read_one_record().unwrap().unwrap();
+        assert_eq!(record.get_as::<u64>("event_time"), Ok(5_000));
+        assert_eq!(record.get_as::<u64>("ingest_time"), Ok(5_000));
+    }
+}
+
+#[test]
+fn test_stamped_rows_are_scannable_by_time() {
+    let clock = Arc::new(ManualClock::new(0));
+    let table = event_table(clock.clone());
+    for v in 0..10 {
+        table.write_record(value(v)).unwrap();
+        clock.advance(10);
+    }
+
+    let values: Vec<i64> = table.scan(20, 50).unwrap()
+        .map(|record| record.get_as::<i64>("value").unwrap())
+        .collect();
+    assert_eq!(values, vec![2, 3, 4]);
+}
+
+#[test]
+fn test_other_fields_are_still_required() {
+    let table = event_table(Arc::new(ManualClock::new(0)));
+    assert_eq!(
+        table.write_record(HashMap::<&str, Box<[u8]>>::new()),
+        Err(TableError::MissingField("value".to_owned()))
+    );
+}
+
+#[test]
+#[should_panic(expected = "must be a timestamp_ns field")]
+fn test_time_column_must_be_a_timestamp() {
+    let mut fields = HashMap::new();
+    fields.insert("value", FieldConfig::new(DataType::I64, 16));
+    Table::new("bad", TableConfig::new(fields).with_time_column("value"));
+}
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::TableError;
+use crate::memory::low_latency_mpmc_ring::{LowLatencyMpmcRing, OverflowPolicy};
+use crate::memory::wait_strategy::{BackoffPark, Blocking, BusySpin, SpinThenYield, WaitStrategy};
+use crate::storage::schema::{DataType, Endianness, FieldValue};
+use crate::storage::table::{Table, TableConfig, FieldConfig};
+
+fn strategies() -> Vec<(&'static str, Arc<dyn WaitStrategy>)> {
+    vec![
+        ("busy_spin", Arc::new(BusySpin)),
+        ("spin_then_yield", Arc::new(SpinThenYield::default())),
+        ("backoff_park", Arc::new(BackoffPark::default())),
+        ("blocking", Arc::new(Blocking::new())),
+    ]
+}
+
+#[test]
+fn test_timed_calls_give_up_at_the_deadline() {
+    for (name, wait) in strategies() {
+        let ring = LowLatencyMpmcRing::new(2).with_wait_strategy(wait);
+        let started = Instant::now();
+        assert_eq!(ring.dequeue_timeout(Duration::from_millis(20)), Ok(None), "{}", name);
+        assert!(started.elapsed() >= Duration::from_millis(20), "{}", name);
+
+        ring.enqueue(1u64).unwrap();
+        ring.enqueue(2).unwrap();
+        let started = Instant::now();
+        assert_eq!(ring.enqueue_timeout(3, Duration::from_millis(20)), Err(TableError::Full), "{}", name);
+        assert!(started.elapsed() >= Duration::from_millis(20), "{}", name);
+    }
+}
+
+#[test]
+fn test_waiters_wake_when_the_ring_changes() {
+    for (name, wait) in strategies() {
+        let ring = Arc::new(LowLatencyMpmcRing::new(2).with_wait_strategy(wait));
+
+        let consumer = {
+            let ring = Arc::clone(&ring);
+            thread::spawn(move || ring.dequeue_timeout(Duration::from_secs(10)))
+        };
+        thread::sleep(Duration::from_millis(10));
+        ring.enqueue(7u64).unwrap();
+        assert_eq!(consumer.join().unwrap(), Ok(Some(7)), "{}", name);
+
+        ring.enqueue(8).unwrap();
+        ring.enqueue(9).unwrap();
+        let producer = {
+            let ring = Arc::clone(&ring);
+            thread::spawn(move || ring.enqueue_timeout(10, Duration::from_secs(10)))
+        };
+        thread::sleep(Duration::from_millis(10));
+        assert_eq!(ring.try_dequeue(), Ok(Some(8)), "{}", name);
+        assert_eq!(producer.join().unwrap(), Ok(()), "{}", name);
+        assert_eq!(ring.drain().collect::<Vec<_>>(), vec![9, 10], "{}", name);
+    }
+}
+
+#[test]
+fn test_blocked_producers_and_consumers_see_every_item() {
+    const ITEMS: u64 = 2_000;
+    for (name, wait) in strategies() {
+        let ring = Arc::new(
+            LowLatencyMpmcRing::with_policy(8, OverflowPolicy::Block { timeout: None }).with_wait_strategy(wait)
+        );
+        let producers: Vec<_> = (0..2).map(|p| {
+            let ring = Arc::clone(&ring);
+            thread::spawn(move || {
+                for i in (p..ITEMS).step_by(2) {
+                    ring.enqueue(i).unwrap();
+                }
+            })
+        }).collect();
+        let consumers: Vec<_> = (0..2).map(|_| {
+            let ring = Arc::clone(&ring);
+            thread::spawn(move || {
+                let mut sum = 0;
+                while let Ok(Some(item)) = ring.dequeue_timeout_with(None, |_, item| item) {
+                    sum += item;
+                }
+                sum
+            })
+        }).collect();
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        ring.close();
+        let sum: u64 = consumers.into_iter().map(|c| c.join().unwrap()).sum();
+        assert_eq!(sum, ITEMS * (ITEMS - 1) / 2, "{}", name);
+    }
+}
+
+#[test]
+fn test_read_blocking_waits_for_a_row_and_ends_at_shutdown() {
+    let mut fields = HashMap::new();
+    fields.insert("price", FieldConfig::new(DataType::F64, 16));
+    let config = TableConfig::new(fields).with_wait_strategy(Arc::new(Blocking::new()));
+    let table = Arc::new(Table::new("prices", config));
+
+    let reader = {
+        let table = Arc::clone(&table);
+        thread::spawn(move || {
+            let mut prices = Vec::new();
+            loop {
+                match table.read_blocking(None) {
+                    Ok(Some(record)) => prices.push(record.get_as::<f64>("price").unwrap()),
+                    Ok(None) => unreachable!("no timeout was given"),
+                    Err(err) => return (prices, err),
+                }
+            }
+        })
+    };
+    for price in [1.5, 2.5] {
+        thread::sleep(Duration::from_millis(10));
+        table.write_record(HashMap::from([("price", price.encode(Endianness::Little))])).unwrap();
+    }
+    thread::sleep(Duration::from_millis(10));
+    table.shutdown().unwrap();
+
+    assert_eq!(reader.join().unwrap(), (vec![1.5, 2.5], TableError::Closed));
+    assert_eq!(table.read_blocking(Some(Duration::from_millis(1))).map(|r| r.is_some()), Err(TableError::Closed));
+}
@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use super::{drain_ids, temp_dir, u64_fields, u64_record};
+use crate::error::TableError;
+use crate::memory::low_latency_mpmc_ring::OverflowPolicy;
+use crate::storage::schema::DataType;
+use crate::storage::table::{Table, TableConfig, FieldConfig};
+use crate::storage::wal::{SyncPolicy, WalConfig};
+
+fn order_config(wal: WalConfig) -> TableConfig {
+    let mut fields = u64_fields("order_id", 128);
+    fields.insert("venue", FieldConfig::new(DataType::Utf8, 128));
+    TableConfig::new(fields).with_wal(wal)
+}
+
+fn order(order_id: u64) -> HashMap<&'static str, Box<[u8]>> {
+    let mut record = u64_record("order_id", order_id);
+    record.insert("venue", Box::from(format!("venue-{}", order_id % 3).as_bytes()));
+    record
+}
+
+fn segments(dir: &PathBuf) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn test_reopening_restores_unread_rows() {
+    let dir = temp_dir("wal-reopen");
+    {
+        let table = Table::open("orders", order_config(WalConfig::new(&dir))).unwrap();
+        for order_id in 0..10 {
+            table.write_record(order(order_id)).unwrap();
+        }
+        assert_eq!(drain_ids(|| table.read_one_record(), "order_id")[..], [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        table.write_record(order(10)).unwrap();
+        table.write_record(order(11)).unwrap();
+    }
+
+    let table = Table::open("orders", order_config(WalConfig::new(&dir))).unwrap();
+    assert_eq!(table.record_count.load(std::sync::atomic::Ordering::SeqCst), 2);
+    table.write_record(order(12)).unwrap();
+    assert_eq!(drain_ids(|| table.read_one_record(), "order_id"), vec![10, 11, 12]);
+    drop(table);
+
+    // Rows read after the last write are replayed again
+    let table = Table::open("orders", order_config(WalConfig::new(&dir))).unwrap();
+    assert_eq!(drain_ids(|| table.read_one_record(), "order_id"), vec![10, 11, 12]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_replay_keeps_sequence_numbers_for_scans_and_overwrites() {
+    let dir = temp_dir("wal-sequence");
+    let config = |dir: &PathBuf| {
+        let mut fields = HashMap::new();
+        fields.insert("timestamp", FieldConfig::new(DataType::TimestampNs, 4));
+        TableConfig::new(fields)
+            .with_overflow_policy(OverflowPolicy::Overwrite)
+            .with_wal(WalConfig::new(dir).with_sync(SyncPolicy::Never))
+    };
+    let stamp = |ts| u64_record("timestamp", ts);
+    {
+        let table = Table::open("ticks", config(&dir)).unwrap();
+        for ts in 0..10 {
+            table.write_record(stamp(ts)).unwrap();
+        }
+    }
+
+    let table = Table::open("ticks", config(&dir)).unwrap();
+    let retained: Vec<u64> = table.scan(0, u64::MAX).unwrap()
+        .map(|record| record.get_as::<u64>("timestamp").unwrap())
+        .collect();
+    assert_eq!(retained, vec![6, 7, 8, 9]);
+    // The ring continues where it left off, so overwrites still evict the oldest row
+    table.write_record(stamp(10)).unwrap();
+    assert_eq!(table.evicted_count(), 1);
+    assert_eq!(table.read_one_record().unwrap().unwrap().get_as::<u64>("timestamp"), Ok(7));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_torn_tail_is_cut_off() {
+    let dir = temp_dir("wal-torn");
+    {
+        let table = Table::open("orders", order_config(WalConfig::new(&dir))).unwrap();
+        for order_id in 0..5 {
+            table.write_record(order(order_id)).unwrap();
+        }
+    }
+    let last = segments(&dir).pop().unwrap();
+    OpenOptions::new().append(true).open(&last).unwrap()
+        .write_all(&[42, 0, 0, 0, 1, 2, 3]).unwrap();
+
+    {
+        let table = Table::open("orders", order_config(WalConfig::new(&dir))).unwrap();
+        assert_eq!(table.record_count.load(std::sync::atomic::Ordering::SeqCst), 5);
+        table.write_record(order(5)).unwrap();
+    }
+    let table = Table::open("orders", order_config(WalConfig::new(&dir))).unwrap();
+    assert_eq!(drain_ids(|| table.read_one_record(), "order_id"), vec![0, 1, 2, 3, 4, 5]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_segments_rotate_and_damage_in_a_sealed_one_is_reported() {
+    let dir = temp_dir("wal-segments");
+    let wal = || WalConfig::new(&dir)
+        .with_segment_bytes(256)
+        .with_sync(SyncPolicy::GroupCommit(Duration::from_millis(5)));
+    {
+        let table = Table::open("orders", order_config(wal())).unwrap();
+        for order_id in 0..100 {
+            table.write_record(order(order_id)).unwrap();
+        }
+        table.sync_wal().unwrap();
+    }
+    let paths = segments(&dir);
+    assert!(paths.len() > 1, "expected several segments, got {}", paths.len());
+
+    let table = Table::open("orders", order_config(wal())).unwrap();
+    assert_eq!(drain_ids(|| table.read_one_record(), "order_id"), (0..100).collect::<Vec<_>>());
+    drop(table);
+
+    let mut bytes = fs::read(&paths[0]).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xFF;
+    fs::write(&paths[0], bytes).unwrap();
+    assert!(matches!(Table::open("orders", order_config(wal())), Err(TableError::Corrupt(_))));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_sealed_segments_are_deleted_once_their_rows_are_read() {
+    let dir = temp_dir("wal-compaction");
+    let wal = || WalConfig::new(&dir).with_segment_bytes(256).with_sync(SyncPolicy::Never);
+    {
+        let table = Table::open("orders", order_config(wal())).unwrap();
+        for order_id in 0..100 {
+            table.write_record(order(order_id)).unwrap();
+        }
+        assert!(segments(&dir).len() > 1);
+        assert_eq!(drain_ids(|| table.read_one_record(), "order_id").len(), 100);
+        // The next write carries the read watermark to the log
+        table.write_record(order(100)).unwrap();
+        assert_eq!(segments(&dir).len(), 1);
+    }
+
+    let table = Table::open("orders", order_config(wal())).unwrap();
+    assert_eq!(drain_ids(|| table.read_one_record(), "order_id"), [100]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_log_written_with_another_schema_is_rejected() {
+    let dir = temp_dir("wal-schema");
+    {
+        let table = Table::open("orders", order_config(WalConfig::new(&dir))).unwrap();
+        table.write_record(order(1)).unwrap();
+    }
+
+    let mut fields = HashMap::new();
+    fields.insert("order_id", FieldConfig::new(DataType::U32, 128));
+    let config = TableConfig::new(fields).with_wal(WalConfig::new(&dir));
+    assert!(matches!(Table::open("orders", config), Err(TableError::Corrupt(_))));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_checksum_matches_the_standard_crc32() {
+    assert_eq!(crate::storage::checksum::crc32(b"123456789"), 0xCBF4_3926);
+    assert_eq!(crate::storage::checksum::crc32(b""), 0);
+}
+
+#[test]
+fn test_batches_are_logged_with_consecutive_sequences() {
+    let dir = temp_dir("wal-batch");
+    {
+        let table = Table::open("orders", order_config(WalConfig::new(&dir))).unwrap();
+        table.write_record(order(0)).unwrap();
+        let mut batch: Vec<_> = (1..50).map(order).collect();
+        table.write_batch(&mut batch).unwrap();
+        table.write_record(order(50)).unwrap();
+    }
+
+    let table = Table::open("orders", order_config(WalConfig::new(&dir))).unwrap();
+    assert_eq!(drain_ids(|| table.read_one_record(), "order_id"), (0..51).collect::<Vec<_>>());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_refused_writes_are_not_logged() {
+    let dir = temp_dir("wal-refused");
+    let config = || {
+        let mut fields = u64_fields("order_id", 2);
+        fields.insert("venue", FieldConfig::new(DataType::Utf8, 2));
+        TableConfig::new(fields).with_wal(WalConfig::new(&dir))
+    };
+    {
+        let table = Table::open("orders", config()).unwrap();
+        table.write_record(order(0)).unwrap();
+        table.write_record(order(1)).unwrap();
+        assert_eq!(table.write_record(order(99)), Err(TableError::Full));
+        assert_eq!(drain_ids(|| table.read_one_record(), "order_id"), vec![0, 1]);
+        table.write_record(order(2)).unwrap();
+        table.shutdown().unwrap();
+        assert_eq!(table.write_record(order(98)), Err(TableError::Closed));
+    }
+
+    let table = Table::open("orders", config()).unwrap();
+    assert_eq!(drain_ids(|| table.read_one_record(), "order_id"), vec![2]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_short_write_is_cut_off_and_written_again() {
+    let dir = temp_dir("wal-short-write");
+    let wal = || WalConfig::new(&dir).with_segment_bytes(256);
+    {
+        let table = Table::open("orders", order_config(wal())).unwrap();
+        for order_id in 0..3 {
+            table.write_record(order(order_id)).unwrap();
+        }
+        crate::storage::wal::SHORT_WRITE.set(Some(10));
+        assert!(matches!(table.write_record(order(3)), Err(TableError::Io(_))));
+        // The torn bytes are gone, and the failed entry goes out ahead of the next
+        for order_id in 4..20 {
+            table.write_record(order(order_id)).unwrap();
+        }
+    }
+    assert!(segments(&dir).len() > 1);
+
+    let table = Table::open("orders", order_config(wal())).unwrap();
+    assert_eq!(drain_ids(|| table.read_one_record(), "order_id"), (0..20).collect::<Vec<_>>());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_blocked_writers_wait_with_the_log_unlocked() {
+    let dir = temp_dir("wal-blocked");
+    let config = || {
+        let mut fields = u64_fields("order_id", 2);
+        fields.insert("venue", FieldConfig::new(DataType::Utf8, 2));
+        TableConfig::new(fields)
+            .with_wal(WalConfig::new(&dir))
+            .with_overflow_policy(OverflowPolicy::Block { timeout: None })
+    };
+    {
+        let table = Table::open("orders", config()).unwrap();
+        table.write_record(order(0)).unwrap();
+        table.write_record(order(1)).unwrap();
+        std::thread::scope(|scope| {
+            let single = scope.spawn(|| table.write_record(order(2)));
+            let batch = scope.spawn(|| table.write_batch(&mut vec![order(3), order(4)]));
+            std::thread::sleep(Duration::from_millis(50));
+            // Neither blocked writer holds the log while the table is full
+            table.sync_wal().unwrap();
+            assert!(!single.is_finished() && !batch.is_finished());
+
+            let mut ids = drain_ids(|| table.read_one_record(), "order_id");
+            while ids.len() < 5 {
+                ids.extend(drain_ids(|| table.read_one_record(), "order_id"));
+            }
+            single.join().unwrap().unwrap();
+            batch.join().unwrap().unwrap();
+            ids.sort();
+            assert_eq!(ids, vec![0, 1, 2, 3, 4]);
+        });
+        table.write_record(order(5)).unwrap();
+    }
+
+    let table = Table::open("orders", config()).unwrap();
+    assert_eq!(drain_ids(|| table.read_one_record(), "order_id"), vec![5]);
+    fs::remove_dir_all(&dir).unwrap();
+}